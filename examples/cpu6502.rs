@@ -1,18 +1,18 @@
 use std::path::Path;
-use graph::{Graph, ID};
+use graph::{Graph, Result};
 
-fn main() {
-    cpu_6502_diagram();
+fn main() -> Result<()> {
+    cpu_6502_diagram()
 }
 
-fn cpu_6502_diagram() {
+fn cpu_6502_diagram() -> Result<()> {
     let mut g = Graph::new()
         .with_property("rankdir", "TB")
         .with_property("nodesep", "0.5")
         .with_property("layout", "fdp");
 
     let cpu = g.new_record("CPU_6502", 5, 20)
-        .insert(2, 0, "6502\\nCPU")
+        .insert(2, 0, "6502\nCPU")
         .insert(1, 0, "1")
         .insert(1, 1, "2")
         .insert(1, 2, "3")
@@ -115,6 +115,6 @@ fn cpu_6502_diagram() {
             .finalize();
     }
 
-    g.save_svg(&Path::new("cpu_6502.svg"));
+    g.save_svg(Path::new("cpu_6502.svg"))
 }
 
@@ -1,12 +1,12 @@
 use std::path::Path;
-use graph::{Graph, ID};
+use graph::{Graph, Result};
 
-fn main() {
-    dfa_diagram();
+fn main() -> Result<()> {
+    dfa_diagram()
 }
 
 
-fn dfa_diagram() {
+fn dfa_diagram() -> Result<()> {
     let mut g = Graph::new()
         .with_property("rankdir", "LR");
 
@@ -44,5 +44,5 @@ fn dfa_diagram() {
         .finalize();
 
     // Build the SVG
-    g.save_svg(&Path::new("dfa_diagram.svg"));
+    g.save_svg(Path::new("dfa_diagram.svg"))
 }
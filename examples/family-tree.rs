@@ -1,16 +1,17 @@
 use std::path::Path;
-use graph::{Graph, ID};
+use graph::{Graph, Properties, ID, Result};
 
-fn main() {
-    build_family_tree();
+fn main() -> Result<()> {
+    build_family_tree()
 }
 
-fn build_family_tree() {
+fn build_family_tree() -> Result<()> {
     let mut g = Graph::new()
         .with_property("rankdir", "TB")
         .with_property("splines", "ortho")
         .with_property("nodesep", "1.25")
-        .with_property("ranksep", "1.25");
+        .with_property("ranksep", "1.25")
+        .with_node_defaults(Properties::from_iter([("style", "filled"), ("shape", "box")]));
 
     // Read the Tree.json file
     let tree = std::fs::read_to_string("assets/tree.json").unwrap();
@@ -24,7 +25,6 @@ fn build_family_tree() {
         gender: String,
         alt_names: Vec<String>,
         category: String,
-        type_: String,
     }
 
     // Read the JSON object into a Vec<Person>
@@ -40,9 +40,8 @@ fn build_family_tree() {
             node["alt_names"].as_array().unwrap().iter().map(|v| v.as_str().unwrap().to_string()).collect()
         };
         let category = node["category"].as_str().unwrap().to_string();
-        let type_ = node["type"].as_str().unwrap_or("").to_string();
         let gender = node["gender"].as_str().unwrap().to_string();
-        people.push(Person { id, gender, parent_id, name, alt_names, category, type_ });
+        people.push(Person { id, gender, parent_id, name, alt_names, category });
     }
 
     // Create a node for each person
@@ -55,17 +54,15 @@ fn build_family_tree() {
             "prophet" => "📖",
             _ => "👤",
         };
-        let alt_names_label = if person.alt_names.len() > 0 {
+        let alt_names_label = if !person.alt_names.is_empty() {
             format!("({})", person.alt_names.join(", "))
         } else {
             "".to_string()
         };
         let label = format!("{} {} {}", person.name, category, alt_names_label);
     
-        let node = g.new_node(person.id)
+        g.new_node(person.id)
             .with_property("label", label)
-            .with_property("style", "filled")
-            .with_property("shape", "box")
             .with_property("fillcolor", if person.gender == "male" { "lightblue" } else { "pink" })
             .finalize();
     }
@@ -80,5 +77,5 @@ fn build_family_tree() {
 
     println!("{}", g);
 
-    g.save_svg(&Path::new("family_tree.svg"));
+    g.save_svg(Path::new("family_tree.svg"))
 }
\ No newline at end of file
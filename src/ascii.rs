@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use super::{Graph, LayeredLayout, Node, Point, Record, ID};
+use super::layout::{node_label, text, Units};
+
+/// Drawings of acyclic graphs wider than this many columns fall back to an indented tree.
+const MAX_WIDTH: usize = 120;
+
+// The directions a line leaves a cell in, combined into box-drawing characters where lines meet
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+fn line_char(mask: u8) -> char {
+    match mask {
+        0 => ' ',
+        1..=3 => '│',
+        4 | 8 | 12 => '─',
+        6 => '┐',
+        10 => '┌',
+        5 => '┘',
+        9 => '└',
+        7 => '┤',
+        11 => '├',
+        14 => '┬',
+        13 => '┴',
+        _ => '┼',
+    }
+}
+
+/// The direction from one cell to a neighbouring cell.
+fn step(from: (i64, i64), to: (i64, i64)) -> u8 {
+    match (to.0 - from.0, to.1 - from.1) {
+        (0, dy) if dy < 0 => UP,
+        (0, _) => DOWN,
+        (dx, _) if dx < 0 => LEFT,
+        _ => RIGHT,
+    }
+}
+
+/// A grid of characters with separate layers for text, box outlines and edges.
+struct Canvas {
+    width: i64,
+    height: i64,
+    text: Vec<Option<char>>,
+    boxes: Vec<u8>,
+    lines: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: i64, height: i64) -> Self {
+        let size = (width * height) as usize;
+        Canvas { width, height, text: vec![None; size], boxes: vec![0; size], lines: vec![0; size] }
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            None
+        } else {
+            Some((y * self.width + x) as usize)
+        }
+    }
+
+    /// Whether nothing but edges has been drawn in a cell.
+    fn is_free(&self, x: i64, y: i64) -> bool {
+        self.index(x, y).is_some_and(|i| self.text[i].is_none() && self.boxes[i] == 0)
+    }
+
+    fn put(&mut self, x: i64, y: i64, c: char) {
+        if let Some(i) = self.index(x, y) {
+            self.text[i] = Some(c);
+        }
+    }
+
+    fn write(&mut self, x: i64, y: i64, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            self.put(x + i as i64, y, c);
+        }
+    }
+
+    /// Write text where it doesn't cover anything else, returning whether it fit.
+    fn write_free(&mut self, x: i64, y: i64, text: &str) -> bool {
+        let length = text.chars().count() as i64;
+        if (x - 1..x + length + 1).all(|x| self.is_free(x, y) && self.index(x, y).is_some_and(|i| self.lines[i] == 0)) {
+            self.write(x, y, text);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn outline(&mut self, x: i64, y: i64, mask: u8) {
+        if let Some(i) = self.index(x, y) {
+            self.boxes[i] |= mask;
+        }
+    }
+
+    fn horizontal(&mut self, y: i64, left: i64, right: i64) {
+        for x in left..=right {
+            let mask = if x > left { LEFT } else { 0 } | if x < right { RIGHT } else { 0 };
+            self.outline(x, y, mask);
+        }
+    }
+
+    fn vertical(&mut self, x: i64, top: i64, bottom: i64) {
+        for y in top..=bottom {
+            let mask = if y > top { UP } else { 0 } | if y < bottom { DOWN } else { 0 };
+            self.outline(x, y, mask);
+        }
+    }
+
+    fn rectangle(&mut self, b: &CharBox) {
+        self.horizontal(b.top, b.left, b.right);
+        self.horizontal(b.bottom, b.left, b.right);
+        self.vertical(b.left, b.top, b.bottom);
+        self.vertical(b.right, b.top, b.bottom);
+    }
+
+    /// Draw an edge through a path of neighbouring cells, skipping cells covered by boxes or text.
+    fn path(&mut self, cells: &[(i64, i64)], start: u8, end: u8) {
+        for (i, &cell) in cells.iter().enumerate() {
+            let mut mask = 0;
+            if i > 0 {
+                mask |= step(cell, cells[i - 1]);
+            } else {
+                mask |= start;
+            }
+            if i + 1 < cells.len() {
+                mask |= step(cell, cells[i + 1]);
+            } else {
+                mask |= end;
+            }
+            if self.is_free(cell.0, cell.1) {
+                let index = self.index(cell.0, cell.1).unwrap();
+                self.lines[index] |= mask;
+            }
+        }
+    }
+
+    /// Turn the drawing into text, trimming the empty space around it.
+    fn render(&self) -> String {
+        let rows: Vec<String> = (0..self.height).map(|y| {
+            let row: String = (0..self.width).map(|x| {
+                let i = self.index(x, y).unwrap();
+                self.text[i].unwrap_or_else(|| line_char(if self.boxes[i] != 0 { self.boxes[i] } else { self.lines[i] }))
+            }).collect();
+            row.trim_end().to_string()
+        }).collect();
+        let first = rows.iter().position(|row| !row.is_empty()).unwrap_or(rows.len());
+        let last = rows.iter().rposition(|row| !row.is_empty()).map_or(first, |last| last + 1);
+        let rows = &rows[first..last];
+        let indent = rows.iter().filter(|row| !row.is_empty()).map(|row| row.chars().take_while(|&c| c == ' ').count()).min().unwrap_or(0);
+        let mut text = String::new();
+        for row in rows {
+            text.extend(row.chars().skip(indent));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// A box on the character grid, with inclusive bounds.
+#[derive(Clone, Copy)]
+struct CharBox {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+impl CharBox {
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+    }
+
+    /// Where an edge from `anchor` towards `towards` leaves the box: the cell just outside it,
+    /// and the direction from that cell back into the box. Edges leave through the top or bottom
+    /// when `vertical` is set and through the sides otherwise, unless `towards` is level with the
+    /// box, along the line between the two points or straight out when `straight` is set.
+    fn exit(&self, anchor: Point, towards: Point, vertical: bool, straight: bool) -> ((i64, i64), u8) {
+        let (dx, dy) = (towards.x - anchor.x, towards.y - anchor.y);
+        let along = |target: f64, from: f64, delta: f64, other_from: f64, other_delta: f64| {
+            if straight || delta.abs() < f64::EPSILON {
+                other_from
+            } else {
+                other_from + other_delta * (target - from) / delta
+            }
+        };
+        let clamp_x = |x: f64| (x.round() as i64).clamp(self.left + 1, (self.right - 1).max(self.left + 1));
+        let clamp_y = |y: f64| (y.round() as i64).clamp(self.top + 1, (self.bottom - 1).max(self.top + 1));
+        // Leave towards the next rank when it is past the box, and sideways otherwise
+        let beyond_y = towards.y > self.bottom as f64 + 0.5 || towards.y < self.top as f64 - 0.5;
+        let beyond_x = towards.x > self.right as f64 + 0.5 || towards.x < self.left as f64 - 0.5;
+        if if vertical { beyond_y || !beyond_x } else { beyond_y && !beyond_x } {
+            let y = if dy > 0.0 { self.bottom + 1 } else { self.top - 1 };
+            ((clamp_x(along(y as f64, anchor.y, dy, anchor.x, dx)), y), if dy > 0.0 { UP } else { DOWN })
+        } else {
+            let x = if dx > 0.0 { self.right + 1 } else { self.left - 1 };
+            ((x, clamp_y(along(x as f64, anchor.x, dx, anchor.y, dy))), if dx > 0.0 { LEFT } else { RIGHT })
+        }
+    }
+}
+
+/// The fields in each column of a record, as lines of text.
+fn record_columns(record: &Record) -> Vec<Vec<(Option<Node>, Vec<String>)>> {
+    record.nodes().columns().map(|column| {
+        let cells: Vec<_> = column.cells().map(|(node, label)| (Some(node), label.split('\n').map(String::from).collect())).collect();
+        if cells.is_empty() {
+            vec![(None, vec![String::new()])]
+        } else {
+            cells
+        }
+    }).collect()
+}
+
+/// The width of a column of a record, without its borders.
+fn column_width(cells: &[(Option<Node>, Vec<String>)]) -> i64 {
+    cells.iter().flat_map(|(_, lines)| lines.iter().map(|line| line.chars().count())).max().unwrap_or(0) as i64 + 2
+}
+
+/// The height of a column of a record, without its outer borders.
+fn column_height(cells: &[(Option<Node>, Vec<String>)]) -> i64 {
+    cells.iter().map(|(_, lines)| lines.len() as i64).sum::<i64>() + cells.len() as i64 - 1
+}
+
+/// The size of a record's box in characters.
+fn record_size(record: &Record) -> (f64, f64) {
+    let columns = record_columns(record);
+    let width: i64 = columns.iter().map(|cells| column_width(cells)).sum::<i64>() + columns.len() as i64 + 1;
+    let height = columns.iter().map(|cells| column_height(cells)).max().unwrap_or(1) + 2;
+    (width.max(3) as f64, height.max(3) as f64)
+}
+
+/// Draw a record, returning the center of each of its fields.
+fn draw_record(canvas: &mut Canvas, record: &Record, b: &CharBox) -> HashMap<Node, (i64, i64)> {
+    let mut ports = HashMap::new();
+    canvas.rectangle(b);
+    let mut left = b.left;
+    for (i, cells) in record_columns(record).iter().enumerate() {
+        let right = left + column_width(cells) + 1;
+        if i > 0 {
+            canvas.vertical(left, b.top, b.bottom);
+        }
+        let mut top = b.top;
+        for (j, (port, lines)) in cells.iter().enumerate() {
+            // The last field stretches to the bottom of the record
+            let bottom = if j + 1 == cells.len() { b.bottom } else { top + lines.len() as i64 + 1 };
+            if j > 0 {
+                canvas.horizontal(top, left, right);
+            }
+            for (k, line) in lines.iter().enumerate() {
+                let x = left + 1 + (right - left - 1 - line.chars().count() as i64) / 2;
+                canvas.write(x, top + 1 + k as i64, line);
+            }
+            if let Some(port) = port {
+                ports.insert(*port, ((left + right) / 2, (top + bottom) / 2));
+            }
+            top = bottom;
+        }
+        left = right;
+    }
+    ports
+}
+
+/// Add the cells along an orthogonal path to another cell, starting and ending either
+/// vertically or horizontally, and turning halfway when both ends go the same way.
+fn orthogonal(to: (i64, i64), start_vertical: bool, end_vertical: bool, cells: &mut Vec<(i64, i64)>) {
+    let walk = |target: (i64, i64), cells: &mut Vec<(i64, i64)>| {
+        let mut current = *cells.last().unwrap();
+        while current != target {
+            if current.0 != target.0 {
+                current.0 += (target.0 - current.0).signum();
+            } else {
+                current.1 += (target.1 - current.1).signum();
+            }
+            cells.push(current);
+        }
+    };
+    let from = *cells.last().unwrap();
+    match (start_vertical, end_vertical) {
+        (true, true) => {
+            let middle = (from.1 + to.1) / 2;
+            walk((from.0, middle), cells);
+            walk((to.0, middle), cells);
+        }
+        (false, false) => {
+            let middle = (from.0 + to.0) / 2;
+            walk((middle, from.1), cells);
+            walk((middle, to.1), cells);
+        }
+        (true, false) => walk((from.0, to.1), cells),
+        (false, true) => walk((to.0, from.1), cells),
+    }
+    walk(to, cells);
+}
+
+impl Graph {
+    /// Draw the graph as text with Unicode box-drawing characters, for terminals and logs.
+    ///
+    /// Nodes are drawn as boxes arranged by the layered layout, and records as boxes divided into
+    /// the columns of their grid. Acyclic graphs whose drawing would be too wide to read are drawn
+    /// as an indented tree instead, like [`Graph::to_ascii_tree`].
+    pub fn to_ascii(&self) -> String {
+        let drawing = self.to_ascii_boxes();
+        let width = drawing.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        if width > MAX_WIDTH {
+            if let Some(tree) = self.to_ascii_tree() {
+                return tree;
+            }
+        }
+        drawing
+    }
+
+    fn to_ascii_boxes(&self) -> String {
+        let layered = LayeredLayout::for_graph(self);
+        let horizontal = layered.rank_dir().is_horizontal();
+        let layered = layered.with_node_sep(2.0).with_rank_sep(if horizontal { 6.0 } else { 3.0 });
+
+        // Measure everything in characters instead of points
+        let records: HashMap<ID, &Record> = self.sorted_records().into_iter().map(|record| (record.id(), record)).collect();
+        let mut units = Units::new(self, horizontal);
+        let mut labels = Vec::with_capacity(units.len());
+        for unit in 0..units.len() {
+            let id = units.ids[unit];
+            if let Some(record) = records.get(&id) {
+                units.sizes[unit] = record_size(record);
+                labels.push(Vec::new());
+            } else {
+                let label = node_label(&format!("Node_{}", id), &self.resolved_node_properties(id));
+                let lines: Vec<String> = label.split('\n').map(String::from).collect();
+                let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+                units.sizes[unit] = (width as f64 + 4.0, lines.len() as f64 + 2.0);
+                labels.push(lines);
+            }
+        }
+        let (centers, bends) = layered.place(self, &units);
+
+        // Move everything onto the canvas, leaving room around it for loops
+        let corners = (0..units.len()).flat_map(|unit| {
+            let (width, height) = units.sizes[unit];
+            [(centers[unit].x - width / 2.0, centers[unit].y - height / 2.0), (centers[unit].x + width / 2.0, centers[unit].y + height / 2.0)]
+        });
+        let points = corners.chain(bends.iter().flatten().map(|p| (p.x, p.y)));
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+        for (i, (x, y)) in points.enumerate() {
+            if i == 0 {
+                (min_x, min_y, max_x, max_y) = (x, y, x, y);
+            }
+            (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+        }
+        let shift = |p: Point| Point::new(p.x - min_x + 4.0, p.y - min_y + 4.0);
+        let widest_label = units.edges.iter()
+            .filter_map(|(edge, _, _)| text(&self.resolved_edge_properties(edge.id()), "label"))
+            .map(|label| label.chars().count() as i64 + 3)
+            .max()
+            .unwrap_or(0);
+        let mut canvas = Canvas::new((max_x - min_x).ceil() as i64 + 9 + widest_label, (max_y - min_y).ceil() as i64 + 9);
+
+        let mut boxes = Vec::with_capacity(units.len());
+        let mut ports = HashMap::new();
+        for unit in 0..units.len() {
+            let center = shift(centers[unit]);
+            let (width, height) = (units.sizes[unit].0 as i64, units.sizes[unit].1 as i64);
+            let left = (center.x - width as f64 / 2.0).round() as i64;
+            let top = (center.y - height as f64 / 2.0).round() as i64;
+            let b = CharBox { left, top, right: left + width - 1, bottom: top + height - 1 };
+            match records.get(&units.ids[unit]) {
+                Some(record) => ports.extend(draw_record(&mut canvas, record, &b)),
+                None => {
+                    canvas.rectangle(&b);
+                    for (i, line) in labels[unit].iter().enumerate() {
+                        let x = left + (width - line.chars().count() as i64) / 2;
+                        canvas.write(x, top + 1 + i as i64, line);
+                    }
+                }
+            }
+            boxes.push(b);
+        }
+
+        let mut edge_labels = Vec::new();
+        for ((edge, start, end), bends) in units.edges.iter().zip(bends) {
+            let (from, to) = (&boxes[*start], &boxes[*end]);
+            // Edges from record fields start at their field
+            let anchor = |node: Node, unit: usize| match ports.get(&node) {
+                Some(&(x, y)) => (Point::new(x as f64, y as f64), true),
+                None => (shift(centers[unit]), false),
+            };
+            let ((from_anchor, from_port), (to_anchor, to_port)) = (anchor(edge.start_node(), *start), anchor(edge.end_node(), *end));
+            let mut cells = Vec::new();
+            let (first, last, start_mask, end_mask);
+            if start == end {
+                // Loop from the top of the box around to its right side
+                first = ((from.right - 1).max(from.left + 1), from.top - 1);
+                last = (from.right + 1, (from.top + from.bottom) / 2);
+                (start_mask, end_mask) = (DOWN, LEFT);
+                cells.push(first);
+                orthogonal((from.right + 2, from.top - 2), true, false, &mut cells);
+                orthogonal((from.right + 2, last.1), false, true, &mut cells);
+                orthogonal(last, false, false, &mut cells);
+            } else {
+                let bends: Vec<Point> = bends.into_iter().map(shift).collect();
+                let next = bends.first().copied().unwrap_or(to_anchor);
+                let previous = bends.last().copied().unwrap_or(from_anchor);
+                (first, start_mask) = from.exit(from_anchor, next, !horizontal, from_port);
+                (last, end_mask) = to.exit(to_anchor, previous, !horizontal, to_port);
+                let mut stops: Vec<(i64, i64)> = bends.iter().map(|p| (p.x.round() as i64, p.y.round() as i64)).collect();
+                stops.retain(|&stop| !from.contains(stop) && !to.contains(stop));
+                stops.push(last);
+                cells.push(first);
+                let count = stops.len();
+                for (i, stop) in stops.into_iter().enumerate() {
+                    let start_vertical = if i == 0 { start_mask & (UP | DOWN) != 0 } else { !horizontal };
+                    let end_vertical = if i + 1 == count { end_mask & (UP | DOWN) != 0 } else { !horizontal };
+                    orthogonal(stop, start_vertical, end_vertical, &mut cells);
+                }
+            }
+            canvas.path(&cells, start_mask, end_mask);
+
+            let properties = self.resolved_edge_properties(edge.id());
+            let default_dir = if self.is_directed() { "forward" } else { "none" };
+            let dir = text(&properties, "dir").unwrap_or_else(|| default_dir.to_string());
+            // Arrowheads point from the end of the edge into the box
+            let arrow = |mask: u8| match mask {
+                UP => '▲',
+                DOWN => '▼',
+                LEFT => '◄',
+                _ => '►',
+            };
+            if matches!(dir.as_str(), "forward" | "both") && canvas.is_free(last.0, last.1) {
+                canvas.put(last.0, last.1, arrow(end_mask));
+            }
+            if matches!(dir.as_str(), "back" | "both") && canvas.is_free(first.0, first.1) {
+                canvas.put(first.0, first.1, arrow(start_mask));
+            }
+            if let Some(label) = text(&properties, "label") {
+                edge_labels.push((cells, label.replace('\n', " ")));
+            }
+        }
+        // Put labels next to the middle of their edges, after every edge is drawn so they don't get drawn over
+        for (cells, label) in edge_labels {
+            let length = label.chars().count() as i64;
+            // Try the middle of the edge first, then further towards either end
+            let middle = cells.len() / 2;
+            let nearest = (0..cells.len()).map(|offset| if offset % 2 == 0 { middle + offset / 2 } else { middle.wrapping_sub(offset / 2 + 1) });
+            for (x, y) in nearest.filter_map(|i| cells.get(i).copied()) {
+                if canvas.write_free(x + 2, y, &label) || canvas.write_free(x - 1 - length, y, &label) {
+                    break;
+                }
+            }
+        }
+        canvas.render()
+    }
+
+    /// Draw the graph as an indented tree, or `None` if it has a cycle.
+    ///
+    /// Each node is listed under the nodes it has edges from, and nodes with several parents are
+    /// only expanded the first time they appear. In undirected graphs every connected part is
+    /// listed from its first node.
+    pub fn to_ascii_tree(&self) -> Option<String> {
+        let units = Units::new(self, false);
+        let records: HashMap<ID, &Record> = self.sorted_records().into_iter().map(|record| (record.id(), record)).collect();
+        let label = |unit: usize| {
+            let id = units.ids[unit];
+            match records.get(&id) {
+                Some(record) => record_columns(record).into_iter().flatten().map(|(_, lines)| lines.join(" ")).collect::<Vec<_>>().join(" | "),
+                None => node_label(&format!("Node_{}", id), &self.resolved_node_properties(id)).replace('\n', " "),
+            }
+        };
+
+        let mut children: Vec<Vec<(usize, Option<String>)>> = vec![Vec::new(); units.len()];
+        let mut has_parent = vec![false; units.len()];
+        for (edge, start, end) in &units.edges {
+            if start == end {
+                return None;
+            }
+            let edge_label = text(&self.resolved_edge_properties(edge.id()), "label").map(|l| l.replace('\n', " "));
+            children[*start].push((*end, edge_label.clone()));
+            has_parent[*end] = true;
+            if !self.is_directed() {
+                children[*end].push((*start, edge_label));
+            }
+        }
+
+        // Directed graphs must be acyclic; undirected graphs must be forests
+        if self.is_directed() {
+            let mut state = vec![0u8; units.len()];
+            // Depth-first with an explicit stack of (unit, next child), so long chains can't overflow
+            fn has_cycle(root: usize, children: &[Vec<(usize, Option<String>)>], state: &mut [u8]) -> bool {
+                state[root] = 1;
+                let mut stack = vec![(root, 0)];
+                while let Some((unit, next)) = stack.last_mut() {
+                    match children[*unit].get(*next) {
+                        Some(&(child, _)) => {
+                            *next += 1;
+                            if state[child] == 1 {
+                                return true;
+                            }
+                            if state[child] == 0 {
+                                state[child] = 1;
+                                stack.push((child, 0));
+                            }
+                        }
+                        None => {
+                            state[*unit] = 2;
+                            stack.pop();
+                        }
+                    }
+                }
+                false
+            }
+            if (0..units.len()).any(|unit| state[unit] == 0 && has_cycle(unit, &children, &mut state)) {
+                return None;
+            }
+        }
+
+        let mut text = String::new();
+        let mut expanded = vec![false; units.len()];
+        struct Printer<'a> {
+            children: &'a [Vec<(usize, Option<String>)>],
+            label: &'a dyn Fn(usize) -> String,
+            directed: bool,
+        }
+        struct Frame<'a> {
+            unit: usize,
+            prefix: String,
+            children: Vec<&'a (usize, Option<String>)>,
+            next: usize,
+        }
+        impl<'a> Printer<'a> {
+            fn frame(&self, unit: usize, parent: Option<usize>, prefix: String, expanded: &mut [bool]) -> Frame<'a> {
+                expanded[unit] = true;
+                let children = self.children[unit].iter().filter(|(child, _)| self.directed || Some(*child) != parent).collect();
+                Frame { unit, prefix, children, next: 0 }
+            }
+
+            // Walks the tree with an explicit stack of frames, so long chains can't overflow
+            fn print(&self, root: usize, text: &mut String, expanded: &mut [bool]) -> bool {
+                let mut stack = vec![self.frame(root, None, String::new(), expanded)];
+                while let Some(frame) = stack.last_mut() {
+                    let Some(&(child, ref edge_label)) = frame.children.get(frame.next).copied() else {
+                        stack.pop();
+                        continue;
+                    };
+                    frame.next += 1;
+                    let last = frame.next == frame.children.len();
+                    text.push_str(&frame.prefix);
+                    text.push_str(if last { "└── " } else { "├── " });
+                    if let Some(edge_label) = edge_label {
+                        text.push_str(&format!("[{}] ", edge_label));
+                    }
+                    text.push_str(&(self.label)(child));
+                    if expanded[child] {
+                        if !self.directed {
+                            // Reaching a node twice in an undirected graph means there is a cycle
+                            return false;
+                        }
+                        text.push_str(" (see above)\n");
+                        continue;
+                    }
+                    text.push('\n');
+                    let prefix = format!("{}{}", frame.prefix, if last { "    " } else { "│   " });
+                    let unit = frame.unit;
+                    stack.push(self.frame(child, Some(unit), prefix, expanded));
+                }
+                true
+            }
+        }
+        let printer = Printer { children: &children, label: &label, directed: self.is_directed() };
+        for unit in 0..units.len() {
+            if expanded[unit] || (self.is_directed() && has_parent[unit]) {
+                continue;
+            }
+            text.push_str(&label(unit));
+            text.push('\n');
+            if !printer.print(unit, &mut text, &mut expanded) {
+                return None;
+            }
+        }
+        Some(text)
+    }
+}
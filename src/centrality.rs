@@ -0,0 +1,229 @@
+use std::collections::{HashMap, VecDeque};
+use super::{Graph, Node, Properties};
+
+/// The most rounds the iterative measures run for before giving up on converging.
+const MAX_ITERATIONS: usize = 100;
+/// How little the scores may change per node in a round for an iterative measure to stop.
+const TOLERANCE: f64 = 1e-6;
+
+/// The nodes of a graph by index, with the indices of the nodes edges lead to from each.
+struct Adjacency {
+    nodes: Vec<Node>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    fn new(graph: &Graph) -> Self {
+        let nodes = graph.endpoints();
+        let position: HashMap<Node, usize> = nodes.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+        let successors = nodes.iter().map(|&node| graph.successors(node).iter().map(|next| position[next]).collect()).collect();
+        Adjacency { nodes, successors }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of edges from `start` to every node, or `None` for nodes it can't reach.
+    fn distances(&self, start: usize) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.len()];
+        distances[start] = Some(0);
+        let mut pending = VecDeque::from([start]);
+        while let Some(node) = pending.pop_front() {
+            for &next in &self.successors[node] {
+                if distances[next].is_none() {
+                    distances[next] = Some(distances[node].unwrap() + 1);
+                    pending.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    fn scores(self, scores: Vec<f64>) -> Vec<(Node, f64)> {
+        self.nodes.into_iter().zip(scores).collect()
+    }
+}
+
+/// Scale scores to run from 0 for the lowest to 1 for the highest, or 0 for all if they're equal.
+fn normalize(scores: &[(Node, f64)]) -> Vec<(Node, f64)> {
+    let low = scores.iter().map(|&(_, score)| score).fold(f64::INFINITY, f64::min);
+    let high = scores.iter().map(|&(_, score)| score).fold(f64::NEG_INFINITY, f64::max);
+    scores.iter().map(|&(node, score)| {
+        let t = if high > low { (score - low) / (high - low) } else { 0.0 };
+        (node, t)
+    }).collect()
+}
+
+/// Parse a color written as `#rrggbb`.
+fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+impl Graph {
+    /// The number of edges at each node, divided by the number of other nodes.
+    /// Nodes which are only the ends of edges, like record fields, are included in this and
+    /// the other centrality measures, and each returns the nodes in insertion order.
+    pub fn degree_centrality(&self) -> Vec<(Node, f64)> {
+        let nodes = self.endpoints();
+        let others = nodes.len().saturating_sub(1).max(1) as f64;
+        nodes.into_iter().map(|node| (node, self.degree(node) as f64 / others)).collect()
+    }
+
+    /// How few edges each node needs to reach the others, as the inverse of the average number
+    /// of edges to the nodes it can reach, scaled down by the fraction of nodes it can reach.
+    pub fn closeness_centrality(&self) -> Vec<(Node, f64)> {
+        let adjacency = Adjacency::new(self);
+        let others = adjacency.len().saturating_sub(1).max(1) as f64;
+        let scores = (0..adjacency.len()).map(|node| {
+            let distances: Vec<usize> = adjacency.distances(node).into_iter().flatten().collect();
+            let reached = (distances.len() - 1) as f64;
+            let total: usize = distances.iter().sum();
+            if total == 0 { 0.0 } else { reached / total as f64 * (reached / others) }
+        }).collect();
+        adjacency.scores(scores)
+    }
+
+    /// The fraction of the shortest paths between pairs of other nodes which pass through each
+    /// node, counting edges, with Brandes' algorithm.
+    pub fn betweenness_centrality(&self) -> Vec<(Node, f64)> {
+        let adjacency = Adjacency::new(self);
+        let count = adjacency.len();
+        let mut scores = vec![0.0; count];
+        for start in 0..count {
+            // Visit the nodes in order of distance, counting the shortest paths to each
+            let mut visited = Vec::new();
+            let mut previous: Vec<Vec<usize>> = vec![Vec::new(); count];
+            let mut paths = vec![0.0; count];
+            let mut distances: Vec<Option<usize>> = vec![None; count];
+            paths[start] = 1.0;
+            distances[start] = Some(0);
+            let mut pending = VecDeque::from([start]);
+            while let Some(node) = pending.pop_front() {
+                visited.push(node);
+                let distance = distances[node].unwrap() + 1;
+                for &next in &adjacency.successors[node] {
+                    if distances[next].is_none() {
+                        distances[next] = Some(distance);
+                        pending.push_back(next);
+                    }
+                    if distances[next] == Some(distance) {
+                        paths[next] += paths[node];
+                        previous[next].push(node);
+                    }
+                }
+            }
+            // Then share out the paths through each node from the farthest back
+            let mut dependency = vec![0.0; count];
+            for &node in visited.iter().rev() {
+                for &before in &previous[node] {
+                    dependency[before] += paths[before] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != start {
+                    scores[node] += dependency[node];
+                }
+            }
+        }
+        // Undirected graphs count each pair twice, which this scale accounts for
+        if count > 2 {
+            let pairs = ((count - 1) * (count - 2)) as f64;
+            scores.iter_mut().for_each(|score| *score /= pairs);
+        }
+        adjacency.scores(scores)
+    }
+
+    /// How much each node is pointed to by nodes which are pointed to themselves, as the
+    /// principal eigenvector of the adjacency matrix, scaled to a length of 1.
+    pub fn eigenvector_centrality(&self) -> Vec<(Node, f64)> {
+        let adjacency = Adjacency::new(self);
+        let count = adjacency.len();
+        let mut scores = vec![1.0 / count as f64; count];
+        for _ in 0..MAX_ITERATIONS {
+            // Adding the scores themselves keeps the iteration from oscillating
+            let mut next = scores.clone();
+            for (node, successors) in adjacency.successors.iter().enumerate() {
+                for &successor in successors {
+                    next[successor] += scores[node];
+                }
+            }
+            let length = next.iter().map(|score| score * score).sum::<f64>().sqrt();
+            if length > 0.0 {
+                next.iter_mut().for_each(|score| *score /= length);
+            }
+            let change: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+            scores = next;
+            if change < count as f64 * TOLERANCE {
+                break;
+            }
+        }
+        adjacency.scores(scores)
+    }
+
+    /// The chance of a random walk along edges being at each node, where each step follows a
+    /// random edge with probability `damping` (usually 0.85) and otherwise jumps to a random node.
+    /// Nodes without edges leaving them jump to a random node. The scores add up to 1.
+    pub fn pagerank(&self, damping: f64) -> Vec<(Node, f64)> {
+        let adjacency = Adjacency::new(self);
+        let count = adjacency.len();
+        let mut scores = vec![1.0 / count as f64; count];
+        for _ in 0..MAX_ITERATIONS {
+            let stranded: f64 = (0..count).filter(|&node| adjacency.successors[node].is_empty()).map(|node| scores[node]).sum();
+            let mut next = vec![(1.0 - damping + damping * stranded) / count as f64; count];
+            for (node, successors) in adjacency.successors.iter().enumerate() {
+                for &successor in successors {
+                    next[successor] += damping * scores[node] / successors.len() as f64;
+                }
+            }
+            let change: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+            scores = next;
+            if change < count as f64 * TOLERANCE {
+                break;
+            }
+        }
+        adjacency.scores(scores)
+    }
+
+    /// The properties of a scored node, or `None` for record fields and nodes which aren't in the graph.
+    fn scored_properties_mut(&mut self, node: Node) -> Option<&mut Properties> {
+        match node.record() {
+            Some(_) => None,
+            None => self.get_node_properties_mut(node.id()),
+        }
+    }
+
+    /// Store each node's score as a property, like `"pagerank"`.
+    /// Nodes which aren't in the graph, like record fields, are skipped.
+    pub fn set_scores(&mut self, scores: &[(Node, f64)], key: &str) {
+        for &(node, score) in scores {
+            if let Some(properties) = self.scored_properties_mut(node) {
+                properties.set(key, score);
+            }
+        }
+    }
+
+    /// Set a numeric property of each node, like `width` or `fontsize`, from `low` for the
+    /// lowest score to `high` for the highest, in proportion to their scores.
+    pub fn scale_by_scores(&mut self, scores: &[(Node, f64)], key: &str, low: f64, high: f64) {
+        for (node, t) in normalize(scores) {
+            if let Some(properties) = self.scored_properties_mut(node) {
+                let value = low + (high - low) * t;
+                properties.set(key, (value * 100.0).round() / 100.0);
+            }
+        }
+    }
+
+    /// Set a color property of each node, like `fillcolor`, on a gradient from the `#rrggbb`
+    /// color `low` for the lowest score to `high` for the highest. Filled nodes also need
+    /// `style=filled`. Nothing is changed if either color can't be read.
+    pub fn color_by_scores(&mut self, scores: &[(Node, f64)], key: &str, low: &str, high: &str) {
+        let (Some(low), Some(high)) = (parse_color(low), parse_color(high)) else { return };
+        for (node, t) in normalize(scores) {
+            if let Some(properties) = self.scored_properties_mut(node) {
+                let channel = |i: usize| (low[i] as f64 + (high[i] as f64 - low[i] as f64) * t).round() as u8;
+                properties.set(key, format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2)));
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+use super::{layout::node_label, Edge, Graph, Node, Properties};
+
+/// The state of Tarjan's search for strongly connected components.
+struct Tarjan<'a> {
+    graph: &'a Graph,
+    /// The order each node was first visited in
+    index: HashMap<Node, usize>,
+    /// The earliest visited node each node can reach while it is on the stack
+    low: HashMap<Node, usize>,
+    stack: Vec<Node>,
+    on_stack: HashSet<Node>,
+    components: Vec<Vec<Node>>,
+}
+
+impl Tarjan<'_> {
+    /// Give a node the next index and put it on the stack, returning its frame: the node, its
+    /// successors and the index of the next successor to look at.
+    fn enter(&mut self, node: Node) -> (Node, Vec<Node>, usize) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low.insert(node, index);
+        self.stack.push(node);
+        self.on_stack.insert(node);
+        (node, self.graph.successors(node), 0)
+    }
+
+    /// Visit every node reachable from `root` depth-first, with an explicit stack of frames so
+    /// long chains can't overflow the stack.
+    fn visit(&mut self, root: Node) {
+        let mut frames = vec![self.enter(root)];
+        while let Some((node, successors, next_index)) = frames.last_mut() {
+            let node = *node;
+            if let Some(&next) = successors.get(*next_index) {
+                *next_index += 1;
+                if !self.index.contains_key(&next) {
+                    let frame = self.enter(next);
+                    frames.push(frame);
+                } else if self.on_stack.contains(&next) {
+                    let low = self.low[&node].min(self.index[&next]);
+                    self.low.insert(node, low);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if self.low[&node] == self.index[&node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+            if let Some(&(parent, ..)) = frames.last() {
+                let low = self.low[&parent].min(self.low[&node]);
+                self.low.insert(parent, low);
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Put the nodes of each component in insertion order.
+    fn component_nodes(nodes: &[Node], mut components: Vec<Vec<Node>>) -> Vec<Vec<Node>> {
+        let position: HashMap<Node, usize> = nodes.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+        for component in &mut components {
+            component.sort_by_key(|node| position[node]);
+        }
+        components
+    }
+
+    /// Split the nodes into groups where every node can reach every other along edges, with
+    /// Tarjan's algorithm. Nodes which are only the ends of edges, like record fields, are included.
+    ///
+    /// The components are in topological order, so edges between them only lead to later ones,
+    /// and the nodes of each are in insertion order. In undirected graphs, these are the
+    /// connected components.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<Node>> {
+        let nodes = self.endpoints();
+        let mut search = Tarjan {
+            graph: self,
+            index: HashMap::new(),
+            low: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+        for &node in &nodes {
+            if !search.index.contains_key(&node) {
+                search.visit(node);
+            }
+        }
+        // Tarjan's algorithm finds a component after every component it has edges to
+        let mut components = search.components;
+        components.reverse();
+        Self::component_nodes(&nodes, components)
+    }
+
+    /// Split the nodes into groups which are connected by edges in either direction, in the
+    /// order of their first nodes. The nodes of each are in insertion order.
+    pub fn weakly_connected_components(&self) -> Vec<Vec<Node>> {
+        let nodes = self.endpoints();
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+        for &node in &nodes {
+            if !seen.insert(node) {
+                continue;
+            }
+            let mut component = vec![node];
+            let mut pending = vec![node];
+            while let Some(node) = pending.pop() {
+                let edges = self.written_edges(node, true).into_iter().chain(self.written_edges(node, false));
+                for edge in edges {
+                    for next in [edge.start_node(), edge.end_node()] {
+                        if seen.insert(next) {
+                            component.push(next);
+                            pending.push(next);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        Self::component_nodes(&nodes, components)
+    }
+
+    /// The text a node shows, or the text of its field for record fields.
+    fn member_label(&self, node: Node) -> String {
+        let field = node.record()
+            .and_then(|record| self.records().find(|other| other.id() == record))
+            .and_then(|record| record.nodes().columns().flat_map(|column| column.cells()).find(|(port, _)| port.id() == node.id()))
+            .map(|(_, label)| label.to_string());
+        field.unwrap_or_else(|| node_label(&node.name(), &self.resolved_node_properties(node.id())))
+    }
+
+    /// Make a new graph with each strongly connected component collapsed into one node, and
+    /// at most one edge from one component to another, which has no cycles.
+    ///
+    /// A component with one node keeps that node's ID and properties. A component with several
+    /// nodes gets the ID of its first node and a label listing the labels of its members.
+    /// Edges keep the ID and properties of the first edge between their components. Subgraphs
+    /// are not kept, and record fields become nodes of their own, with their record's ID added
+    /// to theirs since fields of different records can share an ID.
+    pub fn condensation(&self) -> Graph {
+        let mut graph = Graph::new()
+            .with_kind(self.kind())
+            .with_strict(self.is_strict())
+            .with_node_defaults(self.node_defaults().clone())
+            .with_edge_defaults(self.edge_defaults().clone());
+        *graph.properties_mut() = self.properties().clone();
+
+        let mut component_of = HashMap::new();
+        for members in self.strongly_connected_components() {
+            let first = members[0];
+            let node = Node::new(first.record().map_or(first.id(), |record| record + first.id()));
+            for &member in &members {
+                component_of.insert(member, node);
+            }
+            let own = match members[..] {
+                [member] if member.record().is_none() => self.get_node_properties(member.id()),
+                _ => None,
+            };
+            let properties = own.cloned().unwrap_or_else(|| {
+                let labels: Vec<String> = members.iter().map(|&member| self.member_label(member)).collect();
+                let mut properties = Properties::new();
+                properties.set("label", labels.join(", "));
+                properties
+            });
+            graph.insert_node(node, properties);
+        }
+
+        let mut connected = HashSet::new();
+        for edge in self.edges() {
+            let (start, end) = (component_of[&edge.start_node()], component_of[&edge.end_node()]);
+            if start != end && connected.insert((start, end)) {
+                let properties = self.get_edge_properties(edge.id()).unwrap().clone();
+                graph.insert_edge(Edge::new(edge.id(), start, end), properties);
+            }
+        }
+        graph
+    }
+}
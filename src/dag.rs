@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use super::{Edge, EdgeWeight, Graph, GraphError, Node, Path, Result};
+
+/// The state of Johnson's search for the elementary cycles through one start node.
+struct CycleSearch<'a> {
+    start: usize,
+    successors: &'a [Vec<(usize, Edge)>],
+    /// The nodes in the start node's strongly connected component, among those after it
+    component: Vec<bool>,
+    blocked: Vec<bool>,
+    /// The nodes to unblock when a node is unblocked
+    blocked_by: Vec<Vec<usize>>,
+    path: Vec<Edge>,
+    cycles: Vec<Vec<Edge>>,
+}
+
+impl CycleSearch<'_> {
+    /// Unblock a node, and the nodes waiting on it, with a worklist so long chains of blocked
+    /// nodes can't overflow the stack.
+    fn unblock(&mut self, node: usize) {
+        let mut pending = vec![node];
+        while let Some(node) = pending.pop() {
+            self.blocked[node] = false;
+            for other in std::mem::take(&mut self.blocked_by[node]) {
+                if self.blocked[other] {
+                    pending.push(other);
+                }
+            }
+        }
+    }
+
+    /// Extend the path from the start node through every unblocked node, recording each cycle
+    /// back to the start. The path is walked with an explicit stack of frames holding a node,
+    /// the index of its next successor, and whether any cycle was found from it.
+    fn circuit(&mut self) {
+        let successors = self.successors;
+        self.blocked[self.start] = true;
+        let mut stack = vec![(self.start, 0, false)];
+        while let Some((node, next_index, found)) = stack.last_mut() {
+            if let Some(&(next, edge)) = successors[*node].get(*next_index) {
+                *next_index += 1;
+                if !self.component[next] {
+                    continue;
+                }
+                if next == self.start {
+                    let mut cycle = self.path.clone();
+                    cycle.push(edge);
+                    self.cycles.push(cycle);
+                    *found = true;
+                } else if !self.blocked[next] {
+                    self.path.push(edge);
+                    self.blocked[next] = true;
+                    stack.push((next, 0, false));
+                }
+                continue;
+            }
+
+            let (node, _, found) = stack.pop().unwrap();
+            if found {
+                self.unblock(node);
+            } else {
+                for &(next, _) in &successors[node] {
+                    if self.component[next] && !self.blocked_by[next].contains(&node) {
+                        self.blocked_by[next].push(node);
+                    }
+                }
+            }
+            if let Some((_, _, parent_found)) = stack.last_mut() {
+                *parent_found |= found;
+                self.path.pop();
+            }
+        }
+    }
+}
+
+/// The most expensive paths through a graph without cycles.
+struct LongestPaths {
+    /// The nodes in topological order
+    sorted: Vec<Node>,
+    costs: HashMap<Node, f64>,
+    /// The edge each node is reached by on its most expensive path
+    reached_by: HashMap<Node, Edge>,
+}
+
+/// The nodes which can be reached from `start` along `edges`, passing only through nodes
+/// which are `allowed`.
+fn reachable(start: usize, edges: &[Vec<(usize, Edge)>], allowed: impl Fn(usize) -> bool) -> Vec<bool> {
+    let mut reached = vec![false; edges.len()];
+    reached[start] = true;
+    let mut pending = vec![start];
+    while let Some(node) = pending.pop() {
+        for &(next, _) in &edges[node] {
+            if allowed(next) && !reached[next] {
+                reached[next] = true;
+                pending.push(next);
+            }
+        }
+    }
+    reached
+}
+
+impl Graph {
+    /// Follow edges backwards from `start` through the nodes in `within` until a node repeats,
+    /// returning the edges of the cycle found in their direction.
+    fn cycle_within(&self, start: Node, within: &HashSet<Node>) -> Vec<Edge> {
+        let mut path = Vec::new();
+        let mut seen = HashMap::new();
+        let mut node = start;
+        while !seen.contains_key(&node) {
+            seen.insert(node, path.len());
+            let edge = self.written_edges(node, false).into_iter()
+                .find(|edge| within.contains(&edge.start_node()))
+                .expect("every node left unsorted has an edge from another");
+            path.push(edge);
+            node = edge.start_node();
+        }
+        let mut cycle = path.split_off(seen[&node]);
+        cycle.reverse();
+        cycle
+    }
+
+    /// Order the nodes so that every edge leads from an earlier node to a later one, keeping
+    /// insertion order where the edges allow it. Nodes which are only the ends of edges, like
+    /// record fields, are included.
+    ///
+    /// Edges are followed from their start to their end as written, even in undirected graphs.
+    /// If the graph has a cycle, a `GraphError::Cycle` with the edges of one cycle is returned.
+    pub fn topological_sort(&self) -> Result<Vec<Node>> {
+        let nodes = self.endpoints();
+        let mut unsorted: HashMap<Node, usize> = nodes.iter().map(|&node| (node, self.written_edges(node, false).len())).collect();
+        let mut ready: VecDeque<Node> = nodes.iter().filter(|node| unsorted[node] == 0).copied().collect();
+
+        let mut sorted = Vec::new();
+        while let Some(node) = ready.pop_front() {
+            unsorted.remove(&node);
+            sorted.push(node);
+            for edge in self.written_edges(node, true) {
+                let end = edge.end_node();
+                let count = unsorted.get_mut(&end).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(end);
+                }
+            }
+        }
+
+        match nodes.iter().find(|node| unsorted.contains_key(node)) {
+            Some(&node) => {
+                let within = unsorted.keys().copied().collect();
+                Err(GraphError::Cycle(self.cycle_within(node, &within)))
+            }
+            None => Ok(sorted),
+        }
+    }
+
+    /// Whether the edges, followed as written, never lead back to where they started.
+    pub fn is_dag(&self) -> bool {
+        self.topological_sort().is_ok()
+    }
+
+    /// Find every elementary cycle of edges, which visits no node twice, with Johnson's
+    /// algorithm. Each cycle starts at its earliest node, and self-loops are cycles of one edge.
+    ///
+    /// Edges are followed as written, even in undirected graphs. A graph can have very many
+    /// cycles, so this is best kept to small graphs.
+    pub fn find_cycles(&self) -> Vec<Vec<Edge>> {
+        let nodes = self.endpoints();
+        let index: HashMap<Node, usize> = nodes.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+        let successors: Vec<Vec<(usize, Edge)>> = nodes.iter()
+            .map(|&node| self.written_edges(node, true).into_iter().map(|edge| (index[&edge.end_node()], edge)).collect())
+            .collect();
+        let mut predecessors = vec![Vec::new(); nodes.len()];
+        for (node, edges) in successors.iter().enumerate() {
+            for &(next, edge) in edges {
+                predecessors[next].push((node, edge));
+            }
+        }
+
+        let mut cycles = Vec::new();
+        for start in 0..nodes.len() {
+            // Cycles through `start` and later nodes stay within its strongly connected component,
+            // which is every node reached from `start` through nodes that lead back to it
+            let backward = reachable(start, &predecessors, |next| next >= start);
+            let component = reachable(start, &successors, |next| backward[next]);
+            if !successors[start].iter().any(|&(next, _)| component[next]) {
+                continue;
+            }
+            let mut search = CycleSearch {
+                start,
+                successors: &successors,
+                component,
+                blocked: vec![false; nodes.len()],
+                blocked_by: vec![Vec::new(); nodes.len()],
+                path: Vec::new(),
+                cycles: Vec::new(),
+            };
+            search.circuit();
+            cycles.append(&mut search.cycles);
+        }
+        cycles
+    }
+
+    /// The most expensive paths from `from`, or from every node with `None`, to each node they reach.
+    fn longest_paths(&self, from: Option<Node>, weight: &impl EdgeWeight) -> Result<LongestPaths> {
+        let sorted = self.topological_sort()?;
+        let mut costs: HashMap<Node, f64> = match from {
+            Some(from) => HashMap::from([(from, 0.0)]),
+            None => sorted.iter().map(|&node| (node, 0.0)).collect(),
+        };
+        let mut reached_by = HashMap::new();
+        for &node in &sorted {
+            let Some(&cost) = costs.get(&node) else { continue };
+            for edge in self.written_edges(node, true) {
+                let cost = cost + weight.weight(edge, &self.resolved_edge_properties(edge.id()));
+                let end = edge.end_node();
+                if costs.get(&end).is_none_or(|&known| cost > known) {
+                    costs.insert(end, cost);
+                    reached_by.insert(end, edge);
+                }
+            }
+        }
+        Ok(LongestPaths { sorted, costs, reached_by })
+    }
+
+    /// Find the most expensive path between two nodes of a graph without cycles, or `None` if
+    /// `to` can't be reached from `from`. Edges are followed as written, even in undirected graphs.
+    ///
+    /// If the graph has a cycle, a `GraphError::Cycle` is returned.
+    pub fn longest_path(&self, from: impl Into<Node>, to: impl Into<Node>, weight: impl EdgeWeight) -> Result<Option<Path>> {
+        let to = to.into();
+        let paths = self.longest_paths(Some(from.into()), &weight)?;
+        Ok(paths.costs.get(&to).map(|&cost| Self::trace_path(to, &paths.reached_by, cost)))
+    }
+
+    /// Find the most expensive path anywhere in a graph without cycles, such as the chain of
+    /// tasks which decides how long a project takes when the weights are their durations.
+    /// This is `None` only for a graph with no nodes.
+    ///
+    /// If the graph has a cycle, a `GraphError::Cycle` is returned.
+    pub fn critical_path(&self, weight: impl EdgeWeight) -> Result<Option<Path>> {
+        let paths = self.longest_paths(None, &weight)?;
+        let mut longest: Option<(Node, f64)> = None;
+        for node in paths.sorted {
+            let cost = paths.costs[&node];
+            if longest.is_none_or(|(_, most)| cost > most) {
+                longest = Some((node, cost));
+            }
+        }
+        Ok(longest.map(|(node, cost)| Self::trace_path(node, &paths.reached_by, cost)))
+    }
+}
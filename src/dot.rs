@@ -0,0 +1,695 @@
+use std::{collections::HashMap, error::Error, fmt::{Display, Formatter, Result as FmtResult}, str::FromStr};
+use super::{Graph, GraphKind, Node, Properties, Subgraph, Value, ID};
+
+/// An error encountered while parsing DOT source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: impl ToString) -> Self {
+        ParseError { line, column, message: message.to_string() }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An unquoted identifier or numeral
+    Id(String),
+    /// A double-quoted string, with its escaped quotes and line continuations processed
+    Quoted(String),
+    /// An HTML string, without the outermost angle brackets
+    Html(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semicolon,
+    Comma,
+    Colon,
+    Equals,
+    Plus,
+    DirectedEdge,
+    UndirectedEdge,
+    Eof,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Token::Id(id) => write!(f, "`{}`", id),
+            Token::Quoted(s) => write!(f, "\"{}\"", s),
+            Token::Html(s) => write!(f, "<{}>", s),
+            Token::LBrace => write!(f, "`{{`"),
+            Token::RBrace => write!(f, "`}}`"),
+            Token::LBracket => write!(f, "`[`"),
+            Token::RBracket => write!(f, "`]`"),
+            Token::Semicolon => write!(f, "`;`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::Colon => write!(f, "`:`"),
+            Token::Equals => write!(f, "`=`"),
+            Token::Plus => write!(f, "`+`"),
+            Token::DirectedEdge => write!(f, "`->`"),
+            Token::UndirectedEdge => write!(f, "`--`"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    /// Whether only whitespace has been seen on the current line
+    line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Lexer { chars: source.chars().peekable(), line: 1, column: 1, line_start: true }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+            self.line_start = true;
+        } else {
+            self.column += 1;
+            if !c.is_whitespace() {
+                self.line_start = false;
+            }
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl ToString) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    /// Skip whitespace, comments and preprocessor output lines.
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') if self.line_start => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                Some('/') => {
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+                    match lookahead.next() {
+                        Some('/') => {
+                            while !matches!(self.peek(), None | Some('\n')) {
+                                self.bump();
+                            }
+                        }
+                        Some('*') => {
+                            self.bump();
+                            self.bump();
+                            loop {
+                                match self.bump() {
+                                    Some('*') if self.peek() == Some('/') => {
+                                        self.bump();
+                                        break;
+                                    }
+                                    Some(_) => {}
+                                    None => return Err(self.error("unterminated comment")),
+                                }
+                            }
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<(Token, usize, usize), ParseError> {
+        self.skip_trivia()?;
+        let (line, column) = (self.line, self.column);
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok((Token::Eof, line, column)),
+        };
+        let token = match c {
+            '{' => { self.bump(); Token::LBrace }
+            '}' => { self.bump(); Token::RBrace }
+            '[' => { self.bump(); Token::LBracket }
+            ']' => { self.bump(); Token::RBracket }
+            ';' => { self.bump(); Token::Semicolon }
+            ',' => { self.bump(); Token::Comma }
+            ':' => { self.bump(); Token::Colon }
+            '=' => { self.bump(); Token::Equals }
+            '+' => { self.bump(); Token::Plus }
+            '"' => self.quoted()?,
+            '<' => self.html()?,
+            '-' => {
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                match lookahead.next() {
+                    Some('>') => {
+                        self.bump();
+                        self.bump();
+                        Token::DirectedEdge
+                    }
+                    Some('-') => {
+                        self.bump();
+                        self.bump();
+                        Token::UndirectedEdge
+                    }
+                    _ => self.numeral(),
+                }
+            }
+            c if c == '.' || c.is_ascii_digit() => self.numeral(),
+            c if c == '_' || c.is_alphabetic() || !c.is_ascii() => {
+                let mut id = String::new();
+                while let Some(c) = self.peek() {
+                    if c == '_' || c.is_alphanumeric() || !c.is_ascii() {
+                        id.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Id(id)
+            }
+            c => return Err(self.error(format!("unexpected character `{}`", c))),
+        };
+        Ok((token, line, column))
+    }
+
+    fn numeral(&mut self) -> Token {
+        let mut numeral = String::new();
+        if self.peek() == Some('-') {
+            numeral.push('-');
+            self.bump();
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                numeral.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Token::Id(numeral)
+    }
+
+    fn quoted(&mut self) -> Result<Token, ParseError> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(Token::Quoted(s)),
+                Some('\\') => match self.peek() {
+                    Some('"') => {
+                        self.bump();
+                        s.push('"');
+                    }
+                    // Other escapes are kept for Graphviz to read, and a pair of backslashes is
+                    // kept whole so its second backslash can't escape a quote
+                    Some('\\') => {
+                        self.bump();
+                        s.push_str("\\\\");
+                    }
+                    // A backslash at the end of a line continues the string
+                    Some('\n') => {
+                        self.bump();
+                    }
+                    _ => s.push('\\'),
+                },
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn html(&mut self) -> Result<Token, ParseError> {
+        self.bump();
+        let mut s = String::new();
+        let mut depth = 1;
+        loop {
+            match self.bump() {
+                Some('<') => {
+                    depth += 1;
+                    s.push('<');
+                }
+                Some('>') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(Token::Html(s));
+                    }
+                    s.push('>');
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.error("unterminated HTML string")),
+            }
+        }
+    }
+}
+
+/// The subgraph a block of statements is in, and the attribute defaults which have to be
+/// copied into each new node or edge because they only apply to items after them.
+#[derive(Clone)]
+struct Scope {
+    subgraph: Option<ID>,
+    node_defaults: Properties,
+    edge_defaults: Properties,
+}
+
+/// One side of an edge statement: a list of nodes, each with an optional port.
+type Operand = Vec<(Node, Option<String>)>;
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    token: Token,
+    line: usize,
+    column: usize,
+    graph: Graph,
+    nodes: HashMap<String, Node>,
+    anonymous_subgraphs: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(source);
+        let (token, line, column) = lexer.next_token()?;
+        Ok(Parser {
+            lexer,
+            token,
+            line,
+            column,
+            graph: Graph::new(),
+            nodes: HashMap::new(),
+            anonymous_subgraphs: 0,
+        })
+    }
+
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let (token, line, column) = self.lexer.next_token()?;
+        self.line = line;
+        self.column = column;
+        Ok(std::mem::replace(&mut self.token, token))
+    }
+
+    fn error(&self, message: impl ToString) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        self.error(format!("expected {}, found {}", expected, self.token))
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        if self.token == token {
+            self.advance()?;
+            Ok(())
+        } else {
+            Err(self.unexpected(&token.to_string()))
+        }
+    }
+
+    fn eat(&mut self, token: Token) -> Result<bool, ParseError> {
+        if self.token == token {
+            self.advance()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.token, Token::Id(id) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_id(&self) -> bool {
+        matches!(self.token, Token::Id(_) | Token::Quoted(_) | Token::Html(_))
+            && !["node", "edge", "graph", "digraph", "subgraph", "strict"].iter().any(|k| self.is_keyword(k))
+    }
+
+    /// Parse an identifier, returning its text and the value it denotes.
+    fn id(&mut self) -> Result<(String, Value), ParseError> {
+        if !self.is_id() {
+            return Err(self.unexpected("an identifier"));
+        }
+        match self.advance()? {
+            Token::Id(id) => {
+                // Keep the exact text of numerals which would not print the same way
+                let value = match id.parse::<Value>() {
+                    Ok(value) if value.to_string() == id => value,
+                    _ => Value::String(id.clone()),
+                };
+                Ok((id, value))
+            }
+            Token::Quoted(mut s) => {
+                while self.eat(Token::Plus)? {
+                    match self.advance()? {
+                        Token::Quoted(rest) => s.push_str(&rest),
+                        _ => return Err(self.error("expected a string after `+`")),
+                    }
+                }
+                Ok((s.clone(), Value::String(s)))
+            }
+            Token::Html(s) => Ok((s.clone(), Value::Html(s))),
+            _ => unreachable!(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Graph, ParseError> {
+        let strict = if self.is_keyword("strict") {
+            self.advance()?;
+            true
+        } else {
+            false
+        };
+        let kind = if self.is_keyword("digraph") {
+            GraphKind::Directed
+        } else if self.is_keyword("graph") {
+            GraphKind::Undirected
+        } else {
+            return Err(self.unexpected("`graph` or `digraph`"));
+        };
+        self.advance()?;
+        self.graph = Graph::new().with_kind(kind).with_strict(strict);
+        if self.is_id() {
+            self.id()?;
+        }
+        self.expect(Token::LBrace)?;
+        let mut scope = Scope { subgraph: None, node_defaults: Properties::new(), edge_defaults: Properties::new() };
+        self.stmt_list(&mut scope, &mut Vec::new())?;
+        self.expect(Token::RBrace)?;
+        if self.token != Token::Eof {
+            return Err(self.unexpected("end of input"));
+        }
+        Ok(self.graph)
+    }
+
+    /// Parse statements until the closing brace, collecting every node mentioned in `mentioned`.
+    fn stmt_list(&mut self, scope: &mut Scope, mentioned: &mut Operand) -> Result<(), ParseError> {
+        while self.token != Token::RBrace {
+            if self.token == Token::Eof {
+                return Err(self.unexpected("`}`"));
+            }
+            self.stmt(scope, mentioned)?;
+            self.eat(Token::Semicolon)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, scope: &mut Scope, mentioned: &mut Operand) -> Result<(), ParseError> {
+        if self.is_keyword("graph") {
+            self.advance()?;
+            let mut properties = self.attr_list()?;
+            self.scope_properties(scope).append(&mut properties);
+        } else if self.is_keyword("node") {
+            self.advance()?;
+            let properties = self.attr_list()?;
+            self.defaults(scope, properties, false);
+        } else if self.is_keyword("edge") {
+            self.advance()?;
+            let properties = self.attr_list()?;
+            self.defaults(scope, properties, true);
+        } else if self.is_keyword("subgraph") || self.token == Token::LBrace {
+            let operand = self.subgraph(scope)?;
+            mentioned.extend(operand.iter().cloned());
+            self.edge_rhs(scope, operand, mentioned)?;
+        } else if self.is_id() {
+            let (name, _) = self.id()?;
+            if self.eat(Token::Equals)? {
+                let (_, value) = self.id()?;
+                self.scope_properties(scope).set(name, value);
+                return Ok(());
+            }
+            let port = self.port()?;
+            if matches!(self.token, Token::DirectedEdge | Token::UndirectedEdge) {
+                let node = self.node(&name, scope);
+                mentioned.push((node, None));
+                self.edge_rhs(scope, vec![(node, port)], mentioned)?;
+            } else {
+                let node = self.node(&name, scope);
+                mentioned.push((node, None));
+                if self.token == Token::LBracket {
+                    let mut properties = self.attr_list()?;
+                    if let Some(existing) = self.graph.get_node_properties_mut(node) {
+                        existing.append(&mut properties);
+                    }
+                }
+            }
+        } else {
+            return Err(self.unexpected("a statement"));
+        }
+        Ok(())
+    }
+
+    /// Apply a `node` or `edge` default statement. Defaults are stored in the graph or subgraph
+    /// while it has no nodes (or edges) yet, since they then apply to exactly the items after
+    /// them; otherwise they're copied into each later item.
+    fn defaults(&mut self, scope: &mut Scope, mut properties: Properties, edges: bool) {
+        let pending = if edges { &mut scope.edge_defaults } else { &mut scope.node_defaults };
+        if self.has_items(scope.subgraph, edges) {
+            pending.append(&mut properties);
+            return;
+        }
+        // Stored defaults replace any pending ones of an enclosing scope
+        for (key, _) in &properties {
+            pending.remove(key);
+        }
+        let stored = match (scope.subgraph, edges) {
+            (Some(id), false) => self.graph.get_subgraph_node_defaults_mut(id).unwrap(),
+            (Some(id), true) => self.graph.get_subgraph_edge_defaults_mut(id).unwrap(),
+            (None, false) => self.graph.node_defaults_mut(),
+            (None, true) => self.graph.edge_defaults_mut(),
+        };
+        stored.append(&mut properties);
+    }
+
+    /// Whether the subgraph `scope` (or the whole graph, for `None`) or one nested in it
+    /// has any nodes, or edges.
+    fn has_items(&self, scope: Option<ID>, edges: bool) -> bool {
+        let ids: Vec<ID> = if edges {
+            self.graph.sorted_edges().iter().map(|edge| edge.id()).collect()
+        } else {
+            self.graph.sorted_nodes().iter().map(|node| node.id()).collect()
+        };
+        ids.into_iter().any(|id| {
+            let parent = self.graph.subgraph_of(id).map(|s| s.id());
+            parent == scope || parent.is_some_and(|parent| self.is_ancestor(scope, parent))
+        })
+    }
+
+    /// The properties which graph attribute statements in this scope modify.
+    fn scope_properties(&mut self, scope: &Scope) -> &mut Properties {
+        match scope.subgraph {
+            Some(id) => self.graph.get_subgraph_properties_mut(id).unwrap(),
+            None => self.graph.properties_mut(),
+        }
+    }
+
+    fn attr_list(&mut self) -> Result<Properties, ParseError> {
+        let mut properties = Properties::new();
+        if self.token != Token::LBracket {
+            return Err(self.unexpected("`[`"));
+        }
+        while self.eat(Token::LBracket)? {
+            while self.token != Token::RBracket {
+                let (key, _) = self.id()?;
+                self.expect(Token::Equals)?;
+                let (_, value) = self.id()?;
+                properties.set(key, value);
+                if !self.eat(Token::Comma)? {
+                    self.eat(Token::Semicolon)?;
+                }
+            }
+            self.expect(Token::RBracket)?;
+        }
+        Ok(properties)
+    }
+
+    fn port(&mut self) -> Result<Option<String>, ParseError> {
+        if !self.eat(Token::Colon)? {
+            return Ok(None);
+        }
+        let (mut port, _) = self.id()?;
+        if self.eat(Token::Colon)? {
+            let (compass, _) = self.id()?;
+            port = format!("{}:{}", port, compass);
+        }
+        Ok(Some(port))
+    }
+
+    /// Look up a node by name, creating it with the current defaults if it is new.
+    fn node(&mut self, name: &str, scope: &Scope) -> Node {
+        if let Some(&node) = self.nodes.get(name) {
+            // Nodes mentioned in a nested subgraph move into the innermost one
+            if let Some(subgraph) = scope.subgraph {
+                if self.is_ancestor(self.graph.subgraph_of(node).map(|s| s.id()), subgraph) {
+                    self.move_node(node, subgraph);
+                }
+            }
+            return node;
+        }
+        // Names this library writes keep their IDs, and show the same text without a label
+        let native = id_of_name(name);
+        let mut builder = self.graph.new_node(native.unwrap_or_else(|| ID::from(name))).with_properties(scope.node_defaults.clone());
+        if let Some(subgraph) = scope.subgraph {
+            builder = builder.in_subgraph(subgraph);
+        }
+        let node = builder.finalize();
+        if native.is_none() && self.graph.resolved_node_properties(node).get("label").is_none() {
+            self.graph.get_node_properties_mut(node).unwrap().set("label", name);
+        }
+        self.nodes.insert(name.to_string(), node);
+        node
+    }
+
+    /// Move a node into a subgraph. Defaults only apply to nodes made after them, so the node
+    /// keeps any attribute the subgraph's defaults would change, with `""` for Graphviz's default.
+    fn move_node(&mut self, node: Node, subgraph: ID) {
+        let before = self.graph.resolved_node_properties(node);
+        self.graph.move_to_subgraph(node, Some(subgraph));
+        let after = self.graph.resolved_node_properties(node);
+        let own = self.graph.get_node_properties_mut(node).unwrap();
+        for (key, value) in &after {
+            if own.get(key).is_none() && before.get(key) != Some(value) {
+                own.set(key, before.get(key).cloned().unwrap_or_else(|| Value::from("")));
+            }
+        }
+    }
+
+    /// Whether `ancestor` (or the top level, for `None`) contains the subgraph `id`.
+    fn is_ancestor(&self, ancestor: Option<ID>, id: ID) -> bool {
+        let mut current = Some(id);
+        while let Some(subgraph) = current {
+            current = self.graph.subgraph_of(subgraph).map(|s| s.id());
+            if current == ancestor {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn subgraph(&mut self, parent: &Scope) -> Result<Operand, ParseError> {
+        let name = if self.is_keyword("subgraph") {
+            self.advance()?;
+            if self.is_id() { Some(self.id()?.0) } else { None }
+        } else {
+            None
+        };
+        let name = name.unwrap_or_else(|| {
+            self.anonymous_subgraphs += 1;
+            format!("__anonymous_{}", self.anonymous_subgraphs)
+        });
+        let id = id_of_name(&name).unwrap_or_else(|| ID::from(&name));
+        if self.graph.get_subgraph_properties(id).is_none() {
+            let subgraph = if name.starts_with("cluster") { Subgraph::cluster(id) } else { Subgraph::new(id) };
+            self.graph.insert_subgraph(subgraph, Properties::new());
+            self.graph.move_to_subgraph(subgraph, parent.subgraph);
+        }
+        let mut scope = Scope { subgraph: Some(id), ..parent.clone() };
+        let mut mentioned = Vec::new();
+        self.expect(Token::LBrace)?;
+        self.stmt_list(&mut scope, &mut mentioned)?;
+        self.expect(Token::RBrace)?;
+        Ok(mentioned)
+    }
+
+    /// Parse the rest of an edge statement after its first operand.
+    fn edge_rhs(&mut self, scope: &Scope, first: Operand, mentioned: &mut Operand) -> Result<(), ParseError> {
+        let mut operands = vec![first];
+        while matches!(self.token, Token::DirectedEdge | Token::UndirectedEdge) {
+            let op = self.advance()?;
+            match (op, self.graph.kind()) {
+                (Token::DirectedEdge, GraphKind::Undirected) => return Err(self.error("`->` used in an undirected graph")),
+                (Token::UndirectedEdge, GraphKind::Directed) => return Err(self.error("`--` used in a directed graph")),
+                _ => {}
+            }
+            let operand = if self.is_keyword("subgraph") || self.token == Token::LBrace {
+                self.subgraph(scope)?
+            } else {
+                let (name, _) = self.id()?;
+                let port = self.port()?;
+                vec![(self.node(&name, scope), port)]
+            };
+            mentioned.extend(operand.iter().map(|(node, _)| (*node, None)));
+            operands.push(operand);
+        }
+        if operands.len() == 1 {
+            return Ok(());
+        }
+
+        let mut properties = scope.edge_defaults.clone();
+        if self.token == Token::LBracket {
+            properties.append(&mut self.attr_list()?);
+        }
+        for pair in operands.windows(2) {
+            for (start, start_port) in &pair[0] {
+                for (end, end_port) in &pair[1] {
+                    let mut builder = self.graph.new_edge(*start, *end).with_properties(properties.clone());
+                    if let Some(port) = start_port {
+                        builder = builder.with_property("tailport", port.as_str());
+                    }
+                    if let Some(port) = end_port {
+                        builder = builder.with_property("headport", port.as_str());
+                    }
+                    if let Some(subgraph) = scope.subgraph {
+                        builder = builder.in_subgraph(subgraph);
+                    }
+                    builder.finalize();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Get the `ID` an object was named after in the DOT this library writes.
+pub(crate) fn id_of_name(name: &str) -> Option<ID> {
+    let hex = ["Node_", "Record_", "Edge_", "cluster_", "Subgraph_"].iter().find_map(|prefix| name.strip_prefix(prefix))?;
+    ID::from_hex(hex)
+}
+
+impl Graph {
+    /// Parse a graph from DOT source.
+    ///
+    /// Nodes are identified by `ID::from` their DOT name, which also becomes their label
+    /// unless one is given. Names this library writes, like `Node_<hex>` and `cluster_<hex>`,
+    /// keep the ID they were written with instead. Ports on edge endpoints become `tailport`
+    /// and `headport` properties.
+    ///
+    /// `node` and `edge` default statements become defaults of the graph or subgraph they're
+    /// in, unless it already has nodes (or edges) they mustn't apply to, in which case they are
+    /// copied into each later node (or edge) instead.
+    pub fn from_dot(source: &str) -> Result<Graph, ParseError> {
+        Parser::new(source)?.parse()
+    }
+}
+
+impl FromStr for Graph {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Graph::from_dot(s)
+    }
+}
@@ -1,7 +1,8 @@
-use super::{Properties, ID, Node};
+use super::{GraphKind, ID, Node};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Edge {
     id: ID,
     start_node: Node,
@@ -28,11 +29,22 @@ impl Edge {
     pub fn end_node(&self) -> Node {
         self.end_node
     }
+
+    /// Whether this edge connects `start` to `end` in a graph of the given kind.
+    pub fn connects(&self, start: Node, end: Node, kind: GraphKind) -> bool {
+        (self.start_node == start && self.end_node == end)
+            || (kind == GraphKind::Undirected && self.start_node == end && self.end_node == start)
+    }
+
+    /// Format this edge with the edge operator for the given kind of graph.
+    pub fn to_dot(&self, kind: GraphKind) -> String {
+        format!("{} {} {}", self.start_node, kind.edge_op(), self.end_node)
+    }
 }
 
 impl Display for Edge {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{} -> {}", self.start_node, self.end_node)
+        write!(f, "{}", self.to_dot(GraphKind::Directed))
     }
 }
 
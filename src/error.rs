@@ -0,0 +1,79 @@
+use std::{error::Error, fmt::{Display, Formatter, Result as FmtResult}, io};
+use super::{Edge, ParseError};
+
+/// An error encountered while working with, rendering or saving a graph.
+#[derive(Debug)]
+pub enum GraphError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// The Graphviz program could not be found on the `PATH`.
+    GraphvizNotFound(String),
+    /// The Graphviz program exited unsuccessfully.
+    GraphvizFailed {
+        program: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+    /// The graph cannot be rendered as it is.
+    InvalidGraph(String),
+    /// The output of a Graphviz program could not be read.
+    InvalidOutput(String),
+    /// A cycle of edges with a negative total weight makes the cheapest path undefined.
+    NegativeCycle(Vec<Edge>),
+    /// The graph has a cycle of edges, so its nodes have no topological order.
+    Cycle(Vec<Edge>),
+}
+
+/// The result of a fallible graph operation.
+pub type Result<T, E = GraphError> = std::result::Result<T, E>;
+
+/// The names of a list of edges, separated by commas.
+fn edge_names(edges: &[Edge]) -> String {
+    edges.iter().map(|edge| format!("Edge_{}", edge.id())).collect::<Vec<_>>().join(", ")
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            GraphError::Io(err) => write!(f, "I/O error: {}", err),
+            GraphError::GraphvizNotFound(program) => {
+                write!(f, "could not find `{}`, is Graphviz installed?", program)
+            }
+            GraphError::GraphvizFailed { program, status, stderr } => {
+                match status {
+                    Some(code) => write!(f, "`{}` exited with status {}", program, code)?,
+                    None => write!(f, "`{}` was terminated by a signal", program)?,
+                }
+                if !stderr.trim().is_empty() {
+                    write!(f, ": {}", stderr.trim())?;
+                }
+                Ok(())
+            }
+            GraphError::InvalidGraph(message) => write!(f, "invalid graph: {}", message),
+            GraphError::InvalidOutput(message) => write!(f, "invalid Graphviz output: {}", message),
+            GraphError::NegativeCycle(edges) => write!(f, "cycle with a negative total weight: {}", edge_names(edges)),
+            GraphError::Cycle(edges) => write!(f, "graph has a cycle: {}", edge_names(edges)),
+        }
+    }
+}
+
+impl Error for GraphError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GraphError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GraphError {
+    fn from(err: io::Error) -> Self {
+        GraphError::Io(err)
+    }
+}
+
+impl From<ParseError> for GraphError {
+    fn from(err: ParseError) -> Self {
+        GraphError::InvalidGraph(err.to_string())
+    }
+}
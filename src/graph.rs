@@ -1,17 +1,59 @@
-use std::{collections::{BTreeMap, HashMap}, fmt::{Display, Formatter, Result as FmtResult}, path::Path};
-use super::{Node, Edge, Record, Properties, ID, Value};
+use std::{collections::{HashMap, HashSet}, fmt::{Display, Formatter, Result as FmtResult}};
+use serde::{Deserialize, Serialize};
+use super::{Node, Edge, Record, Subgraph, Properties, ID, Value};
+
+/// Whether the edges of a graph have a direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphKind {
+    #[default]
+    Directed,
+    Undirected,
+}
+
+impl GraphKind {
+    /// The keyword which opens a graph of this kind in DOT.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            GraphKind::Directed => "digraph",
+            GraphKind::Undirected => "graph",
+        }
+    }
+
+    /// The operator which connects two nodes in DOT.
+    pub fn edge_op(&self) -> &'static str {
+        match self {
+            GraphKind::Directed => "->",
+            GraphKind::Undirected => "--",
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq,  Default)]
 pub struct Graph {
+    kind: GraphKind,
+    strict: bool,
     properties: Properties,
+    node_defaults: Properties,
+    edge_defaults: Properties,
     order: HashMap<ID, usize>,
 
     nodes: HashMap<ID, Node>,
     edges: HashMap<ID, Edge>,
     records: HashMap<ID, Record>,
+    subgraphs: HashMap<ID, Subgraph>,
     node_properties: HashMap<ID, Properties>,
     edge_properties: HashMap<ID, Properties>,
     record_properties: HashMap<ID, Properties>,
+    subgraph_properties: HashMap<ID, Properties>,
+    subgraph_node_defaults: HashMap<ID, Properties>,
+    subgraph_edge_defaults: HashMap<ID, Properties>,
+    /// The subgraph that each node, edge, record or subgraph belongs to
+    parents: HashMap<ID, ID>,
+    /// The edges leaving and entering each node, in insertion order. Record fields are keyed
+    /// with their record, since fields of different records can share an ID
+    outgoing: HashMap<Node, Vec<ID>>,
+    incoming: HashMap<Node, Vec<ID>>,
 }
 
 impl Graph {
@@ -24,6 +66,65 @@ impl Graph {
         self
     }
 
+    /// Set attributes which every node uses unless it sets them itself.
+    pub fn with_node_defaults(mut self, mut defaults: Properties) -> Self {
+        self.node_defaults.append(&mut defaults);
+        self
+    }
+
+    /// Set attributes which every edge uses unless it sets them itself.
+    pub fn with_edge_defaults(mut self, mut defaults: Properties) -> Self {
+        self.edge_defaults.append(&mut defaults);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: GraphKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Strict graphs merge edges between the same pair of nodes instead of adding parallel edges.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    pub fn properties_mut(&mut self) -> &mut Properties {
+        &mut self.properties
+    }
+
+    pub fn node_defaults(&self) -> &Properties {
+        &self.node_defaults
+    }
+
+    pub fn node_defaults_mut(&mut self) -> &mut Properties {
+        &mut self.node_defaults
+    }
+
+    pub fn edge_defaults(&self) -> &Properties {
+        &self.edge_defaults
+    }
+
+    pub fn edge_defaults_mut(&mut self) -> &mut Properties {
+        &mut self.edge_defaults
+    }
+
+    pub fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.kind == GraphKind::Directed
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
     pub fn get_node_properties(&self, id: impl Into<ID>) -> Option<&Properties> {
         self.node_properties.get(&id.into())
     }
@@ -48,119 +149,536 @@ impl Graph {
         self.record_properties.get_mut(&id.into())
     }
 
+    pub fn get_subgraph_properties(&self, id: impl Into<ID>) -> Option<&Properties> {
+        self.subgraph_properties.get(&id.into())
+    }
+
+    pub fn get_subgraph_properties_mut(&mut self, id: impl Into<ID>) -> Option<&mut Properties> {
+        self.subgraph_properties.get_mut(&id.into())
+    }
+
+    pub fn get_subgraph_node_defaults(&self, id: impl Into<ID>) -> Option<&Properties> {
+        self.subgraph_node_defaults.get(&id.into())
+    }
+
+    pub fn get_subgraph_node_defaults_mut(&mut self, id: impl Into<ID>) -> Option<&mut Properties> {
+        self.subgraph_node_defaults.get_mut(&id.into())
+    }
+
+    pub fn get_subgraph_edge_defaults(&self, id: impl Into<ID>) -> Option<&Properties> {
+        self.subgraph_edge_defaults.get(&id.into())
+    }
+
+    pub fn get_subgraph_edge_defaults_mut(&mut self, id: impl Into<ID>) -> Option<&mut Properties> {
+        self.subgraph_edge_defaults.get_mut(&id.into())
+    }
+
+    /// Get the subgraph which directly contains the given node, edge, record or subgraph.
+    pub fn subgraph_of(&self, id: impl Into<ID>) -> Option<Subgraph> {
+        self.parents.get(&id.into()).and_then(|parent| self.subgraphs.get(parent)).copied()
+    }
+
+    /// Move a node, edge, record or subgraph into a subgraph, or back to the top level with `None`.
+    pub fn move_to_subgraph(&mut self, id: impl Into<ID>, subgraph: Option<ID>) {
+        let id = id.into();
+        match subgraph {
+            Some(parent) => self.parents.insert(id, parent),
+            None => self.parents.remove(&id),
+        };
+    }
+
+    /// Get a node's properties combined with the node defaults of the graph and its enclosing subgraphs.
+    pub fn resolved_node_properties(&self, id: impl Into<ID>) -> Properties {
+        let id = id.into();
+        let own = self.node_properties.get(&id).or_else(|| self.record_properties.get(&id));
+        let properties = self.node_defaults.join(&self.inherited_node_defaults(id));
+        match own {
+            Some(own) => properties.join(own),
+            None => properties,
+        }
+    }
+
+    /// Get an edge's properties combined with the edge defaults of the graph and its enclosing subgraphs.
+    pub fn resolved_edge_properties(&self, id: impl Into<ID>) -> Properties {
+        let id = id.into();
+        let properties = self.edge_defaults.join(&self.inherited_edge_defaults(id));
+        match self.edge_properties.get(&id) {
+            Some(own) => properties.join(own),
+            None => properties,
+        }
+    }
+
+    /// Get the node defaults of the subgraphs enclosing an item, with inner subgraphs' defaults taking precedence.
+    pub(crate) fn inherited_node_defaults(&self, id: impl Into<ID>) -> Properties {
+        self.inherit(id.into(), &self.subgraph_node_defaults)
+    }
+
+    /// Get the edge defaults of the subgraphs enclosing an item, with inner subgraphs' defaults taking precedence.
+    pub(crate) fn inherited_edge_defaults(&self, id: impl Into<ID>) -> Properties {
+        self.inherit(id.into(), &self.subgraph_edge_defaults)
+    }
+
+    fn inherit(&self, id: ID, subgraph_defaults: &HashMap<ID, Properties>) -> Properties {
+        let mut chain = Vec::new();
+        let mut current = self.parent_of(id);
+        while let Some(subgraph) = current {
+            if chain.contains(&subgraph) {
+                break;
+            }
+            chain.push(subgraph);
+            current = self.parent_of(subgraph);
+        }
+        let mut properties = Properties::new();
+        for subgraph in chain.iter().rev() {
+            if let Some(defaults) = subgraph_defaults.get(subgraph) {
+                properties = properties.join(defaults);
+            }
+        }
+        properties
+    }
+
+    /// Insert a node. A node replacing one with the same ID keeps its place in the order.
     pub fn insert_node(&mut self, node: Node, properties: Properties) {
-        self.order.insert(node.id(), self.order.len());
+        self.keep_order(node.id());
         self.nodes.insert(node.id(), node);
         self.node_properties.insert(node.id(), properties);
     }
 
-    pub fn insert_edge(&mut self, edge: Edge, properties: Properties) {
-        self.order.insert(edge.id(), self.order.len());
+    /// Insert an edge, returning the edge which ends up in the graph.
+    ///
+    /// In a strict graph, an edge between two already connected nodes is merged into the
+    /// existing edge, and the existing edge is returned.
+    pub fn insert_edge(&mut self, edge: Edge, mut properties: Properties) -> Edge {
+        if self.strict {
+            if let Some(existing) = self.find_edge(edge.start_node(), edge.end_node()) {
+                if existing.id() != edge.id() {
+                    self.edge_properties.entry(existing.id()).or_default().append(&mut properties);
+                    return existing;
+                }
+            }
+        }
+        if let Some(replaced) = self.edges.get(&edge.id()).copied() {
+            self.unlink_edge(replaced);
+        }
+        self.keep_order(edge.id());
         self.edges.insert(edge.id(), edge);
         self.edge_properties.insert(edge.id(), properties);
+        self.link_edge(edge);
+        edge
+    }
+
+    /// Add an edge to the adjacency index of its nodes, keeping their lists in insertion order.
+    fn link_edge(&mut self, edge: Edge) {
+        let order = &self.order;
+        for (index, node) in [(&mut self.outgoing, edge.start_node()), (&mut self.incoming, edge.end_node())] {
+            let edges = index.entry(node).or_default();
+            let place = edges.partition_point(|other| order[other] < order[&edge.id()]);
+            edges.insert(place, edge.id());
+        }
+    }
+
+    /// Remove an edge from the adjacency index of its nodes.
+    fn unlink_edge(&mut self, edge: Edge) {
+        for (index, node) in [(&mut self.outgoing, edge.start_node()), (&mut self.incoming, edge.end_node())] {
+            if let Some(edges) = index.get_mut(&node) {
+                edges.retain(|&id| id != edge.id());
+                if edges.is_empty() {
+                    index.remove(&node);
+                }
+            }
+        }
     }
 
     pub fn insert_record(&mut self, record: Record, properties: Properties) {
-        self.order.insert(record.id(), self.order.len());
+        self.keep_order(record.id());
         let id = record.id();
         self.records.insert(id, record);
         self.record_properties.insert(id, properties);
     }
 
-    pub fn new_node(&mut self, id: impl Into<ID>) -> NodeBuilder {
+    pub fn insert_subgraph(&mut self, subgraph: Subgraph, properties: Properties) {
+        self.keep_order(subgraph.id());
+        self.subgraphs.insert(subgraph.id(), subgraph);
+        self.subgraph_properties.insert(subgraph.id(), properties);
+        self.subgraph_node_defaults.entry(subgraph.id()).or_default();
+        self.subgraph_edge_defaults.entry(subgraph.id()).or_default();
+    }
+
+    /// Give a new item the next place in the order, keeping the place of an item it replaces.
+    fn keep_order(&mut self, id: ID) {
+        let next = self.order.len();
+        self.order.entry(id).or_insert(next);
+    }
+
+    /// Forget the places of removed items, moving the items after them up so the places stay
+    /// numbered from zero. Places shared with items of another kind which are still in the
+    /// graph are kept.
+    fn forget_order(&mut self, ids: &[ID]) {
+        let mut removed: Vec<usize> = ids.iter()
+            .filter(|id| !self.nodes.contains_key(id) && !self.edges.contains_key(id) && !self.records.contains_key(id) && !self.subgraphs.contains_key(id))
+            .filter_map(|id| self.order.remove(id))
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        removed.sort_unstable();
+        for place in self.order.values_mut() {
+            *place -= removed.partition_point(|&removed| removed < *place);
+        }
+    }
+
+    /// Remove an edge, returning it and its properties.
+    pub fn remove_edge(&mut self, id: impl Into<ID>) -> Option<(Edge, Properties)> {
+        let id = id.into();
+        let edge = self.edges.remove(&id)?;
+        self.unlink_edge(edge);
+        let properties = self.edge_properties.remove(&id).unwrap_or_default();
+        self.parents.remove(&id);
+        self.forget_order(&[id]);
+        Some((edge, properties))
+    }
+
+    /// Remove the edges leaving or entering some nodes, returning their IDs.
+    fn remove_attached_edges(&mut self, nodes: &[Node]) -> Vec<ID> {
+        let mut edges: Vec<ID> = nodes.iter()
+            .flat_map(|node| self.outgoing.get(node).into_iter().chain(self.incoming.get(node)).flatten())
+            .copied()
+            .collect();
+        edges.sort();
+        edges.dedup();
+        for id in &edges {
+            let edge = self.edges.remove(id).unwrap();
+            self.unlink_edge(edge);
+            self.edge_properties.remove(id);
+            self.parents.remove(id);
+        }
+        edges
+    }
+
+    /// Remove a node and every edge leaving or entering it, returning the node and its properties.
+    pub fn remove_node(&mut self, id: impl Into<ID>) -> Option<(Node, Properties)> {
+        let id = id.into();
+        let node = self.nodes.remove(&id)?;
+        let properties = self.node_properties.remove(&id).unwrap_or_default();
+        self.parents.remove(&id);
+        let mut removed = self.remove_attached_edges(&[Node::new(id)]);
+        removed.push(id);
+        self.forget_order(&removed);
+        Some((node, properties))
+    }
+
+    /// Remove a record and every edge attached to its fields, returning the record and its properties.
+    pub fn remove_record(&mut self, id: impl Into<ID>) -> Option<(Record, Properties)> {
+        let id = id.into();
+        let record = self.records.remove(&id)?;
+        let properties = self.record_properties.remove(&id).unwrap_or_default();
+        self.parents.remove(&id);
+        let fields: Vec<Node> = record.nodes().columns().flat_map(|column| column.cells().map(|(node, _)| node)).collect();
+        let mut removed = self.remove_attached_edges(&fields);
+        removed.push(id);
+        self.forget_order(&removed);
+        Some((record, properties))
+    }
+
+    /// Remove a subgraph, moving everything in it to the subgraph containing it (or the top level).
+    /// Returns the subgraph and its properties.
+    pub fn remove_subgraph(&mut self, id: impl Into<ID>) -> Option<(Subgraph, Properties)> {
+        let id = id.into();
+        let subgraph = self.subgraphs.remove(&id)?;
+        let properties = self.subgraph_properties.remove(&id).unwrap_or_default();
+        self.subgraph_node_defaults.remove(&id);
+        self.subgraph_edge_defaults.remove(&id);
+        let parent = self.parents.remove(&id);
+        let members: Vec<ID> = self.parents.iter().filter(|(_, &p)| p == id).map(|(&member, _)| member).collect();
+        for member in members {
+            self.move_to_subgraph(member, parent);
+        }
+        self.forget_order(&[id]);
+        Some((subgraph, properties))
+    }
+
+    /// Move an edge to connect different nodes, keeping its ID, properties and place in the order.
+    ///
+    /// In a strict graph, an edge moved between two already connected nodes is merged into the
+    /// existing edge like in `insert_edge`, and the existing edge is returned.
+    pub fn set_edge_endpoints(&mut self, id: impl Into<ID>, start_node: impl Into<Node>, end_node: impl Into<Node>) -> Option<Edge> {
+        let id = id.into();
+        let (start_node, end_node) = (start_node.into(), end_node.into());
+        let old = *self.edges.get(&id)?;
+        if self.strict {
+            let existing = self.edges_between(start_node, end_node).into_iter().find(|edge| edge.id() != id);
+            if let Some(existing) = existing {
+                let (_, mut properties) = self.remove_edge(id)?;
+                self.edge_properties.entry(existing.id()).or_default().append(&mut properties);
+                return Some(existing);
+            }
+        }
+        let edge = Edge::new(id, start_node, end_node);
+        self.unlink_edge(old);
+        self.edges.insert(id, edge);
+        self.link_edge(edge);
+        Some(edge)
+    }
+
+    pub fn new_node(&mut self, id: impl Into<ID>) -> NodeBuilder<'_> {
         NodeBuilder::new(id, self)
     }
 
-    pub fn new_edge(&mut self, start_node: impl Into<Node>, end_node: impl Into<Node>) -> EdgeBuilder {
+    pub fn new_edge(&mut self, start_node: impl Into<Node>, end_node: impl Into<Node>) -> EdgeBuilder<'_> {
         EdgeBuilder::new(start_node, end_node, self)
     }
 
-    pub fn new_record(&mut self, id: impl Into<ID>, width: usize, height: usize) -> RecordBuilder {
+    pub fn new_record(&mut self, id: impl Into<ID>, width: usize, height: usize) -> RecordBuilder<'_> {
         RecordBuilder::new(id, width, height, self)
     }
 
-    fn sorted_records(&self) -> Vec<&Record> {
+    pub fn new_subgraph(&mut self, id: impl Into<ID>) -> SubgraphBuilder<'_> {
+        SubgraphBuilder::new(Subgraph::new(id), self)
+    }
+
+    pub fn new_cluster(&mut self, id: impl Into<ID>) -> SubgraphBuilder<'_> {
+        SubgraphBuilder::new(Subgraph::cluster(id), self)
+    }
+
+    /// Find the first edge from `start` to `end`. Undirected graphs also match edges from `end` to `start`.
+    pub fn find_edge(&self, start: impl Into<Node>, end: impl Into<Node>) -> Option<Edge> {
+        let (start, end) = (start.into(), end.into());
+        self.out_edges(start).into_iter().find(|edge| edge.connects(start, end, self.kind))
+    }
+
+    /// Get every edge from `start` to `end` in insertion order. Undirected graphs also include edges from `end` to `start`.
+    pub fn edges_between(&self, start: impl Into<Node>, end: impl Into<Node>) -> Vec<Edge> {
+        let (start, end) = (start.into(), end.into());
+        self.out_edges(start).into_iter().filter(|edge| edge.connects(start, end, self.kind)).collect()
+    }
+
+    /// Iterate over the nodes in insertion order.
+    pub fn nodes(&self) -> impl Iterator<Item=&Node> {
+        self.sorted_nodes().into_iter()
+    }
+
+    /// Iterate over the edges in insertion order.
+    pub fn edges(&self) -> impl Iterator<Item=&Edge> {
+        self.sorted_edges().into_iter()
+    }
+
+    /// Iterate over the records in insertion order.
+    pub fn records(&self) -> impl Iterator<Item=&Record> {
+        self.sorted_records().into_iter()
+    }
+
+    /// Iterate over the subgraphs in insertion order.
+    pub fn subgraphs(&self) -> impl Iterator<Item=&Subgraph> {
+        self.sorted_subgraphs().into_iter()
+    }
+
+    /// The edges in one direction from a node, in insertion order. Undirected graphs use both directions.
+    fn adjacent_edges(&self, node: Node, outgoing: bool) -> Vec<Edge> {
+        let (forward, backward) = if outgoing { (&self.outgoing, &self.incoming) } else { (&self.incoming, &self.outgoing) };
+        let mut ids: Vec<ID> = forward.get(&node).cloned().unwrap_or_default();
+        if !self.is_directed() {
+            ids.extend(backward.get(&node).into_iter().flatten());
+            ids.sort_by_key(|id| self.order[id]);
+            // Self-loops are in both lists
+            ids.dedup();
+        }
+        ids.iter().map(|id| self.edges[id]).collect()
+    }
+
+    /// Every node edges can be attached to: the nodes in insertion order, then the other ends of
+    /// edges, like record fields, in the order of their edges.
+    pub(crate) fn endpoints(&self) -> Vec<Node> {
+        let mut seen = HashSet::new();
+        let ends = self.edges().flat_map(|edge| [edge.start_node(), edge.end_node()]);
+        self.nodes().copied().chain(ends).filter(|&node| seen.insert(node)).collect()
+    }
+
+    /// The edges starting (or ending) at a node as they are written, even in undirected graphs.
+    pub(crate) fn written_edges(&self, node: Node, outgoing: bool) -> Vec<Edge> {
+        let index = if outgoing { &self.outgoing } else { &self.incoming };
+        index.get(&node).into_iter().flatten().map(|id| self.edges[id]).collect()
+    }
+
+    /// The edges leaving a node, in insertion order. In undirected graphs, these are all of its edges.
+    ///
+    /// Edges from record fields are found from the field's node, like `record[0][1]`.
+    pub fn out_edges(&self, node: impl Into<Node>) -> Vec<Edge> {
+        self.adjacent_edges(node.into(), true)
+    }
+
+    /// The edges entering a node, in insertion order. In undirected graphs, these are all of its edges.
+    pub fn in_edges(&self, node: impl Into<Node>) -> Vec<Edge> {
+        self.adjacent_edges(node.into(), false)
+    }
+
+    /// The nodes at the other ends of a list of edges, each listed once.
+    fn other_ends(node: Node, edges: Vec<Edge>) -> Vec<Node> {
+        let mut nodes: Vec<Node> = Vec::new();
+        for edge in edges {
+            let other = if edge.start_node() == node { edge.end_node() } else { edge.start_node() };
+            if !nodes.contains(&other) {
+                nodes.push(other);
+            }
+        }
+        nodes
+    }
+
+    /// The nodes which edges from a node lead to, in the order of those edges.
+    /// In undirected graphs, these are all of its neighbors.
+    pub fn successors(&self, node: impl Into<Node>) -> Vec<Node> {
+        let node = node.into();
+        Self::other_ends(node, self.out_edges(node))
+    }
+
+    /// The nodes with edges leading to a node, in the order of those edges.
+    /// In undirected graphs, these are all of its neighbors.
+    pub fn predecessors(&self, node: impl Into<Node>) -> Vec<Node> {
+        let node = node.into();
+        Self::other_ends(node, self.in_edges(node))
+    }
+
+    /// The number of edges leaving a node. In undirected graphs, this is the number of edges it has.
+    pub fn out_degree(&self, node: impl Into<Node>) -> usize {
+        let node = node.into();
+        match self.kind {
+            GraphKind::Directed => self.outgoing.get(&node).map_or(0, Vec::len),
+            GraphKind::Undirected => self.out_edges(node).len(),
+        }
+    }
+
+    /// The number of edges entering a node. In undirected graphs, this is the number of edges it has.
+    pub fn in_degree(&self, node: impl Into<Node>) -> usize {
+        let node = node.into();
+        match self.kind {
+            GraphKind::Directed => self.incoming.get(&node).map_or(0, Vec::len),
+            GraphKind::Undirected => self.in_edges(node).len(),
+        }
+    }
+
+    /// The number of edge ends at a node, counting self-loops twice.
+    pub fn degree(&self, node: impl Into<Node>) -> usize {
+        let node = node.into();
+        self.outgoing.get(&node).map_or(0, Vec::len) + self.incoming.get(&node).map_or(0, Vec::len)
+    }
+
+    pub(crate) fn sorted_records(&self) -> Vec<&Record> {
         let mut records: Vec<&Record> = self.records.values().collect();
         records.sort_by_key(|record| self.order.get(&record.id()).unwrap());
         records
     }
 
-    fn sorted_nodes(&self) -> Vec<&Node> {
+    pub(crate) fn sorted_nodes(&self) -> Vec<&Node> {
         let mut nodes: Vec<&Node> = self.nodes.values().collect();
         nodes.sort_by_key(|node| self.order.get(&node.id()).unwrap());
         nodes
     }
 
-    fn sorted_edges(&self) -> Vec<&Edge> {
+    pub(crate) fn sorted_edges(&self) -> Vec<&Edge> {
         let mut edges: Vec<&Edge> = self.edges.values().collect();
         edges.sort_by_key(|edge| self.order.get(&edge.id()).unwrap());
         edges
     }
 
+    pub(crate) fn sorted_subgraphs(&self) -> Vec<&Subgraph> {
+        let mut subgraphs: Vec<&Subgraph> = self.subgraphs.values().collect();
+        subgraphs.sort_by_key(|subgraph| self.order.get(&subgraph.id()).unwrap());
+        subgraphs
+    }
+
+    /// The place of a node, edge, record or subgraph in the order they were inserted.
+    pub(crate) fn order_of(&self, id: ID) -> Option<usize> {
+        self.order.get(&id).copied()
+    }
+
+    /// The subgraph an item is emitted in, ignoring subgraphs which were never inserted.
+    pub(crate) fn parent_of(&self, id: ID) -> Option<ID> {
+        self.subgraph_of(id).map(|subgraph| subgraph.id())
+    }
+
+    /// The edges in the order `to_dot` writes them, which is the order Graphviz numbers them in.
+    pub(crate) fn edges_in_dot_order(&self) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        self.collect_dot_edges(None, &mut edges);
+        edges
+    }
+
+    fn collect_dot_edges(&self, parent: Option<ID>, edges: &mut Vec<Edge>) {
+        for subgraph in self.sorted_subgraphs() {
+            if self.parent_of(subgraph.id()) == parent {
+                self.collect_dot_edges(Some(subgraph.id()), edges);
+            }
+        }
+        edges.extend(self.sorted_edges().into_iter().filter(|edge| self.parent_of(edge.id()) == parent));
+    }
+
     pub fn to_dot(&self) -> String {
         let mut dot = String::new();
-        dot.push_str("digraph {\n");
+        if self.strict {
+            dot.push_str("strict ");
+        }
+        dot.push_str(&format!("{} {{\n", self.kind.keyword()));
         // Graph properties
         dot.push_str(&format!("  graph {};\n", self.properties));
+        write_defaults(&mut dot, &self.node_defaults, &self.edge_defaults, "  ");
+        self.write_dot_body(&mut dot, None, 1);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Write every item directly contained in `parent`, recursing into its subgraphs.
+    fn write_dot_body(&self, dot: &mut String, parent: Option<ID>, depth: usize) {
+        let indent = "  ".repeat(depth);
         for record in self.sorted_records() {
             let id = record.id();
+            if self.parent_of(id) != parent {
+                continue;
+            }
             let properties = self.record_properties.get(&id).unwrap();
-            dot.push_str(&format!("  {} {};\n", record, properties));
+            dot.push_str(&format!("{}{} {};\n", indent, record, properties));
         }
         for node in self.sorted_nodes() {
             let id = node.id();
+            if self.parent_of(id) != parent {
+                continue;
+            }
             let properties = self.node_properties.get(&id).unwrap();
-            dot.push_str(&format!("  {} {};\n", node, properties));
+            dot.push_str(&format!("{}{} {};\n", indent, node, properties));
+        }
+        for subgraph in self.sorted_subgraphs() {
+            let id = subgraph.id();
+            if self.parent_of(id) != parent {
+                continue;
+            }
+            let properties = self.subgraph_properties.get(&id).unwrap();
+            dot.push_str(&format!("{}subgraph {} {{\n", indent, subgraph));
+            dot.push_str(&format!("{}  graph {};\n", indent, properties));
+            write_defaults(
+                dot,
+                &self.subgraph_node_defaults[&id],
+                &self.subgraph_edge_defaults[&id],
+                &format!("{}  ", indent),
+            );
+            self.write_dot_body(dot, Some(id), depth + 1);
+            dot.push_str(&format!("{}}}\n", indent));
         }
         for edge in self.sorted_edges() {
             let id = edge.id();
+            if self.parent_of(id) != parent {
+                continue;
+            }
             let properties = self.edge_properties.get(&id).unwrap();
-            dot.push_str(&format!("  {} {};\n", edge, properties));
+            dot.push_str(&format!("{}{} {};\n", indent, edge.to_dot(self.kind), properties));
         }
-        dot.push_str("}\n");
-        dot
     }
+}
 
-    pub fn save_png(&self, path: &Path) {
-        let dot = self.to_dot();
-        let dot_path = path.with_extension("dot");
-        std::fs::write(&dot_path, dot).unwrap();
-        let output = std::process::Command::new("dot")
-            .arg("-Tpng")
-            .arg(dot_path)
-            .arg("-o")
-            .arg(path)
-            .output()
-            .unwrap();
-        assert!(output.status.success());
-    }
-
-    pub fn save_svg(&self, path: &Path) {
-        let dot = self.to_dot();
-        let dot_path = path.with_extension("dot");
-        std::fs::write(&dot_path, dot).unwrap();
-        let output = std::process::Command::new("dot")
-            .arg("-Tsvg")
-            .arg(dot_path)
-            .arg("-o")
-            .arg(path)
-            .output()
-            .unwrap();
-        assert!(output.status.success());
-    }
-
-    pub fn build_circuit_diagram(&self, path: &Path) {
-        let dot = self.to_dot();
-        let dot_path = path.with_extension("dot");
-        std::fs::write(&dot_path, dot).unwrap();
-        let output = std::process::Command::new("neato")
-            .arg("-Tsvg")
-            .arg(dot_path)
-            .arg("-o")
-            .arg(path)
-            .output()
-            .unwrap();
-        assert!(output.status.success());
+/// Write `node` and `edge` default attribute statements, skipping empty ones.
+fn write_defaults(dot: &mut String, node_defaults: &Properties, edge_defaults: &Properties, indent: &str) {
+    if !node_defaults.is_empty() {
+        dot.push_str(&format!("{}node {};\n", indent, node_defaults));
+    }
+    if !edge_defaults.is_empty() {
+        dot.push_str(&format!("{}edge {};\n", indent, edge_defaults));
     }
 }
 
@@ -193,6 +711,7 @@ impl Display for Graph {
 pub struct NodeBuilder<'a> {
     graph: &'a mut Graph,
     id: ID,
+    subgraph: Option<ID>,
     properties: Properties,
 }
 
@@ -201,10 +720,16 @@ impl<'a> NodeBuilder<'a> {
         NodeBuilder {
             graph,
             id: id.into(),
+            subgraph: None,
             properties: Properties::new(),
         }
     }
 
+    pub fn in_subgraph(mut self, subgraph: impl Into<ID>) -> Self {
+        self.subgraph = Some(subgraph.into());
+        self
+    }
+
     pub fn with_property(mut self, key: &str, value: impl Into<Value>) -> Self {
         self.properties.set(key.to_string(), value);
         self
@@ -218,6 +743,7 @@ impl<'a> NodeBuilder<'a> {
     pub fn finalize(self) -> Node {
         let node = Node::new(self.id);
         self.graph.insert_node(node, self.properties);
+        self.graph.move_to_subgraph(node, self.subgraph);
         node
     }
 }
@@ -227,6 +753,7 @@ pub struct EdgeBuilder<'a> {
     id: ID,
     start_node: Node,
     end_node: Node,
+    subgraph: Option<ID>,
     properties: Properties,
 }
 
@@ -234,15 +761,32 @@ impl<'a> EdgeBuilder<'a> {
     pub fn new(start_node: impl Into<Node>, end_node: impl Into<Node>, graph: &'a mut Graph) -> Self {
         let start_node = start_node.into();
         let end_node = end_node.into();
+        // Parallel edges between the same nodes each get their own ID
+        let mut id = start_node.id() + end_node.id();
+        while graph.edges.contains_key(&id) {
+            id = id.next_id();
+        }
         EdgeBuilder {
             graph,
-            id: start_node.id() + end_node.id(),
-            start_node: start_node.into(),
-            end_node: end_node.into(),
+            id,
+            start_node,
+            end_node,
+            subgraph: None,
             properties: Properties::new(),
         }
     }
 
+    /// Use an explicit ID for this edge. An existing edge with the same ID is replaced.
+    pub fn with_id(mut self, id: impl Into<ID>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    pub fn in_subgraph(mut self, subgraph: impl Into<ID>) -> Self {
+        self.subgraph = Some(subgraph.into());
+        self
+    }
+
     pub fn with_property(mut self, key: &str, value: impl Into<Value>) -> Self {
         self.properties.set(key.to_string(), value);
         self
@@ -255,8 +799,11 @@ impl<'a> EdgeBuilder<'a> {
     
     pub fn finalize(self) -> Edge {
         let edge = Edge::new(self.id, self.start_node, self.end_node);
-        self.graph.insert_edge(edge, self.properties);
-        edge
+        let inserted = self.graph.insert_edge(edge, self.properties);
+        if inserted == edge {
+            self.graph.move_to_subgraph(edge, self.subgraph);
+        }
+        inserted
     }
 }
 
@@ -264,6 +811,7 @@ impl<'a> EdgeBuilder<'a> {
 pub struct RecordBuilder<'a> {
     graph: &'a mut Graph,
     record: Record,
+    subgraph: Option<ID>,
     properties: Properties,
 }
 
@@ -273,10 +821,16 @@ impl<'a> RecordBuilder<'a> {
         RecordBuilder {
             graph,
             record,
+            subgraph: None,
             properties: Properties::new(),
         }
     }
 
+    pub fn in_subgraph(mut self, subgraph: impl Into<ID>) -> Self {
+        self.subgraph = Some(subgraph.into());
+        self
+    }
+
     pub fn insert(mut self, x: usize, y: usize, label: impl ToString) -> Self {
         self.record.insert_node(x, y, Node::new(ID::from(x) + ID::from(y) + ID::from(label.to_string())), label);
         self
@@ -294,6 +848,89 @@ impl<'a> RecordBuilder<'a> {
 
     pub fn finalize(self) -> Record {
         self.graph.insert_record(self.record.clone(), self.properties);
+        self.graph.move_to_subgraph(&self.record, self.subgraph);
         self.record
     }
+}
+
+pub struct SubgraphBuilder<'a> {
+    graph: &'a mut Graph,
+    subgraph: Subgraph,
+    parent: Option<ID>,
+    properties: Properties,
+    node_defaults: Properties,
+    edge_defaults: Properties,
+}
+
+impl<'a> SubgraphBuilder<'a> {
+    pub fn new(subgraph: Subgraph, graph: &'a mut Graph) -> Self {
+        SubgraphBuilder {
+            graph,
+            subgraph,
+            parent: None,
+            properties: Properties::new(),
+            node_defaults: Properties::new(),
+            edge_defaults: Properties::new(),
+        }
+    }
+
+    pub fn in_subgraph(mut self, subgraph: impl Into<ID>) -> Self {
+        self.parent = Some(subgraph.into());
+        self
+    }
+
+    pub fn id(&self) -> ID {
+        self.subgraph.id()
+    }
+
+    pub fn with_property(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.properties.set(key.to_string(), value);
+        self
+    }
+
+    pub fn with_properties(mut self, mut properties: Properties) -> Self {
+        self.properties.append(&mut properties);
+        self
+    }
+
+    /// Set attributes which nodes in this subgraph use unless they set them themselves.
+    pub fn with_node_defaults(mut self, mut defaults: Properties) -> Self {
+        self.node_defaults.append(&mut defaults);
+        self
+    }
+
+    /// Set attributes which edges in this subgraph use unless they set them themselves.
+    pub fn with_edge_defaults(mut self, mut defaults: Properties) -> Self {
+        self.edge_defaults.append(&mut defaults);
+        self
+    }
+
+    pub fn new_node(&mut self, id: impl Into<ID>) -> NodeBuilder<'_> {
+        NodeBuilder::new(id, self.graph).in_subgraph(self.subgraph)
+    }
+
+    pub fn new_edge(&mut self, start_node: impl Into<Node>, end_node: impl Into<Node>) -> EdgeBuilder<'_> {
+        EdgeBuilder::new(start_node, end_node, self.graph).in_subgraph(self.subgraph)
+    }
+
+    pub fn new_record(&mut self, id: impl Into<ID>, width: usize, height: usize) -> RecordBuilder<'_> {
+        RecordBuilder::new(id, width, height, self.graph).in_subgraph(self.subgraph)
+    }
+
+    pub fn new_subgraph(&mut self, id: impl Into<ID>) -> SubgraphBuilder<'_> {
+        SubgraphBuilder::new(Subgraph::new(id), self.graph).in_subgraph(self.subgraph)
+    }
+
+    pub fn new_cluster(&mut self, id: impl Into<ID>) -> SubgraphBuilder<'_> {
+        SubgraphBuilder::new(Subgraph::cluster(id), self.graph).in_subgraph(self.subgraph)
+    }
+
+    pub fn finalize(self) -> Subgraph {
+        let id = self.subgraph.id();
+        self.graph.insert_subgraph(self.subgraph, self.properties);
+        self.graph.subgraph_node_defaults.insert(id, self.node_defaults);
+        self.graph.subgraph_edge_defaults.insert(id, self.edge_defaults);
+        self.graph.move_to_subgraph(self.subgraph, self.parent);
+        self.subgraph
+    }
 }
\ No newline at end of file
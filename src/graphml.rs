@@ -0,0 +1,609 @@
+use std::collections::HashMap;
+use super::{Graph, GraphKind, Node, ParseError, Properties, Record, Subgraph, Value, ID};
+use super::dot::id_of_name;
+use super::svg::escape_xml;
+
+/// The GraphML type of a value, or `None` for values which aren't written.
+fn graphml_type(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Bool(_) => Some("boolean"),
+        Value::I32(_) => Some("int"),
+        Value::F64(_) => Some("double"),
+        Value::String(_) | Value::Html(_) => Some("string"),
+        Value::Nothing => None,
+    }
+}
+
+/// The type of a key holding values of both types.
+fn merge_types(a: &'static str, b: &'static str) -> &'static str {
+    match (a, b) {
+        _ if a == b => a,
+        ("int" | "double", "int" | "double") => "double",
+        _ => "string",
+    }
+}
+
+/// The text of a value in a `<data>` or `<default>` element.
+fn data_text(value: &Value) -> String {
+    match value {
+        Value::String(s) | Value::Html(s) => s.clone(),
+        Value::F64(n) if n.is_infinite() => if *n > 0.0 { "INF" } else { "-INF" }.to_string(),
+        Value::F64(n) if n.is_nan() => "NaN".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Read the text of a `<data>` element as a value of a GraphML type.
+fn parse_data(text: &str, kind: &str) -> Option<Value> {
+    let text = text.trim();
+    match kind {
+        "boolean" => match text.to_ascii_lowercase().as_str() {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        "int" | "long" => text.parse().ok().map(Value::I32).or_else(|| text.parse::<i64>().ok().map(|n| Value::F64(n as f64))),
+        "float" | "double" => match text {
+            "INF" => Some(Value::F64(f64::INFINITY)),
+            "-INF" => Some(Value::F64(f64::NEG_INFINITY)),
+            text => text.parse().ok().map(Value::F64),
+        },
+        _ => None,
+    }
+}
+
+/// The `<key>` declarations of a graph: one per domain and property name.
+#[derive(Default)]
+struct Keys {
+    keys: Vec<(&'static str, String, &'static str, Option<Value>)>,
+    index: HashMap<(&'static str, String), usize>,
+}
+
+impl Keys {
+    fn add(&mut self, domain: &'static str, properties: &Properties) {
+        for (name, value) in properties {
+            let Some(kind) = graphml_type(value) else { continue };
+            match self.index.get(&(domain, name.clone())) {
+                Some(&i) => self.keys[i].2 = merge_types(self.keys[i].2, kind),
+                None => {
+                    self.index.insert((domain, name.clone()), self.keys.len());
+                    self.keys.push((domain, name.clone(), kind, None));
+                }
+            }
+        }
+    }
+
+    /// Make the values of `defaults` the defaults of their keys.
+    fn add_defaults(&mut self, domain: &'static str, defaults: &Properties) {
+        self.add(domain, defaults);
+        for (name, value) in defaults {
+            if let Some(&i) = self.index.get(&(domain, name.clone())) {
+                self.keys[i].3 = Some(value.clone());
+            }
+        }
+    }
+
+    fn write(&self, graphml: &mut String) {
+        for (i, (domain, name, kind, default)) in self.keys.iter().enumerate() {
+            let key = format!("  <key id=\"d{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"", i, domain, escape_xml(name), kind);
+            match default {
+                Some(default) => graphml.push_str(&format!("{}>\n    <default>{}</default>\n  </key>\n", key, escape_xml(&data_text(default)))),
+                None => graphml.push_str(&format!("{}/>\n", key)),
+            }
+        }
+    }
+
+    /// The `<data>` elements for some properties, one per line.
+    fn data(&self, domain: &'static str, properties: &Properties, indent: &str) -> String {
+        let mut data = String::new();
+        for (name, value) in properties {
+            if let Some(i) = self.index.get(&(domain, name.clone())).filter(|_| graphml_type(value).is_some()) {
+                data.push_str(&format!("{}<data key=\"d{}\">{}</data>\n", indent, i, escape_xml(&data_text(value))));
+            }
+        }
+        data
+    }
+
+    /// Write an element holding the `<data>` for some properties, closing it if there are none.
+    fn write_element(&self, graphml: &mut String, start: &str, domain: &'static str, properties: &Properties, indent: &str) {
+        let data = self.data(domain, properties, &format!("{}  ", indent));
+        if data.is_empty() {
+            graphml.push_str(&format!("{}<{}/>\n", indent, start));
+        } else {
+            let name = start.split(' ').next().unwrap_or_default();
+            graphml.push_str(&format!("{}<{}>\n{}{}</{}>\n", indent, start, data, indent, name));
+        }
+    }
+}
+
+/// The properties of a record field, which GraphML keeps on the port.
+fn port_properties(label: &str, column: usize, row: usize) -> Properties {
+    Properties::from_iter([("label", Value::from(label)), ("column", Value::I32(column as i32)), ("row", Value::I32(row as i32))])
+}
+
+impl Graph {
+    /// Write the graph as GraphML, for tools like Gephi and yEd.
+    ///
+    /// Every property name gets a `<key>` typed from its values, with the graph's node and
+    /// edge defaults as the keys' defaults. Nodes and edges keep their DOT names as their IDs,
+    /// records keep their fields as ports, and subgraphs become nodes holding nested graphs.
+    /// GraphML has no defaults for parts of a graph, so the node and edge defaults of subgraphs
+    /// are written into the data of their members. HTML-like labels are written as plain strings.
+    pub fn to_graphml(&self) -> String {
+        let mut keys = Keys::default();
+        keys.add("graph", self.properties());
+        keys.add_defaults("node", self.node_defaults());
+        keys.add_defaults("edge", self.edge_defaults());
+        for record in self.sorted_records() {
+            keys.add("node", &self.graphml_node_data(record.id()));
+        }
+        for node in self.sorted_nodes() {
+            keys.add("node", &self.graphml_node_data(node.id()));
+        }
+        for subgraph in self.sorted_subgraphs() {
+            keys.add("node", self.get_subgraph_properties(subgraph.id()).unwrap());
+        }
+        for edge in self.sorted_edges() {
+            keys.add("edge", &self.graphml_edge_data(edge.id()));
+        }
+        if !self.sorted_records().is_empty() {
+            keys.add("port", &port_properties("", 0, 0));
+        }
+
+        let mut graphml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+        keys.write(&mut graphml);
+        graphml.push_str(&format!("  <graph id=\"G\" edgedefault=\"{}\">\n", self.edgedefault()));
+        graphml.push_str(&keys.data("graph", self.properties(), "    "));
+        self.write_graphml_body(&mut graphml, &keys, None, 2);
+        graphml.push_str("  </graph>\n</graphml>\n");
+        graphml
+    }
+
+    /// The data of a node or record: its properties over the node defaults of its subgraphs.
+    fn graphml_node_data(&self, id: ID) -> Properties {
+        let own = self.get_node_properties(id).or_else(|| self.get_record_properties(id));
+        self.inherited_node_defaults(id).join(own.unwrap())
+    }
+
+    /// The data of an edge: its properties over the edge defaults of its subgraphs.
+    fn graphml_edge_data(&self, id: ID) -> Properties {
+        self.inherited_edge_defaults(id).join(self.get_edge_properties(id).unwrap())
+    }
+
+    fn edgedefault(&self) -> &'static str {
+        if self.is_directed() { "directed" } else { "undirected" }
+    }
+
+    /// Write every item directly contained in `parent`, recursing into its subgraphs.
+    fn write_graphml_body(&self, graphml: &mut String, keys: &Keys, parent: Option<ID>, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for record in self.sorted_records() {
+            if self.parent_of(record.id()) != parent {
+                continue;
+            }
+            graphml.push_str(&format!("{}<node id=\"{}\">\n", indent, record.name()));
+            graphml.push_str(&keys.data("node", &self.graphml_node_data(record.id()), &format!("{}  ", indent)));
+            for (column, cells) in record.nodes().columns().enumerate() {
+                for (row, (node, label)) in cells.cells().enumerate() {
+                    let start = format!("port name=\"Node_{}\"", node.id());
+                    keys.write_element(graphml, &start, "port", &port_properties(label, column, row), &format!("{}  ", indent));
+                }
+            }
+            graphml.push_str(&format!("{}</node>\n", indent));
+        }
+        for node in self.sorted_nodes() {
+            if self.parent_of(node.id()) != parent {
+                continue;
+            }
+            let start = format!("node id=\"{}\"", node.name());
+            keys.write_element(graphml, &start, "node", &self.graphml_node_data(node.id()), &indent);
+        }
+        for subgraph in self.sorted_subgraphs() {
+            let id = subgraph.id();
+            if self.parent_of(id) != parent {
+                continue;
+            }
+            graphml.push_str(&format!("{}<node id=\"{}\">\n", indent, subgraph.name()));
+            graphml.push_str(&keys.data("node", self.get_subgraph_properties(id).unwrap(), &format!("{}  ", indent)));
+            graphml.push_str(&format!("{}  <graph id=\"{}:\" edgedefault=\"{}\">\n", indent, subgraph.name(), self.edgedefault()));
+            self.write_graphml_body(graphml, keys, Some(id), depth + 2);
+            graphml.push_str(&format!("{}  </graph>\n{}</node>\n", indent, indent));
+        }
+        for edge in self.sorted_edges() {
+            if self.parent_of(edge.id()) != parent {
+                continue;
+            }
+            let mut endpoints = String::new();
+            for (end, node) in [("source", edge.start_node()), ("target", edge.end_node())] {
+                match node.record() {
+                    Some(record) => endpoints.push_str(&format!(" {}=\"Record_{}\" {}port=\"Node_{}\"", end, record, end, node.id())),
+                    None => endpoints.push_str(&format!(" {}=\"Node_{}\"", end, node.id())),
+                }
+            }
+            let start = format!("edge id=\"Edge_{}\"{}", edge.id(), endpoints);
+            keys.write_element(graphml, &start, "edge", &self.graphml_edge_data(edge.id()), &indent);
+        }
+    }
+
+    /// Parse a graph from GraphML.
+    ///
+    /// Data are read with the types of their keys, and key defaults become the graph's node
+    /// and edge defaults. IDs written by `to_graphml` are kept, and other nodes and edges are
+    /// identified by `ID::from` their GraphML ID, which also labels nodes without a label.
+    /// Nodes with ports become records, and nodes holding nested graphs become clusters
+    /// (or plain subgraphs, for `Subgraph_` IDs). Data of unknown types, like yEd's graphics,
+    /// are skipped.
+    pub fn from_graphml(source: &str) -> Result<Graph, ParseError> {
+        let root = XmlParser { source, pos: 0 }.document()?;
+        GraphmlReader { source, keys: HashMap::new(), graph: Graph::new() }.read(&root)
+    }
+}
+
+/// The `ID` of a GraphML ID, which is the one it was written with by `to_graphml`.
+fn id_of(name: &str) -> ID {
+    id_of_name(name).unwrap_or_else(|| ID::from(name))
+}
+
+/// A key declared in a GraphML file.
+struct Key {
+    domain: String,
+    name: String,
+    kind: String,
+}
+
+struct GraphmlReader<'a> {
+    source: &'a str,
+    keys: HashMap<String, Key>,
+    graph: Graph,
+}
+
+impl<'a> GraphmlReader<'a> {
+    fn error(&self, element: &Element, message: impl ToString) -> ParseError {
+        let (line, column) = position(self.source, element.pos);
+        ParseError::new(line, column, message)
+    }
+
+    fn attribute<'e>(&self, element: &'e Element, name: &str) -> Result<&'e str, ParseError> {
+        element.attribute(name).ok_or_else(|| self.error(element, format!("`<{}>` is missing the `{}` attribute", element.name, name)))
+    }
+
+    fn read(mut self, root: &Element) -> Result<Graph, ParseError> {
+        if root.local_name() != "graphml" {
+            return Err(self.error(root, "expected a `<graphml>` element"));
+        }
+        for key in root.children_named("key") {
+            let id = self.attribute(key, "id")?.to_string();
+            // Keys without a name, like yEd's graphics, aren't properties
+            let Some(name) = key.attribute("attr.name") else { continue };
+            let key = Key {
+                domain: key.attribute("for").unwrap_or("all").to_string(),
+                name: name.to_string(),
+                kind: key.attribute("attr.type").unwrap_or("string").to_string(),
+            };
+            self.keys.insert(id, key);
+        }
+        for key in root.children_named("key") {
+            let Some(default) = key.children_named("default").next() else { continue };
+            let Some(id) = key.attribute("id") else { continue };
+            let Some(value) = self.value(id, default)? else { continue };
+            let name = self.keys[id].name.clone();
+            match self.keys[id].domain.as_str() {
+                "node" => self.graph.node_defaults_mut().set(name, value),
+                "edge" => self.graph.edge_defaults_mut().set(name, value),
+                _ => {}
+            }
+        }
+
+        let Some(graph) = root.children_named("graph").next() else {
+            return Err(self.error(root, "expected a `<graph>` element"));
+        };
+        let kind = match graph.attribute("edgedefault") {
+            Some("undirected") => GraphKind::Undirected,
+            _ => GraphKind::Directed,
+        };
+        self.graph = std::mem::take(&mut self.graph).with_kind(kind);
+        let properties = self.data(graph, "graph")?;
+        self.graph.properties_mut().extend(properties);
+        self.graph_body(graph, None)?;
+        Ok(self.graph)
+    }
+
+    /// Read the value of a `<data>` or `<default>` element for the key `id`.
+    fn value(&self, id: &str, element: &Element) -> Result<Option<Value>, ParseError> {
+        let Some(key) = self.keys.get(id) else { return Ok(None) };
+        if key.kind == "string" {
+            return Ok(Some(Value::String(element.text.clone())));
+        }
+        match parse_data(&element.text, &key.kind) {
+            Some(value) => Ok(Some(value)),
+            None => Err(self.error(element, format!("`{}` is not a valid {} for `{}`", element.text.trim(), key.kind, key.name))),
+        }
+    }
+
+    /// Read the `<data>` children of an element into properties.
+    fn data(&self, element: &Element, domain: &str) -> Result<Properties, ParseError> {
+        let mut properties = Properties::new();
+        for data in element.children_named("data") {
+            let id = self.attribute(data, "key")?;
+            let Some(key) = self.keys.get(id).filter(|key| key.domain == domain || key.domain == "all") else { continue };
+            // Data holding markup belong to other tools
+            if !data.children.is_empty() {
+                continue;
+            }
+            if let Some(value) = self.value(id, data)? {
+                properties.set(key.name.clone(), value);
+            }
+        }
+        Ok(properties)
+    }
+
+    /// Read the nodes, edges and nested graphs of a `<graph>` element into the subgraph `parent`.
+    fn graph_body(&mut self, graph: &Element, parent: Option<ID>) -> Result<(), ParseError> {
+        for node in graph.children_named("node") {
+            let name = self.attribute(node, "id")?;
+            let id = id_of(name);
+            let mut properties = self.data(node, "node")?;
+            if let Some(nested) = node.children_named("graph").next() {
+                let subgraph = if name.starts_with("Subgraph_") { Subgraph::new(id) } else { Subgraph::cluster(id) };
+                properties.extend(self.data(nested, "graph")?);
+                if properties.get("label").is_none() && id_of_name(name).is_none() {
+                    properties.set("label", name);
+                }
+                self.graph.insert_subgraph(subgraph, properties);
+                self.graph.move_to_subgraph(subgraph, parent);
+                self.graph_body(nested, Some(id))?;
+            } else if node.children_named("port").next().is_some() {
+                let mut fields = Vec::new();
+                for (i, port) in node.children_named("port").enumerate() {
+                    let port_name = self.attribute(port, "name")?;
+                    let port_properties = self.data(port, "port")?;
+                    let index = |key: &str, default: usize| match port_properties.get(key) {
+                        Some(Value::I32(n)) if *n >= 0 => *n as usize,
+                        _ => default,
+                    };
+                    let label = match port_properties.get("label") {
+                        Some(Value::String(label)) => label.clone(),
+                        _ => port_name.to_string(),
+                    };
+                    fields.push((index("column", 0), index("row", i), id_of(port_name), label));
+                }
+                fields.sort_by_key(|&(column, row, _, _)| (column, row));
+                let width = fields.iter().map(|&(column, _, _, _)| column + 1).max().unwrap_or(0);
+                let mut record = Record::new(id, width, 0);
+                for (column, row, port, label) in fields {
+                    let length = record.nodes().get_column(column).map(|column| column.cells().count()).unwrap_or(0);
+                    record.insert_node(column, row.min(length), Node::new(port), label);
+                }
+                self.graph.insert_record(record, properties);
+                self.graph.move_to_subgraph(id, parent);
+            } else {
+                if properties.get("label").is_none() && id_of_name(name).is_none() {
+                    properties.set("label", name);
+                }
+                self.graph.insert_node(Node::new(id), properties);
+                self.graph.move_to_subgraph(id, parent);
+            }
+        }
+        for edge in graph.children_named("edge") {
+            let endpoint = |end: &str| -> Result<Node, ParseError> {
+                let node = id_of(self.attribute(edge, end)?);
+                Ok(match edge.attribute(&format!("{}port", end)) {
+                    Some(port) => Node::new(id_of(port)).with_record(node),
+                    None => Node::new(node),
+                })
+            };
+            let (start, end) = (endpoint("source")?, endpoint("target")?);
+            let properties = self.data(edge, "edge")?;
+            let mut builder = self.graph.new_edge(start, end).with_properties(properties);
+            if let Some(name) = edge.attribute("id") {
+                builder = builder.with_id(id_of(name));
+            }
+            if let Some(parent) = parent {
+                builder = builder.in_subgraph(parent);
+            }
+            builder.finalize();
+        }
+        Ok(())
+    }
+}
+
+/// The line and column of a byte offset in some text.
+fn position(source: &str, pos: usize) -> (usize, usize) {
+    let before = &source[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
+/// An XML element, with the text directly inside it.
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+    pos: usize,
+}
+
+impl Element {
+    /// The name without its namespace prefix.
+    fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or_default()
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn children_named<'e>(&'e self, name: &'e str) -> impl Iterator<Item=&'e Element> {
+        self.children.iter().filter(move |child| child.local_name() == name)
+    }
+}
+
+/// A parser for the subset of XML GraphML files use: elements, attributes, text, entity and
+/// character references, comments, CDATA sections, and a skipped prolog.
+struct XmlParser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn error(&self, message: impl ToString) -> ParseError {
+        let (line, column) = position(self.source, self.pos);
+        ParseError::new(line, column, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.source.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skip past the next `end`.
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, ParseError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                let skipped = &self.rest()[..i];
+                self.pos += i + end.len();
+                Ok(skipped)
+            }
+            None => Err(self.error(format!("expected `{}`", end))),
+        }
+    }
+
+    /// Skip whitespace, comments, processing instructions and declarations between elements.
+    fn skip_misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.skip_past("-->")?;
+            } else if self.eat("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!") && !self.rest().starts_with("<![CDATA[") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<Element, ParseError> {
+        self.eat("\u{feff}");
+        self.skip_misc()?;
+        let root = self.element()?;
+        self.skip_misc()?;
+        if !self.rest().is_empty() {
+            return Err(self.error("expected the end of the document"));
+        }
+        Ok(root)
+    }
+
+    fn name(&mut self) -> Result<&'a str, ParseError> {
+        let length = self.rest().find(|c: char| c.is_whitespace() || matches!(c, '=' | '/' | '>')).unwrap_or(self.rest().len());
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = &self.rest()[..length];
+        self.pos += length;
+        Ok(name)
+    }
+
+    fn element(&mut self) -> Result<Element, ParseError> {
+        let pos = self.pos;
+        if !self.eat("<") {
+            return Err(self.error("expected an element"));
+        }
+        let name = self.name()?.to_string();
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok(Element { name, attributes, children: Vec::new(), text: String::new(), pos });
+            }
+            if self.eat(">") {
+                break;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.eat("=") {
+                return Err(self.error("expected `=`"));
+            }
+            self.skip_whitespace();
+            let quote = if self.eat("\"") { "\"" } else if self.eat("'") { "'" } else { return Err(self.error("expected a quoted value")) };
+            let value = self.skip_past(quote)?;
+            attributes.push((key, self.unescape(value)?));
+        }
+
+        let mut element = Element { name, attributes, children: Vec::new(), text: String::new(), pos };
+        loop {
+            if self.eat("</") {
+                let close = self.name()?;
+                if close != element.name {
+                    return Err(self.error(format!("expected `</{}>`", element.name)));
+                }
+                self.skip_whitespace();
+                if !self.eat(">") {
+                    return Err(self.error("expected `>`"));
+                }
+                return Ok(element);
+            } else if self.eat("<![CDATA[") {
+                element.text.push_str(self.skip_past("]]>")?);
+            } else if self.eat("<!--") {
+                self.skip_past("-->")?;
+            } else if self.eat("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with('<') {
+                element.children.push(self.element()?);
+            } else if self.rest().is_empty() {
+                return Err(self.error(format!("expected `</{}>`", element.name)));
+            } else {
+                let length = self.rest().find('<').unwrap_or(self.rest().len());
+                let text = &self.rest()[..length];
+                element.text.push_str(&self.unescape(text)?);
+                self.pos += length;
+            }
+        }
+    }
+
+    /// Replace entity and character references.
+    fn unescape(&self, text: &str) -> Result<String, ParseError> {
+        let mut unescaped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            unescaped.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            let Some(end) = rest.find(';') else {
+                return Err(self.error("unterminated entity reference"));
+            };
+            let c = match &rest[..end] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                code => match code.strip_prefix("#x").or_else(|| code.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => code.strip_prefix('#').and_then(|decimal| decimal.parse().ok()),
+                }.and_then(char::from_u32),
+            };
+            match c {
+                Some(c) => unescaped.push(c),
+                None => return Err(self.error(format!("unknown entity `&{};`", &rest[..end]))),
+            }
+            rest = &rest[end + 1..];
+        }
+        unescaped.push_str(rest);
+        Ok(unescaped)
+    }
+}
@@ -123,9 +123,8 @@ impl Sha512 {
     fn process_block(&mut self, block: &[u8]) {
         // Prepare the message schedule
         let mut w = [0u64; 80];
-        for t in 0..16 {
-            let start = t * 8;
-            w[t] = u64::from_be_bytes(block[start..start + 8].try_into().unwrap());
+        for (t, chunk) in block.chunks_exact(8).take(16).enumerate() {
+            w[t] = u64::from_be_bytes(chunk.try_into().unwrap());
         }
         for t in 16..80 {
             let s0 = (w[t - 15].rotate_right(1)) ^ (w[t - 15].rotate_right(8)) ^ (w[t - 15] >> 7);
@@ -148,16 +147,16 @@ impl Sha512 {
 
         // Compression function main loop
         for t in 0..80 {
-            let S1 = (e.rotate_right(14)) ^ (e.rotate_right(18)) ^ (e.rotate_right(41));
+            let sum1 = (e.rotate_right(14)) ^ (e.rotate_right(18)) ^ (e.rotate_right(41));
             let ch = (e & f) ^ ((!e) & g);
             let temp1 = h
-                .wrapping_add(S1)
+                .wrapping_add(sum1)
                 .wrapping_add(ch)
                 .wrapping_add(K[t])
                 .wrapping_add(w[t]);
-            let S0 = (a.rotate_right(28)) ^ (a.rotate_right(34)) ^ (a.rotate_right(39));
+            let sum0 = (a.rotate_right(28)) ^ (a.rotate_right(34)) ^ (a.rotate_right(39));
             let maj = (a & b) ^ (a & c) ^ (b & c);
-            let temp2 = S0.wrapping_add(maj);
+            let temp2 = sum0.wrapping_add(maj);
 
             h = g;
             g = f;
@@ -181,6 +180,12 @@ impl Sha512 {
     }
 }
 
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Convenience function to compute SHA-512 hash in one step
 pub fn sha512(data: &[u8]) -> [u8; 64] {
     let mut sha = Sha512::new();
@@ -1,7 +1,8 @@
 use std::{
-    fmt::{Display, Formatter, LowerHex, Result as FmtResult, UpperHex}, str::FromStr, sync::Mutex,
+    fmt::{Display, Formatter, LowerHex, Result as FmtResult, UpperHex}, str::FromStr,
     ops::{AddAssign, Add}
 };
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 
 fn increment(seed: IDValue) -> IDValue {
     seed + 1
@@ -35,6 +36,7 @@ pub type IDGenerator = fn(IDValue) -> IDValue;
 /// A unique identifier.
 pub type IDValue = u128;
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ID {
     id: IDValue,
@@ -55,6 +57,14 @@ impl ID {
         }
     }
 
+    /// Parse an ID from the hexadecimal form it is displayed in.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        IDValue::from_str_radix(hex, 16).ok().map(ID::new)
+    }
+
     pub fn random() -> Self {
         Self::new(rand::random())
     }
@@ -135,6 +145,20 @@ impl Display for ID {
     }
 }
 
+// IDs are stored as the same hexadecimal strings they are displayed as.
+impl Serialize for ID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        ID::from_hex(&hex).ok_or_else(|| D::Error::custom(format!("invalid ID `{}`, expected up to 32 hexadecimal digits", hex)))
+    }
+}
+
 impl LowerHex for ID {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         // Convert to bytes
@@ -0,0 +1,1426 @@
+use std::{collections::HashMap, f64::consts::SQRT_2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use super::{Edge, Graph, Node, Properties, Record, Value, ID};
+
+/// The number of points in an inch, the unit Graphviz uses for sizes in attributes.
+pub(crate) const POINTS_PER_INCH: f64 = 72.0;
+pub(crate) const DEFAULT_FONT_SIZE: f64 = 14.0;
+/// The space left around the drawing.
+const MARGIN: f64 = 4.0;
+
+/// A position in points, with the origin at the top left and `y` growing downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    fn lerp(&self, other: Point, t: f64) -> Point {
+        Point::new(self.x + (other.x - self.x) * t, self.y + (other.y - self.y) * t)
+    }
+
+    fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// An axis-aligned box given by its center and size, in points.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub center: Point,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(center: Point, width: f64, height: f64) -> Self {
+        Rect { center, width, height }
+    }
+
+    pub fn left(&self) -> f64 {
+        self.center.x - self.width / 2.0
+    }
+
+    pub fn right(&self) -> f64 {
+        self.center.x + self.width / 2.0
+    }
+
+    pub fn top(&self) -> f64 {
+        self.center.y - self.height / 2.0
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.center.y + self.height / 2.0
+    }
+
+    fn from_corners(left: f64, top: f64, right: f64, bottom: f64) -> Self {
+        Rect::new(Point::new((left + right) / 2.0, (top + bottom) / 2.0), right - left, bottom - top)
+    }
+
+    fn union(&self, other: &Rect) -> Rect {
+        Rect::from_corners(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    fn translate(&mut self, dx: f64, dy: f64) {
+        self.center.x += dx;
+        self.center.y += dy;
+    }
+}
+
+/// The route of an edge, and where the center of its label goes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EdgeRoute {
+    /// The points the edge passes through, or the control points of its curve when `spline` is set
+    pub points: Vec<Point>,
+    /// Whether `points` are the control points of a piecewise cubic Bézier curve, as in Graphviz's
+    /// edge `pos` attribute: a start point followed by three points for each piece
+    pub spline: bool,
+    /// Where the tip of the arrowhead at the end goes, when the route stops short of it
+    pub head: Option<Point>,
+    /// Where the tip of the arrowhead at the start goes, when the route stops short of it
+    pub tail: Option<Point>,
+    pub label: Option<Point>,
+}
+
+/// The positions of everything in a graph, keyed by the `ID`s of its nodes, records, edges and subgraphs.
+///
+/// Record fields are positioned under their port `Node`s, which include the record they belong to,
+/// so identical fields of different records are kept apart.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Layout {
+    nodes: HashMap<Node, Rect>,
+    edges: HashMap<ID, EdgeRoute>,
+    subgraphs: HashMap<ID, Rect>,
+    width: f64,
+    height: f64,
+}
+
+impl Layout {
+    pub fn new(width: f64, height: f64) -> Self {
+        Layout { width, height, ..Default::default() }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn node(&self, node: impl Into<Node>) -> Option<&Rect> {
+        self.nodes.get(&node.into())
+    }
+
+    pub fn edge(&self, id: impl Into<ID>) -> Option<&EdgeRoute> {
+        self.edges.get(&id.into())
+    }
+
+    pub fn subgraph(&self, id: impl Into<ID>) -> Option<&Rect> {
+        self.subgraphs.get(&id.into())
+    }
+
+    pub fn set_node(&mut self, node: impl Into<Node>, rect: Rect) {
+        self.nodes.insert(node.into(), rect);
+    }
+
+    pub fn set_edge(&mut self, id: impl Into<ID>, route: EdgeRoute) {
+        self.edges.insert(id.into(), route);
+    }
+
+    pub fn set_subgraph(&mut self, id: impl Into<ID>, rect: Rect) {
+        self.subgraphs.insert(id.into(), rect);
+    }
+
+    /// Move everything so the drawing starts at the margin, and resize the layout to fit it.
+    pub(crate) fn fit(&mut self) {
+        let rects = self.nodes.values().chain(self.subgraphs.values());
+        let points = self.edges.values().flat_map(|route| {
+            route.points.iter().chain(route.label.iter()).chain(route.head.iter()).chain(route.tail.iter())
+        });
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for rect in rects {
+            min_x = min_x.min(rect.left());
+            min_y = min_y.min(rect.top());
+            max_x = max_x.max(rect.right());
+            max_y = max_y.max(rect.bottom());
+        }
+        for point in points {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+        if min_x > max_x {
+            self.width = 2.0 * MARGIN;
+            self.height = 2.0 * MARGIN;
+            return;
+        }
+
+        let (dx, dy) = (MARGIN - min_x, MARGIN - min_y);
+        for rect in self.nodes.values_mut().chain(self.subgraphs.values_mut()) {
+            rect.translate(dx, dy);
+        }
+        for route in self.edges.values_mut() {
+            let points = route.points.iter_mut().chain(route.label.iter_mut());
+            for point in points.chain(route.head.iter_mut()).chain(route.tail.iter_mut()) {
+                point.x += dx;
+                point.y += dy;
+            }
+        }
+        self.width = max_x - min_x + 2.0 * MARGIN;
+        self.height = max_y - min_y + 2.0 * MARGIN;
+    }
+}
+
+/// The direction layers are stacked in, from the `rankdir` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RankDir {
+    #[default]
+    TopToBottom,
+    LeftToRight,
+    BottomToTop,
+    RightToLeft,
+}
+
+impl RankDir {
+    pub fn from_properties(properties: &Properties) -> Self {
+        match text(properties, "rankdir").as_deref() {
+            Some("LR") => RankDir::LeftToRight,
+            Some("BT") => RankDir::BottomToTop,
+            Some("RL") => RankDir::RightToLeft,
+            _ => RankDir::TopToBottom,
+        }
+    }
+
+    /// Whether layers run horizontally, which also turns record fields sideways.
+    pub fn is_horizontal(&self) -> bool {
+        matches!(self, RankDir::LeftToRight | RankDir::RightToLeft)
+    }
+}
+
+/// The outline of a node, from the `shape` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Shape {
+    Box,
+    #[default]
+    Ellipse,
+    Circle,
+    DoubleCircle,
+    Diamond,
+    Record,
+    Point,
+    /// Just the label, without an outline
+    Plain,
+}
+
+impl Shape {
+    pub fn from_properties(properties: &Properties) -> Self {
+        match text(properties, "shape").as_deref() {
+            Some("box" | "rect" | "rectangle" | "square") => Shape::Box,
+            Some("circle") => Shape::Circle,
+            Some("doublecircle") => Shape::DoubleCircle,
+            Some("diamond") => Shape::Diamond,
+            Some("record" | "Mrecord") => Shape::Record,
+            Some("point") => Shape::Point,
+            Some("plain" | "plaintext" | "none") => Shape::Plain,
+            _ => Shape::Ellipse,
+        }
+    }
+
+    /// Whether a point lies inside this shape when it is drawn in `rect`.
+    pub fn contains(&self, rect: &Rect, point: Point) -> bool {
+        let dx = (point.x - rect.center.x).abs() / (rect.width / 2.0).max(f64::EPSILON);
+        let dy = (point.y - rect.center.y).abs() / (rect.height / 2.0).max(f64::EPSILON);
+        match self {
+            Shape::Ellipse | Shape::Circle | Shape::DoubleCircle | Shape::Point => dx * dx + dy * dy <= 1.0,
+            Shape::Diamond => dx + dy <= 1.0,
+            Shape::Box | Shape::Record | Shape::Plain => dx <= 1.0 && dy <= 1.0,
+        }
+    }
+}
+
+/// Read a numeric property, accepting numbers written as strings.
+pub(crate) fn number(properties: &Properties, key: &str) -> Option<f64> {
+    match properties.get(key)? {
+        Value::I32(i) => Some(*i as f64),
+        Value::F64(n) => Some(*n),
+        Value::String(s) => s.split_whitespace().next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The text a string with Graphviz's escape sequences shows. `\l`, `\r` and `\n` end lines
+/// (a final one adds no empty line) and `\\` is a backslash; other escapes are kept as written.
+fn unescape(s: &str) -> String {
+    let mut text = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('l' | 'r' | 'n')) => {
+                chars.next();
+                if chars.peek().is_some() {
+                    text.push('\n');
+                }
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                text.push('\\');
+            }
+            (c, _) => text.push(c),
+        }
+    }
+    text
+}
+
+/// Read a property as plain text.
+pub(crate) fn text(properties: &Properties, key: &str) -> Option<String> {
+    match properties.get(key)? {
+        Value::String(s) => Some(unescape(s)),
+        Value::Html(html) => Some(strip_html(html)),
+        Value::Nothing => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// The text an HTML-like label displays, with line breaks for `<br/>` tags.
+pub(crate) fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut chars = html.chars();
+    while let Some(c) = chars.next() {
+        if c == '<' {
+            let tag: String = chars.by_ref().take_while(|&c| c != '>').collect();
+            if tag.trim_start().to_ascii_lowercase().starts_with("br") {
+                text.push('\n');
+            }
+        } else {
+            text.push(c);
+        }
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&nbsp;", " ").replace("&amp;", "&")
+}
+
+/// The text a node displays. Like Graphviz, nodes without a label show their name.
+pub(crate) fn node_label(name: &str, properties: &Properties) -> String {
+    match properties.get("label") {
+        None => name.to_string(),
+        Some(_) => text(properties, "label").unwrap_or_default(),
+    }
+}
+
+/// Estimate the size of some text in points.
+pub(crate) fn measure(text: &str, font_size: f64) -> (f64, f64) {
+    let lines = text.split('\n');
+    let (count, longest) = lines.fold((0, 0), |(count, longest), line| (count + 1, longest.max(line.chars().count())));
+    (longest as f64 * font_size * 0.55, count as f64 * font_size * 1.2)
+}
+
+pub(crate) fn font_size(properties: &Properties) -> f64 {
+    number(properties, "fontsize").unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+/// The size of a node, following Graphviz's default margins and minimum sizes.
+pub(crate) fn node_size(label: &str, shape: Shape, properties: &Properties) -> (f64, f64) {
+    let (text_width, text_height) = measure(label, font_size(properties));
+    let (padded_width, padded_height) = (text_width + 16.0, text_height + 8.0);
+    let (width, height) = match shape {
+        Shape::Point => (0.0, 0.0),
+        Shape::Plain => (text_width, text_height),
+        Shape::Box | Shape::Record => (padded_width, padded_height),
+        Shape::Ellipse | Shape::Circle | Shape::DoubleCircle => (padded_width * SQRT_2, padded_height * SQRT_2),
+        Shape::Diamond => (padded_width * 2.0, padded_height * 2.0),
+    };
+    let (min_width, min_height) = match shape {
+        Shape::Point => (3.6, 3.6),
+        Shape::Plain => (0.0, 0.0),
+        _ => (54.0, 36.0),
+    };
+    let min_width = number(properties, "width").map(|w| w * POINTS_PER_INCH).unwrap_or(min_width);
+    let min_height = number(properties, "height").map(|h| h * POINTS_PER_INCH).unwrap_or(min_height);
+    let fixed = matches!(properties.get("fixedsize"), Some(Value::Bool(true)))
+        || text(properties, "fixedsize").as_deref() == Some("true");
+    let (mut width, mut height) = if fixed {
+        (min_width, min_height)
+    } else {
+        (width.max(min_width), height.max(min_height))
+    };
+    if matches!(shape, Shape::Circle | Shape::DoubleCircle | Shape::Point) {
+        width = width.max(height);
+        height = width;
+    }
+    if shape == Shape::DoubleCircle {
+        width += 8.0;
+        height += 8.0;
+    }
+    (width, height)
+}
+
+/// A field of a record with the size its label needs.
+struct Field {
+    node: Option<Node>,
+    label: String,
+    width: f64,
+    height: f64,
+}
+
+/// The fields of each of a record's columns.
+fn record_fields(record: &Record, font_size: f64) -> Vec<Vec<Field>> {
+    record.nodes().columns().map(|column| {
+        let mut fields: Vec<Field> = column.cells().map(|(node, label)| {
+            let (width, height) = measure(label, font_size);
+            Field { node: Some(node), label: label.to_string(), width: width + 16.0, height: height + 8.0 }
+        }).collect();
+        if fields.is_empty() {
+            fields.push(Field { node: None, label: String::new(), width: 16.0, height: font_size * 1.2 + 8.0 });
+        }
+        fields
+    }).collect()
+}
+
+/// The size of a record. Columns run left to right, or top to bottom when `flipped`.
+pub(crate) fn record_size(record: &Record, font_size: f64, flipped: bool) -> (f64, f64) {
+    let columns = record_fields(record, font_size);
+    let across = |fields: &Vec<Field>| -> (f64, f64) {
+        if flipped {
+            (fields.iter().map(|f| f.width).sum(), fields.iter().map(|f| f.height).fold(0.0, f64::max))
+        } else {
+            (fields.iter().map(|f| f.width).fold(0.0, f64::max), fields.iter().map(|f| f.height).sum())
+        }
+    };
+    let (width, height) = if flipped {
+        columns.iter().map(across).fold((0.0, 0.0), |(w, h), (cw, ch)| (f64::max(w, cw), h + ch))
+    } else {
+        columns.iter().map(across).fold((0.0, 0.0), |(w, h), (cw, ch)| (w + cw, f64::max(h, ch)))
+    };
+    (width.max(54.0), height.max(36.0))
+}
+
+/// Divide a record drawn in `rect` into its fields, returning each field's port node, label and box.
+pub(crate) fn record_cells(record: &Record, rect: &Rect, font_size: f64, flipped: bool) -> Vec<(Option<Node>, String, Rect)> {
+    let columns = record_fields(record, font_size);
+    let mut cells = Vec::new();
+    // Split a length between items in proportion to the space each one needs
+    let split = |start: f64, length: f64, sizes: Vec<f64>| -> Vec<(f64, f64)> {
+        let total: f64 = sizes.iter().sum::<f64>().max(f64::EPSILON);
+        let mut offset = start;
+        sizes.into_iter().map(|size| {
+            let part = length * size / total;
+            offset += part;
+            (offset - part, part)
+        }).collect()
+    };
+    if flipped {
+        let rows = split(rect.top(), rect.height, columns.iter().map(|f| f.iter().map(|f| f.height).fold(0.0, f64::max)).collect());
+        for (fields, (top, height)) in columns.into_iter().zip(rows) {
+            let parts = split(rect.left(), rect.width, fields.iter().map(|f| f.width).collect());
+            for (field, (left, width)) in fields.into_iter().zip(parts) {
+                cells.push((field.node, field.label, Rect::from_corners(left, top, left + width, top + height)));
+            }
+        }
+    } else {
+        let columns_x = split(rect.left(), rect.width, columns.iter().map(|f| f.iter().map(|f| f.width).fold(0.0, f64::max)).collect());
+        for (fields, (left, width)) in columns.into_iter().zip(columns_x) {
+            let parts = split(rect.top(), rect.height, fields.iter().map(|f| f.height).collect());
+            for (field, (top, height)) in fields.into_iter().zip(parts) {
+                cells.push((field.node, field.label, Rect::from_corners(left, top, left + width, top + height)));
+            }
+        }
+    }
+    cells
+}
+
+/// The boxes a layout positions: nodes, records, and the endpoints of edges to nodes which were never inserted.
+pub(crate) struct Units {
+    pub ids: Vec<ID>,
+    pub sizes: Vec<(f64, f64)>,
+    pub shapes: Vec<Shape>,
+    pub index: HashMap<ID, usize>,
+    /// Each edge with the units it starts and ends at
+    pub edges: Vec<(Edge, usize, usize)>,
+    pub flipped: bool,
+}
+
+impl Units {
+    pub fn new(graph: &Graph, flipped: bool) -> Self {
+        let mut units = Units {
+            ids: Vec::new(),
+            sizes: Vec::new(),
+            shapes: Vec::new(),
+            index: HashMap::new(),
+            edges: Vec::new(),
+            flipped,
+        };
+        for record in graph.sorted_records() {
+            let properties = graph.resolved_node_properties(record.id());
+            units.push(record.id(), record_size(record, font_size(&properties), flipped), Shape::Record);
+        }
+        for node in graph.sorted_nodes() {
+            let properties = graph.resolved_node_properties(node.id());
+            let shape = Shape::from_properties(&properties);
+            let size = node_size(&node_label(&node.name(), &properties), shape, &properties);
+            units.push(node.id(), size, shape);
+        }
+        for edge in graph.sorted_edges() {
+            let start = units.unit_of(graph, edge.start_node());
+            let end = units.unit_of(graph, edge.end_node());
+            units.edges.push((*edge, start, end));
+        }
+        units
+    }
+
+    fn push(&mut self, id: ID, size: (f64, f64), shape: Shape) -> usize {
+        self.index.insert(id, self.ids.len());
+        self.ids.push(id);
+        self.sizes.push(size);
+        self.shapes.push(shape);
+        self.ids.len() - 1
+    }
+
+    /// The unit an edge endpoint attaches to, adding one for nodes which are not in the graph.
+    fn unit_of(&mut self, graph: &Graph, node: Node) -> usize {
+        let (id, name) = match node.record() {
+            Some(record) => (record, format!("Record_{}", record)),
+            None => (node.id(), node.name()),
+        };
+        if let Some(&unit) = self.index.get(&id) {
+            return unit;
+        }
+        let properties = graph.resolved_node_properties(id);
+        let shape = Shape::from_properties(&properties);
+        let size = node_size(&node_label(&name, &properties), shape, &properties);
+        self.push(id, size, shape)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Assemble a layout from the centers of the units and the bends of each edge.
+    ///
+    /// Edges are clipped to the outlines of their endpoints, and edges from record fields
+    /// start at their field. Subgraphs get a box around their members.
+    pub fn finish(&self, graph: &Graph, centers: &[Point], bends: Vec<Vec<Point>>) -> Layout {
+        let mut layout = Layout::new(0.0, 0.0);
+        for (unit, &id) in self.ids.iter().enumerate() {
+            layout.set_node(id, Rect::new(centers[unit], self.sizes[unit].0, self.sizes[unit].1));
+        }
+        for record in graph.sorted_records() {
+            let rect = layout.nodes[&Node::from(record.id())];
+            let properties = graph.resolved_node_properties(record.id());
+            for (node, _, cell) in record_cells(record, &rect, font_size(&properties), self.flipped) {
+                if let Some(node) = node {
+                    layout.set_node(node, cell);
+                }
+            }
+        }
+
+        for ((edge, start, end), bends) in self.edges.iter().zip(bends) {
+            let anchor = |node: Node, unit: usize| match node.record() {
+                Some(_) => layout.node(node).map(|cell| cell.center).unwrap_or(centers[unit]),
+                None => centers[unit],
+            };
+            let mut points = vec![anchor(edge.start_node(), *start)];
+            points.extend(bends);
+            points.push(anchor(edge.end_node(), *end));
+
+            let count = points.len();
+            points[0] = self.clip(*start, &layout, points[0], points[1]);
+            points[count - 1] = self.clip(*end, &layout, points[count - 1], points[count - 2]);
+
+            let properties = graph.resolved_edge_properties(edge.id());
+            // Put the label beside the middle of the edge
+            let label = text(&properties, "label").map(|label| {
+                let (width, _) = measure(&label, font_size(&properties));
+                let middle = midpoint(&points);
+                Point::new(middle.x + 6.0 + width / 2.0, middle.y)
+            });
+            layout.set_edge(edge.id(), EdgeRoute { points, label, ..Default::default() });
+        }
+
+        let mut boxes = HashMap::new();
+        for subgraph in graph.sorted_subgraphs() {
+            self.subgraph_box(graph, subgraph.id(), &layout, &mut boxes);
+        }
+        for (id, rect) in boxes {
+            if let Some(rect) = rect {
+                layout.set_subgraph(id, rect);
+            }
+        }
+        layout.fit();
+        layout
+    }
+
+    /// Move the end of an edge at `inside` to where the line towards `outside` leaves its unit's outline.
+    fn clip(&self, unit: usize, layout: &Layout, inside: Point, outside: Point) -> Point {
+        let rect = layout.nodes[&Node::from(self.ids[unit])];
+        let shape = self.shapes[unit];
+        if shape.contains(&rect, outside) || !shape.contains(&rect, inside) {
+            return inside;
+        }
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..32 {
+            let middle = (low + high) / 2.0;
+            if shape.contains(&rect, inside.lerp(outside, middle)) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        inside.lerp(outside, high)
+    }
+
+    /// The box around the members of a subgraph, including nested subgraphs.
+    fn subgraph_box(&self, graph: &Graph, id: ID, layout: &Layout, boxes: &mut HashMap<ID, Option<Rect>>) -> Option<Rect> {
+        if let Some(rect) = boxes.get(&id) {
+            return *rect;
+        }
+        // Guard against subgraphs which contain themselves
+        boxes.insert(id, None);
+        let mut bounds: Option<Rect> = None;
+        for &unit in &self.ids {
+            if graph.parent_of(unit) == Some(id) {
+                let rect = layout.nodes[&Node::from(unit)];
+                bounds = Some(bounds.map_or(rect, |b| b.union(&rect)));
+            }
+        }
+        for subgraph in graph.sorted_subgraphs() {
+            if graph.parent_of(subgraph.id()) == Some(id) {
+                if let Some(rect) = self.subgraph_box(graph, subgraph.id(), layout, boxes) {
+                    bounds = Some(bounds.map_or(rect, |b| b.union(&rect)));
+                }
+            }
+        }
+        let rect = bounds.map(|b| {
+            let properties = graph.get_subgraph_properties(id).cloned().unwrap_or_default();
+            let label = if properties.get("label").is_some() { font_size(&properties) * 1.2 } else { 0.0 };
+            Rect::from_corners(b.left() - 8.0, b.top() - 8.0 - label, b.right() + 8.0, b.bottom() + 8.0)
+        });
+        boxes.insert(id, rect);
+        rect
+    }
+}
+
+/// The point halfway along a polyline.
+fn midpoint(points: &[Point]) -> Point {
+    let total: f64 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let mut remaining = total / 2.0;
+    for w in points.windows(2) {
+        let length = w[0].distance(w[1]);
+        if length >= remaining && length > 0.0 {
+            return w[0].lerp(w[1], remaining / length);
+        }
+        remaining -= length;
+    }
+    points[0]
+}
+
+/// A layered (Sugiyama-style) layout, like Graphviz's `dot`.
+///
+/// Cycles are broken by reversing edges, nodes are assigned to layers by longest path,
+/// crossings are reduced with barycenter sweeps, and coordinates are placed to straighten edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayeredLayout {
+    node_sep: f64,
+    rank_sep: f64,
+    rank_dir: RankDir,
+}
+
+impl Default for LayeredLayout {
+    fn default() -> Self {
+        LayeredLayout {
+            node_sep: 0.25 * POINTS_PER_INCH,
+            rank_sep: 0.5 * POINTS_PER_INCH,
+            rank_dir: RankDir::TopToBottom,
+        }
+    }
+}
+
+/// A vertex of the layered graph: either a unit or a bend in a long edge.
+struct Vertex {
+    unit: Option<usize>,
+    /// The size along the layer
+    breadth: f64,
+    /// The size across layers
+    depth: f64,
+    rank: usize,
+}
+
+impl LayeredLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure the layout from the graph's `nodesep`, `ranksep` and `rankdir` attributes.
+    pub fn for_graph(graph: &Graph) -> Self {
+        let defaults = Self::default();
+        let properties = graph.properties();
+        LayeredLayout {
+            node_sep: number(properties, "nodesep").map(|n| n * POINTS_PER_INCH).unwrap_or(defaults.node_sep),
+            rank_sep: number(properties, "ranksep").map(|r| r * POINTS_PER_INCH).unwrap_or(defaults.rank_sep),
+            rank_dir: RankDir::from_properties(properties),
+        }
+    }
+
+    /// Set the space between neighbouring nodes in a layer, in points.
+    pub fn with_node_sep(mut self, node_sep: f64) -> Self {
+        self.node_sep = node_sep;
+        self
+    }
+
+    /// Set the space between layers, in points.
+    pub fn with_rank_sep(mut self, rank_sep: f64) -> Self {
+        self.rank_sep = rank_sep;
+        self
+    }
+
+    pub fn with_rank_dir(mut self, rank_dir: RankDir) -> Self {
+        self.rank_dir = rank_dir;
+        self
+    }
+
+    pub fn rank_dir(&self) -> RankDir {
+        self.rank_dir
+    }
+
+    pub fn layout(&self, graph: &Graph) -> Layout {
+        let units = Units::new(graph, self.rank_dir.is_horizontal());
+        let (centers, mut bends) = self.place(graph, &units);
+        spread_parallel_edges(&units, &centers, &mut bends);
+        units.finish(graph, &centers, bends)
+    }
+
+    /// Find the centers of the units and the bends of each edge.
+    ///
+    /// Only the sizes of the units are used, so they may be measured in something other than points.
+    pub(crate) fn place(&self, graph: &Graph, units: &Units) -> (Vec<Point>, Vec<Vec<Point>>) {
+        let flipped = units.flipped;
+        let ranks = self.assign_ranks(graph, units);
+
+        // Build the layered graph, splitting edges which span several layers with bends
+        let mut vertices: Vec<Vertex> = (0..units.len()).map(|unit| {
+            let (width, height) = units.sizes[unit];
+            let (breadth, depth) = if flipped { (height, width) } else { (width, height) };
+            Vertex { unit: Some(unit), breadth, depth, rank: ranks[unit] }
+        }).collect();
+        let mut below: Vec<Vec<usize>> = vec![Vec::new(); units.len()];
+        let mut above: Vec<Vec<usize>> = vec![Vec::new(); units.len()];
+        let mut chains = Vec::new();
+        for &(_, start, end) in &units.edges {
+            if ranks[start] == ranks[end] {
+                chains.push(vec![start, end]);
+                continue;
+            }
+            let (upper, lower) = if ranks[start] < ranks[end] { (start, end) } else { (end, start) };
+            let mut chain = vec![upper];
+            for rank in ranks[upper] + 1..ranks[lower] {
+                vertices.push(Vertex { unit: None, breadth: 0.0, depth: 0.0, rank });
+                below.push(Vec::new());
+                above.push(Vec::new());
+                chain.push(vertices.len() - 1);
+            }
+            chain.push(lower);
+            for pair in chain.windows(2) {
+                below[pair[0]].push(pair[1]);
+                above[pair[1]].push(pair[0]);
+            }
+            if upper != start {
+                chain.reverse();
+            }
+            chains.push(chain);
+        }
+
+        let layer_count = vertices.iter().map(|v| v.rank + 1).max().unwrap_or(0);
+        let mut layers = vec![Vec::new(); layer_count];
+        for (i, vertex) in vertices.iter().enumerate() {
+            layers[vertex.rank].push(i);
+        }
+        let layers = order_layers(layers, &above, &below, vertices.len());
+        let along = self.place_along(&layers, &vertices, &above, &below);
+
+        // Stack the layers, leaving room for the deepest node in each
+        let mut across = vec![0.0; layer_count];
+        let depths: Vec<f64> = layers.iter().map(|layer| layer.iter().map(|&v| vertices[v].depth).fold(0.0, f64::max)).collect();
+        for rank in 1..layer_count {
+            across[rank] = across[rank - 1] + depths[rank - 1] / 2.0 + self.rank_sep + depths[rank] / 2.0;
+        }
+        let place = |along: f64, across: f64| match self.rank_dir {
+            RankDir::TopToBottom => Point::new(along, across),
+            RankDir::BottomToTop => Point::new(along, -across),
+            RankDir::LeftToRight => Point::new(across, along),
+            RankDir::RightToLeft => Point::new(-across, along),
+        };
+        let position = |v: usize| place(along[v], across[vertices[v].rank]);
+        let centers: Vec<Point> = (0..units.len()).map(position).collect();
+
+        let mut bends: Vec<Vec<Point>> = chains.iter().map(|chain| {
+            chain[1..chain.len() - 1].iter().map(|&v| position(v)).collect()
+        }).collect();
+        let mut position_in_layer = vec![0; vertices.len()];
+        for layer in &layers {
+            for (i, &v) in layer.iter().enumerate() {
+                position_in_layer[v] = i;
+            }
+        }
+        for (e, &(_, start, end)) in units.edges.iter().enumerate() {
+            if start != end && ranks[start] == ranks[end] && position_in_layer[start].abs_diff(position_in_layer[end]) > 1 {
+                // Arc over the nodes between the ends of an edge within a layer
+                let rank = ranks[start];
+                let middle = (along[start] + along[end]) / 2.0;
+                bends[e] = vec![place(middle, across[rank] - depths[rank] / 2.0 - self.rank_sep / 3.0)];
+            }
+        }
+        route_self_loops(units, &centers, &mut bends);
+        (centers, bends)
+    }
+
+    /// Assign each unit to a layer, keeping `rank=same` subgraphs together.
+    fn assign_ranks(&self, graph: &Graph, units: &Units) -> Vec<usize> {
+        // Union the members of each rank=same subgraph
+        let mut group: Vec<usize> = (0..units.len()).collect();
+        fn find(group: &mut [usize], x: usize) -> usize {
+            let mut root = x;
+            while group[root] != root {
+                root = group[root];
+            }
+            group[x] = root;
+            root
+        }
+        for subgraph in graph.sorted_subgraphs() {
+            let same = graph.get_subgraph_properties(subgraph.id())
+                .and_then(|properties| text(properties, "rank"))
+                .is_some_and(|rank| rank == "same");
+            if !same {
+                continue;
+            }
+            let members: Vec<usize> = (0..units.len()).filter(|&unit| {
+                let mut current = graph.parent_of(units.ids[unit]);
+                let mut depth = 0;
+                while let Some(parent) = current {
+                    if parent == subgraph.id() {
+                        return true;
+                    }
+                    depth += 1;
+                    if depth > units.len() {
+                        break;
+                    }
+                    current = graph.parent_of(parent);
+                }
+                false
+            }).collect();
+            for pair in members.windows(2) {
+                let (a, b) = (find(&mut group, pair[0]), find(&mut group, pair[1]));
+                group[a.max(b)] = a.min(b);
+            }
+        }
+        let group: Vec<usize> = (0..units.len()).map(|unit| find(&mut group, unit)).collect();
+
+        // Break cycles by reversing the edges a depth-first search finds going back up its stack
+        let mut successors = vec![Vec::new(); units.len()];
+        for &(_, start, end) in &units.edges {
+            let (start, end) = (group[start], group[end]);
+            if start != end {
+                successors[start].push(end);
+            }
+        }
+        let mut state = vec![0u8; units.len()];
+        let mut dag = vec![Vec::new(); units.len()];
+        for root in 0..units.len() {
+            if group[root] != root || state[root] != 0 {
+                continue;
+            }
+            let mut stack = vec![(root, 0)];
+            state[root] = 1;
+            while let Some((v, i)) = stack.pop() {
+                if i == successors[v].len() {
+                    state[v] = 2;
+                    continue;
+                }
+                stack.push((v, i + 1));
+                let w = successors[v][i];
+                match state[w] {
+                    0 => {
+                        dag[v].push(w);
+                        state[w] = 1;
+                        stack.push((w, 0));
+                    }
+                    1 => dag[w].push(v),
+                    _ => dag[v].push(w),
+                }
+            }
+        }
+
+        // Longest path layering in topological order
+        let mut indegree = vec![0; units.len()];
+        for targets in &dag {
+            for &w in targets {
+                indegree[w] += 1;
+            }
+        }
+        let mut queue: Vec<usize> = (0..units.len()).filter(|&v| group[v] == v && indegree[v] == 0).collect();
+        let mut order = Vec::new();
+        let mut rank = vec![0; units.len()];
+        while let Some(v) = queue.pop() {
+            order.push(v);
+            for &w in &dag[v] {
+                rank[w] = rank[w].max(rank[v] + 1);
+                indegree[w] -= 1;
+                if indegree[w] == 0 {
+                    queue.push(w);
+                }
+            }
+        }
+        // Pull sources down next to their nearest successor
+        let has_predecessor: Vec<bool> = {
+            let mut has = vec![false; units.len()];
+            for targets in &dag {
+                for &w in targets {
+                    has[w] = true;
+                }
+            }
+            has
+        };
+        for &v in order.iter().rev() {
+            if !has_predecessor[v] {
+                if let Some(nearest) = dag[v].iter().map(|&w| rank[w]).min() {
+                    rank[v] = nearest - 1;
+                }
+            }
+        }
+        (0..units.len()).map(|unit| rank[group[unit]]).collect()
+    }
+
+    /// Place the vertices of each layer along it, pulling each towards its neighbours in the adjacent layers.
+    fn place_along(&self, layers: &[Vec<usize>], vertices: &[Vertex], above: &[Vec<usize>], below: &[Vec<usize>]) -> Vec<f64> {
+        let mut along = vec![0.0; vertices.len()];
+        for layer in layers {
+            let mut offset = 0.0;
+            for &v in layer {
+                along[v] = offset + vertices[v].breadth / 2.0;
+                offset += vertices[v].breadth + self.node_sep;
+            }
+            for &v in layer {
+                along[v] -= (offset - self.node_sep) / 2.0;
+            }
+        }
+
+        let place_layer = |along: &mut Vec<f64>, layer: &[usize], neighbours: &dyn Fn(usize) -> Vec<usize>| {
+            let mut targets = Vec::with_capacity(layer.len());
+            let mut weights = Vec::with_capacity(layer.len());
+            let mut offsets = Vec::with_capacity(layer.len());
+            let mut offset = 0.0;
+            for (i, &v) in layer.iter().enumerate() {
+                if i > 0 {
+                    offset += (vertices[layer[i - 1]].breadth + vertices[v].breadth) / 2.0 + self.node_sep;
+                }
+                let adjacent = neighbours(v);
+                let (target, weight) = if adjacent.is_empty() {
+                    (along[v], 0.1)
+                } else {
+                    let mean = adjacent.iter().map(|&w| along[w]).sum::<f64>() / adjacent.len() as f64;
+                    // Bends weigh more so that long edges come out straight
+                    (mean, if vertices[v].unit.is_none() { 4.0 } else { 1.0 })
+                };
+                targets.push(target - offset);
+                weights.push(weight);
+                offsets.push(offset);
+            }
+            for (i, value) in isotonic(&targets, &weights).into_iter().enumerate() {
+                along[layer[i]] = value + offsets[i];
+            }
+        };
+
+        for iteration in 0..10 {
+            if iteration < 8 {
+                for layer in layers.iter().skip(1) {
+                    place_layer(&mut along, layer, &|v| above[v].clone());
+                }
+                for layer in layers.iter().rev().skip(1) {
+                    place_layer(&mut along, layer, &|v| below[v].clone());
+                }
+            } else {
+                for layer in layers {
+                    place_layer(&mut along, layer, &|v| above[v].iter().chain(&below[v]).copied().collect());
+                }
+            }
+        }
+        along
+    }
+}
+
+/// Reorder the vertices in each layer to reduce edge crossings, keeping the best ordering found.
+fn order_layers(mut layers: Vec<Vec<usize>>, above: &[Vec<usize>], below: &[Vec<usize>], count: usize) -> Vec<Vec<usize>> {
+    let mut position = vec![0.0; count];
+    let update = |position: &mut Vec<f64>, layer: &[usize]| {
+        for (i, &v) in layer.iter().enumerate() {
+            position[v] = i as f64;
+        }
+    };
+    for layer in &layers {
+        update(&mut position, layer);
+    }
+    let mut best = layers.clone();
+    let mut best_crossings = crossings(&layers, below, &position);
+    for iteration in 0..24 {
+        if best_crossings == 0 {
+            break;
+        }
+        let sweep: Vec<usize> = if iteration % 2 == 0 {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for rank in sweep {
+            let neighbours = if iteration % 2 == 0 { above } else { below };
+            let mut keyed: Vec<(f64, usize)> = layers[rank].iter().map(|&v| {
+                let adjacent = &neighbours[v];
+                let key = if adjacent.is_empty() {
+                    position[v]
+                } else {
+                    adjacent.iter().map(|&w| position[w]).sum::<f64>() / adjacent.len() as f64
+                };
+                (key, v)
+            }).collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[rank] = keyed.into_iter().map(|(_, v)| v).collect();
+            update(&mut position, &layers[rank]);
+        }
+        let count = crossings(&layers, below, &position);
+        if count < best_crossings {
+            best = layers.clone();
+            best_crossings = count;
+        }
+    }
+    best
+}
+
+/// Count the crossings between the edges of each pair of adjacent layers.
+fn crossings(layers: &[Vec<usize>], below: &[Vec<usize>], position: &[f64]) -> usize {
+    let mut total = 0;
+    for rank in 0..layers.len().saturating_sub(1) {
+        let mut segments: Vec<(usize, usize)> = layers[rank].iter()
+            .flat_map(|&u| below[u].iter().map(move |&v| (position[u] as usize, position[v] as usize)))
+            .collect();
+        segments.sort();
+        // Count inversions of the lower ends with a Fenwick tree
+        let size = layers[rank + 1].len() + 1;
+        let mut tree = vec![0usize; size + 1];
+        for (seen, &(_, lower)) in segments.iter().enumerate() {
+            let mut not_greater = 0;
+            let mut i = lower + 1;
+            while i > 0 {
+                not_greater += tree[i];
+                i -= i & i.wrapping_neg();
+            }
+            total += seen - not_greater;
+            let mut i = lower + 1;
+            while i <= size {
+                tree[i] += 1;
+                i += i & i.wrapping_neg();
+            }
+        }
+    }
+    total
+}
+
+/// Weighted least-squares fit of a non-decreasing sequence to `targets` (pool adjacent violators).
+fn isotonic(targets: &[f64], weights: &[f64]) -> Vec<f64> {
+    // Each block is (total weight, weighted sum, length)
+    let mut blocks: Vec<(f64, f64, usize)> = Vec::new();
+    for (&target, &weight) in targets.iter().zip(weights) {
+        blocks.push((weight, weight * target, 1));
+        while blocks.len() > 1 {
+            let (w2, s2, n2) = blocks[blocks.len() - 1];
+            let (w1, s1, n1) = blocks[blocks.len() - 2];
+            if s1 / w1 <= s2 / w2 {
+                break;
+            }
+            blocks.pop();
+            *blocks.last_mut().unwrap() = (w1 + w2, s1 + s2, n1 + n2);
+        }
+    }
+    blocks.into_iter().flat_map(|(weight, sum, length)| std::iter::repeat_n(sum / weight, length)).collect()
+}
+
+/// Route edges from a unit to itself in loops around its right side.
+pub(crate) fn route_self_loops(units: &Units, centers: &[Point], bends: &mut [Vec<Point>]) {
+    let mut loops = vec![0; units.len()];
+    for (e, &(_, start, end)) in units.edges.iter().enumerate() {
+        if start == end {
+            let (width, height) = units.sizes[start];
+            let offset = 16.0 + 10.0 * loops[start] as f64;
+            loops[start] += 1;
+            let center = centers[start];
+            bends[e] = vec![
+                Point::new(center.x + width / 2.0 + offset, center.y - height / 4.0),
+                Point::new(center.x + width / 2.0 + offset, center.y + height / 4.0),
+            ];
+        }
+    }
+}
+
+/// Bow out straight edges which join the same pair of units so they don't draw on top of each other.
+pub(crate) fn spread_parallel_edges(units: &Units, centers: &[Point], bends: &mut [Vec<Point>]) {
+    let mut groups: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (e, &(_, start, end)) in units.edges.iter().enumerate() {
+        if start != end && bends[e].is_empty() {
+            groups.entry((start.min(end), start.max(end))).or_default().push(e);
+        }
+    }
+    for ((a, b), edges) in groups {
+        if edges.len() < 2 {
+            continue;
+        }
+        let (from, to) = (centers[a], centers[b]);
+        let length = from.distance(to).max(f64::EPSILON);
+        let normal = Point::new(-(to.y - from.y) / length, (to.x - from.x) / length);
+        let middle = from.lerp(to, 0.5);
+        for (k, &e) in edges.iter().enumerate() {
+            let offset = (k as f64 - (edges.len() - 1) as f64 / 2.0) * 14.0;
+            bends[e] = vec![Point::new(middle.x + normal.x * offset, middle.y + normal.y * offset)];
+        }
+    }
+}
+
+/// A force-directed (Fruchterman–Reingold) layout, like Graphviz's `fdp`.
+///
+/// Repulsion between nodes is approximated with a Barnes–Hut quadtree, so graphs with tens of
+/// thousands of nodes stay fast. Nodes start at their `pos` attribute (`"x,y"` in inches) if they
+/// have one, and stay there if it ends in `!` or they have `pin=true`. Everything else starts at a
+/// random position chosen from the seed, so the same seed always gives the same layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceLayout {
+    ideal_length: f64,
+    iterations: usize,
+    theta: f64,
+    seed: u64,
+}
+
+impl Default for ForceLayout {
+    fn default() -> Self {
+        ForceLayout {
+            ideal_length: 0.3 * POINTS_PER_INCH,
+            iterations: 300,
+            theta: 0.8,
+            seed: 1,
+        }
+    }
+}
+
+impl ForceLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure the layout from the graph's `K`, `maxiter` and `start` attributes.
+    pub fn for_graph(graph: &Graph) -> Self {
+        let defaults = Self::default();
+        let properties = graph.properties();
+        ForceLayout {
+            ideal_length: number(properties, "K").map(|k| k * POINTS_PER_INCH).unwrap_or(defaults.ideal_length),
+            iterations: number(properties, "maxiter").map(|n| n.max(0.0) as usize).unwrap_or(defaults.iterations),
+            seed: number(properties, "start").map(|seed| seed as u64).unwrap_or(defaults.seed),
+            ..defaults
+        }
+    }
+
+    /// Set the space the layout aims to leave between the outlines of connected nodes, in points.
+    pub fn with_ideal_length(mut self, ideal_length: f64) -> Self {
+        self.ideal_length = ideal_length;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Set how coarse the Barnes–Hut approximation is. Zero computes every repulsion exactly.
+    pub fn with_theta(mut self, theta: f64) -> Self {
+        self.theta = theta;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn layout(&self, graph: &Graph) -> Layout {
+        let units = Units::new(graph, false);
+        let count = units.len();
+        let sizes = &units.sizes;
+        let mean_size = sizes.iter().map(|&(w, h)| w.max(h)).sum::<f64>() / count.max(1) as f64;
+        let k = self.ideal_length + mean_size;
+        let side = k * (count as f64).sqrt();
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut pinned = vec![false; count];
+        let mut centers: Vec<Point> = (0..count).map(|unit| {
+            let properties = graph.resolved_node_properties(units.ids[unit]);
+            match pinned_position(&properties) {
+                Some((position, pin)) => {
+                    pinned[unit] = pin;
+                    position
+                }
+                None => Point::new(rng.gen_range(0.0..side), rng.gen_range(0.0..side)),
+            }
+        }).collect();
+
+        let mut temperature = side / 10.0 + k;
+        let cooling = temperature / (self.iterations + 1) as f64;
+        for _ in 0..self.iterations {
+            let tree = QuadTree::new(&centers);
+            let mut forces: Vec<Point> = (0..count)
+                .map(|unit| if pinned[unit] { Point::default() } else { tree.repulsion(unit, &centers, k * k, self.theta) })
+                .collect();
+            for &(_, start, end) in &units.edges {
+                if start == end {
+                    continue;
+                }
+                let (from, to) = (centers[start], centers[end]);
+                let distance = from.distance(to);
+                let pull = Point::new((to.x - from.x) * distance / k, (to.y - from.y) * distance / k);
+                forces[start].x += pull.x;
+                forces[start].y += pull.y;
+                forces[end].x -= pull.x;
+                forces[end].y -= pull.y;
+            }
+            // A weak pull towards the middle keeps disconnected parts from drifting apart
+            let middle = Point::new(
+                centers.iter().map(|p| p.x).sum::<f64>() / count as f64,
+                centers.iter().map(|p| p.y).sum::<f64>() / count as f64,
+            );
+            for unit in 0..count {
+                if pinned[unit] {
+                    continue;
+                }
+                let force = Point::new(
+                    forces[unit].x + (middle.x - centers[unit].x) * 0.5,
+                    forces[unit].y + (middle.y - centers[unit].y) * 0.5,
+                );
+                let length = force.x.hypot(force.y);
+                if length > f64::EPSILON {
+                    let step = length.min(temperature) / length;
+                    centers[unit].x += force.x * step;
+                    centers[unit].y += force.y * step;
+                }
+            }
+            temperature -= cooling;
+        }
+        remove_overlaps(&mut centers, sizes, &pinned, self.ideal_length / 2.0);
+
+        let mut bends = vec![Vec::new(); units.edges.len()];
+        route_self_loops(&units, &centers, &mut bends);
+        spread_parallel_edges(&units, &centers, &mut bends);
+        units.finish(graph, &centers, bends)
+    }
+}
+
+/// Read a node's `pos` attribute as a point, and whether the node is pinned there.
+///
+/// Positions are in inches with `y` growing upwards, as in Graphviz.
+fn pinned_position(properties: &Properties) -> Option<(Point, bool)> {
+    let pos = text(properties, "pos")?;
+    let pos = pos.trim();
+    let (coordinates, pinned) = match pos.strip_suffix('!') {
+        Some(coordinates) => (coordinates, true),
+        None => (pos, false),
+    };
+    let mut parts = coordinates.split(',').map(|part| part.trim().parse::<f64>());
+    let (x, y) = (parts.next()?.ok()?, parts.next()?.ok()?);
+    let pin = matches!(properties.get("pin"), Some(Value::Bool(true))) || text(properties, "pin").as_deref() == Some("true");
+    Some((Point::new(x * POINTS_PER_INCH, -y * POINTS_PER_INCH), pinned || pin))
+}
+
+/// A cell of a Barnes–Hut quadtree, holding the total mass and center of mass of the points inside it.
+struct Cell {
+    size: f64,
+    mass: f64,
+    center: Point,
+    /// The range of `QuadTree::members` inside this cell
+    members: (usize, usize),
+    /// The children of an inner cell, which are stored next to each other
+    children: (usize, usize),
+}
+
+/// A quadtree over points for approximating the repulsion from far away groups of points.
+struct QuadTree {
+    cells: Vec<Cell>,
+    /// The points, ordered so that every cell's points are next to each other
+    members: Vec<usize>,
+}
+
+impl QuadTree {
+    fn new(points: &[Point]) -> Self {
+        let mut tree = QuadTree { cells: Vec::new(), members: (0..points.len()).collect() };
+        if points.is_empty() {
+            return tree;
+        }
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for point in points {
+            min_x = min_x.min(point.x);
+            min_y = min_y.min(point.y);
+            max_x = max_x.max(point.x);
+            max_y = max_y.max(point.y);
+        }
+        let size = (max_x - min_x).max(max_y - min_y).max(1.0);
+        tree.cells.push(Cell { size, mass: 0.0, center: Point::default(), members: (0, points.len()), children: (0, 0) });
+        tree.build(points, 0, Point::new(min_x, min_y), 0);
+        tree
+    }
+
+    /// Fill in a cell covering the square at `corner`, splitting it into quadrants as needed.
+    fn build(&mut self, points: &[Point], index: usize, corner: Point, depth: usize) {
+        let (start, end) = self.cells[index].members;
+        let members = &mut self.members[start..end];
+        let mass = members.len() as f64;
+        let center = Point::new(
+            members.iter().map(|&p| points[p].x).sum::<f64>() / mass,
+            members.iter().map(|&p| points[p].y).sum::<f64>() / mass,
+        );
+        self.cells[index].mass = mass;
+        self.cells[index].center = center;
+        // Points which are very close together share a leaf rather than splitting forever
+        if members.len() == 1 || depth > 32 {
+            return;
+        }
+
+        let half = self.cells[index].size / 2.0;
+        let quadrant = |p: usize| (points[p].x >= corner.x + half) as usize + 2 * (points[p].y >= corner.y + half) as usize;
+        members.sort_unstable_by_key(|&p| quadrant(p));
+        let mut bounds = [start; 5];
+        for (q, bound) in bounds.iter_mut().enumerate().skip(1) {
+            *bound = start + members.partition_point(|&p| quadrant(p) < q);
+        }
+        let first = self.cells.len();
+        let mut corners = Vec::new();
+        for q in 0..4 {
+            if bounds[q] < bounds[q + 1] {
+                self.cells.push(Cell { size: half, mass: 0.0, center: Point::default(), members: (bounds[q], bounds[q + 1]), children: (0, 0) });
+                corners.push(Point::new(
+                    corner.x + if q % 2 == 1 { half } else { 0.0 },
+                    corner.y + if q >= 2 { half } else { 0.0 },
+                ));
+            }
+        }
+        self.cells[index].children = (first, self.cells.len());
+        for (child, corner) in (first..self.cells.len()).zip(corners) {
+            self.build(points, child, corner, depth + 1);
+        }
+    }
+
+    /// The repulsive force on a point from all the others, where two points at distance `d` repel with `strength / d`.
+    fn repulsion(&self, point: usize, points: &[Point], strength: f64, theta: f64) -> Point {
+        let mut force = Point::default();
+        if self.cells.is_empty() {
+            return force;
+        }
+        let position = points[point];
+        let mut push = |from: Point, mass: f64, other: usize| {
+            let (mut dx, mut dy) = (position.x - from.x, position.y - from.y);
+            let mut distance_squared = dx * dx + dy * dy;
+            if distance_squared < 1e-6 {
+                // Separate points on top of each other in a direction that depends on which points they are
+                let angle = (point as f64 - other as f64) * 2.399;
+                dx = angle.cos() * 0.01;
+                dy = angle.sin() * 0.01;
+                distance_squared = 1e-4;
+            }
+            force.x += dx * strength * mass / distance_squared;
+            force.y += dy * strength * mass / distance_squared;
+        };
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let cell = &self.cells[index];
+            if cell.children.0 == cell.children.1 {
+                for &other in &self.members[cell.members.0..cell.members.1] {
+                    if other != point {
+                        push(points[other], 1.0, other);
+                    }
+                }
+            } else if cell.size * cell.size < theta * theta * {
+                let (dx, dy) = (position.x - cell.center.x, position.y - cell.center.y);
+                dx * dx + dy * dy
+            } {
+                push(cell.center, cell.mass, usize::MAX);
+            } else {
+                stack.extend(cell.children.0..cell.children.1);
+            }
+        }
+        force
+    }
+}
+
+/// Push apart nodes whose boxes overlap, leaving `gap` between them where possible.
+fn remove_overlaps(centers: &mut [Point], sizes: &[(f64, f64)], pinned: &[bool], gap: f64) {
+    let mut order: Vec<usize> = (0..centers.len()).collect();
+    for _ in 0..50 {
+        let mut moved = false;
+        // Sweep from left to right, only comparing nodes whose horizontal extents overlap
+        order.sort_by(|&a, &b| (centers[a].x - sizes[a].0 / 2.0).total_cmp(&(centers[b].x - sizes[b].0 / 2.0)));
+        for i in 0..order.len() {
+            let a = order[i];
+            for &b in &order[i + 1..] {
+                let left = centers[b].x - sizes[b].0 / 2.0;
+                if left >= centers[a].x + sizes[a].0 / 2.0 + gap {
+                    break;
+                }
+                let overlap_x = (sizes[a].0 + sizes[b].0) / 2.0 + gap - (centers[a].x - centers[b].x).abs();
+                let overlap_y = (sizes[a].1 + sizes[b].1) / 2.0 + gap - (centers[a].y - centers[b].y).abs();
+                if overlap_x <= 0.0 || overlap_y <= 0.0 || (pinned[a] && pinned[b]) {
+                    continue;
+                }
+                moved = true;
+                // Move along whichever axis needs the smaller push, splitting it between unpinned nodes
+                let (share_a, share_b) = match (pinned[a], pinned[b]) {
+                    (true, _) => (0.0, 1.0),
+                    (_, true) => (1.0, 0.0),
+                    _ => (0.5, 0.5),
+                };
+                if overlap_x < overlap_y {
+                    let sign = if centers[a].x <= centers[b].x { 1.0 } else { -1.0 };
+                    centers[a].x -= sign * overlap_x * share_a;
+                    centers[b].x += sign * overlap_x * share_b;
+                } else {
+                    let sign = if centers[a].y <= centers[b].y { 1.0 } else { -1.0 };
+                    centers[a].y -= sign * overlap_y * share_a;
+                    centers[b].y += sign * overlap_y * share_b;
+                }
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+}
+
+impl Graph {
+    /// Lay out the graph with the native layered layout, configured from the graph's attributes.
+    pub fn layered_layout(&self) -> Layout {
+        LayeredLayout::for_graph(self).layout(self)
+    }
+
+    /// Lay out the graph with the native force-directed layout, configured from the graph's attributes.
+    pub fn force_layout(&self) -> Layout {
+        ForceLayout::for_graph(self).layout(self)
+    }
+
+    /// Lay out the graph natively, using the force-directed layout when the graph's `layout`
+    /// attribute asks for one of Graphviz's spring model engines, and the layered layout otherwise.
+    pub fn native_layout(&self) -> Layout {
+        match text(self.properties(), "layout").as_deref() {
+            Some("neato" | "fdp" | "sfdp") => self.force_layout(),
+            _ => self.layered_layout(),
+        }
+    }
+}
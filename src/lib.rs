@@ -10,9 +10,48 @@ pub use edge::*;
 mod record;
 pub use record::*;
 
+mod subgraph;
+pub use subgraph::*;
+
 mod graph;
 pub use graph::*;
 
+mod error;
+pub use error::*;
+
+mod layout;
+pub use layout::*;
+
+mod svg;
+
+mod ascii;
+
+mod render;
+pub use render::*;
+
+mod dot;
+pub use dot::*;
+
+mod mermaid;
+
+mod graphml;
+
+mod serialize;
+
+mod validate;
+pub use validate::*;
+
+mod paths;
+pub use paths::*;
+
+mod dag;
+
+mod components;
+
+mod transitive;
+
+mod centrality;
+
 mod props;
 pub use props::*;
 
@@ -0,0 +1,523 @@
+use std::collections::{HashMap, HashSet};
+use super::{Graph, GraphKind, Node, ParseError, Properties, RankDir, Shape, Subgraph, Value, ID};
+use super::layout::{node_label, text};
+use super::dot::id_of_name;
+
+/// Mermaid's name for a layout direction.
+fn direction(rank_dir: RankDir) -> &'static str {
+    match rank_dir {
+        RankDir::TopToBottom => "TD",
+        RankDir::LeftToRight => "LR",
+        RankDir::BottomToTop => "BT",
+        RankDir::RightToLeft => "RL",
+    }
+}
+
+/// Quote a label, replacing the characters Mermaid can't show in strings with entity codes.
+fn quote(label: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in label.chars() {
+        match c {
+            '"' => quoted.push_str("#quot;"),
+            '<' => quoted.push_str("#lt;"),
+            '>' => quoted.push_str("#gt;"),
+            '#' => quoted.push_str("#35;"),
+            '\n' => quoted.push_str("<br>"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Undo `quote`'s entity codes and line breaks.
+fn unquote(label: &str) -> String {
+    let mut text = String::new();
+    let mut rest = label.trim();
+    if let Some(inner) = rest.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        rest = inner;
+    }
+    while let Some(c) = rest.chars().next() {
+        let lower = rest.to_ascii_lowercase();
+        let entity = rest.strip_prefix('#').and_then(|code| code.find(';').map(|end| &code[..end]));
+        let decoded = entity.and_then(|entity| match entity {
+            "quot" => Some('"'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            code => code.parse().ok().and_then(char::from_u32),
+        });
+        if let (Some(entity), Some(decoded)) = (entity, decoded) {
+            text.push(decoded);
+            rest = &rest[entity.len() + 2..];
+        } else if let Some(tag) = ["<br>", "<br/>", "<br />"].iter().find(|tag| lower.starts_with(*tag)) {
+            text.push('\n');
+            rest = &rest[tag.len()..];
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    text
+}
+
+/// The brackets around the label of a node with this shape.
+fn brackets(shape: Shape) -> (&'static str, &'static str) {
+    match shape {
+        Shape::Ellipse => ("(", ")"),
+        Shape::Circle | Shape::Point => ("((", "))"),
+        Shape::DoubleCircle => ("(((", ")))"),
+        Shape::Diamond => ("{", "}"),
+        Shape::Box | Shape::Record | Shape::Plain => ("[", "]"),
+    }
+}
+
+/// The node shapes the parser understands, with the `shape` each becomes. Longer brackets come
+/// first so they aren't mistaken for the shorter ones they start with.
+const SHAPES: [(&str, &str, Option<&str>); 9] = [
+    ("(((", ")))", Some("doublecircle")),
+    ("((", "))", Some("circle")),
+    ("([", "])", None),
+    ("[[", "]]", Some("box")),
+    ("[(", ")]", Some("cylinder")),
+    ("{{", "}}", Some("hexagon")),
+    ("(", ")", None),
+    ("[", "]", Some("box")),
+    ("{", "}", Some("diamond")),
+];
+
+impl Graph {
+    /// Write the graph as a Mermaid flowchart.
+    ///
+    /// Nodes keep their labels and the shapes Mermaid has (boxes, ellipses, circles, double
+    /// circles and diamonds), and clusters become Mermaid subgraphs. Records are written as
+    /// single boxes listing their fields, with edges to their fields attached to the whole box.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = format!("flowchart {}\n", direction(RankDir::from_properties(self.properties())));
+        let clusters: HashSet<ID> = self.sorted_subgraphs().into_iter().filter(|s| s.is_cluster()).map(|s| s.id()).collect();
+        self.write_mermaid_block(&mut mermaid, &clusters, None, 1);
+
+        for edge in self.sorted_edges() {
+            let properties = self.resolved_edge_properties(edge.id());
+            let default_dir = if self.is_directed() { "forward" } else { "none" };
+            let dir = text(&properties, "dir").unwrap_or_else(|| default_dir.to_string());
+            let style = text(&properties, "style").unwrap_or_default();
+            let (line, arrow) = if style.contains("dashed") || style.contains("dotted") {
+                ("-.-", "-.->")
+            } else if style.contains("bold") {
+                ("===", "==>")
+            } else {
+                ("---", "-->")
+            };
+            let mut op = match dir.as_str() {
+                "forward" => arrow.to_string(),
+                "back" | "both" => format!("<{}", arrow),
+                _ => line.to_string(),
+            };
+            if let Some(label) = text(&properties, "label") {
+                op.push_str(&format!("|{}|", quote(&label)));
+            }
+            let (mut start, mut end) = (edge.start_node(), edge.end_node());
+            if dir == "back" {
+                // Mermaid has no arrows pointing back alone, so turn the edge around
+                std::mem::swap(&mut start, &mut end);
+                op = op.replacen('<', "", 1);
+            }
+            mermaid.push_str(&format!("    {} {} {}\n", mermaid_name(start), op, mermaid_name(end)));
+        }
+        mermaid
+    }
+
+    /// Write the records, nodes and clusters in the cluster `parent`, recursing into clusters.
+    /// Plain subgraphs have no Mermaid equivalent, so their contents go in the nearest cluster.
+    fn write_mermaid_block(&self, mermaid: &mut String, clusters: &HashSet<ID>, parent: Option<ID>, depth: usize) {
+        let indent = "    ".repeat(depth);
+        let cluster_of = |id: ID| {
+            let mut current = self.parent_of(id);
+            while let Some(subgraph) = current.filter(|subgraph| !clusters.contains(subgraph)) {
+                current = self.parent_of(subgraph);
+            }
+            current
+        };
+        for record in self.sorted_records() {
+            if cluster_of(record.id()) != parent {
+                continue;
+            }
+            let fields: Vec<&str> = record.nodes().columns().flat_map(|column| column.cells().map(|(_, label)| label)).collect();
+            mermaid.push_str(&format!("{}{}[{}]\n", indent, record.name(), quote(&fields.join(" | "))));
+        }
+        for node in self.sorted_nodes() {
+            if cluster_of(node.id()) != parent {
+                continue;
+            }
+            let properties = self.resolved_node_properties(node.id());
+            let (open, close) = brackets(Shape::from_properties(&properties));
+            let label = node_label(&node.name(), &properties);
+            mermaid.push_str(&format!("{}{}{}{}{}\n", indent, node.name(), open, quote(&label), close));
+        }
+        for subgraph in self.sorted_subgraphs() {
+            let id = subgraph.id();
+            if !clusters.contains(&id) || cluster_of(id) != parent {
+                continue;
+            }
+            let properties = self.get_subgraph_properties(id).unwrap();
+            let label = text(properties, "label").unwrap_or_else(|| " ".to_string());
+            mermaid.push_str(&format!("{}subgraph {}[{}]\n", indent, subgraph.name(), quote(&label)));
+            if properties.get("rankdir").is_some() {
+                mermaid.push_str(&format!("{}    direction {}\n", indent, direction(RankDir::from_properties(properties))));
+            }
+            self.write_mermaid_block(mermaid, clusters, Some(id), depth + 1);
+            mermaid.push_str(&format!("{}end\n", indent));
+        }
+    }
+
+    /// Parse a Mermaid flowchart.
+    ///
+    /// This understands nodes with the shapes `to_mermaid` writes (plus stadiums, subroutines,
+    /// cylinders and hexagons), chains of links with `&`, solid, dotted and thick links with
+    /// or without arrows and labels, and nested subgraphs, which become clusters. Like
+    /// `from_dot`, nodes are identified by `ID::from` their Mermaid ID, except the names
+    /// `to_mermaid` writes, which keep their IDs. Styling statements such as `classDef` and
+    /// `style` are skipped.
+    pub fn from_mermaid(source: &str) -> Result<Graph, ParseError> {
+        MermaidParser::new().parse(source)
+    }
+}
+
+/// The DOT name of an edge endpoint, using the whole record for record fields.
+fn mermaid_name(node: Node) -> String {
+    match node.record() {
+        Some(record) => format!("Record_{}", record),
+        None => format!("Node_{}", node.id()),
+    }
+}
+
+/// A position in a single statement of a Mermaid diagram.
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.rest().trim().is_empty()
+    }
+
+    fn error(&self, message: impl ToString) -> ParseError {
+        ParseError::new(self.line, self.column + self.text[..self.pos].chars().count(), message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.text.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take characters while `f` holds for them.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let length = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.pos += length;
+        &self.text[start..self.pos]
+    }
+
+    fn name(&mut self) -> Result<&'a str, ParseError> {
+        self.skip_whitespace();
+        let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        if name.is_empty() {
+            Err(self.error("expected a node ID"))
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// Take the text up to `close`, which may be quoted.
+    fn until(&mut self, close: &str) -> Result<&'a str, ParseError> {
+        let start = self.pos;
+        if self.rest().starts_with('"') {
+            match self.rest()[1..].find('"') {
+                Some(end) => self.pos += end + 2,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        match self.rest().find(close) {
+            Some(end) => {
+                self.pos += end + close.len();
+                Ok(&self.text[start..self.pos - close.len()])
+            }
+            None => Err(self.error(format!("expected `{}`", close))),
+        }
+    }
+}
+
+struct MermaidParser {
+    graph: Graph,
+    nodes: HashMap<String, Node>,
+    subgraphs: Vec<ID>,
+}
+
+impl MermaidParser {
+    fn new() -> Self {
+        MermaidParser {
+            graph: Graph::new().with_kind(GraphKind::Directed),
+            nodes: HashMap::new(),
+            subgraphs: Vec::new(),
+        }
+    }
+
+    fn parse(mut self, source: &str) -> Result<Graph, ParseError> {
+        let mut header = false;
+        let mut front_matter = false;
+        let mut line_number = 0;
+        for (i, line) in source.lines().enumerate() {
+            line_number = i + 1;
+            let trimmed = line.trim();
+            if trimmed == "---" && !header {
+                front_matter = !front_matter;
+                continue;
+            }
+            if front_matter || trimmed.is_empty() || trimmed.starts_with("%%") {
+                continue;
+            }
+            for (column, statement) in split_statements(line) {
+                let mut cursor = Cursor { text: statement, pos: 0, line: i + 1, column };
+                if header {
+                    self.statement(&mut cursor)?;
+                } else {
+                    self.header(&mut cursor)?;
+                    header = true;
+                }
+            }
+        }
+        if !header {
+            return Err(ParseError::new(line_number.max(1), 1, "expected `flowchart` or `graph`"));
+        }
+        if !self.subgraphs.is_empty() {
+            return Err(ParseError::new(line_number, 1, "expected `end` to close a subgraph"));
+        }
+        Ok(self.graph)
+    }
+
+    fn header(&mut self, cursor: &mut Cursor) -> Result<(), ParseError> {
+        let keyword = cursor.name().ok().filter(|keyword| matches!(*keyword, "flowchart" | "graph"));
+        if keyword.is_none() {
+            return Err(cursor.error("expected `flowchart` or `graph`"));
+        }
+        cursor.skip_whitespace();
+        if !cursor.is_done() {
+            self.direction(cursor, None)?;
+        }
+        Ok(())
+    }
+
+    /// Parse a direction, setting the `rankdir` of the graph or a subgraph.
+    fn direction(&mut self, cursor: &mut Cursor, subgraph: Option<ID>) -> Result<(), ParseError> {
+        let rank_dir = match cursor.name()? {
+            "TB" | "TD" => "TB",
+            "LR" => "LR",
+            "BT" => "BT",
+            "RL" => "RL",
+            _ => return Err(cursor.error("expected a direction: `TB`, `TD`, `LR`, `BT` or `RL`")),
+        };
+        let properties = match subgraph {
+            Some(id) => self.graph.get_subgraph_properties_mut(id).unwrap(),
+            None => self.graph.properties_mut(),
+        };
+        if subgraph.is_some() || rank_dir != "TB" {
+            properties.set("rankdir", rank_dir);
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, cursor: &mut Cursor) -> Result<(), ParseError> {
+        cursor.skip_whitespace();
+        let keyword = cursor.rest().split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "subgraph" => {
+                cursor.eat("subgraph");
+                cursor.skip_whitespace();
+                let (name, title) = match cursor.rest().find('[') {
+                    Some(open) => {
+                        let name = cursor.rest()[..open].trim();
+                        cursor.pos += open + 1;
+                        let title = cursor.until("]")?;
+                        (name, Some(unquote(title)))
+                    }
+                    None => (cursor.rest().trim(), None),
+                };
+                let name = name.trim_matches('"');
+                if name.is_empty() {
+                    return Err(cursor.error("expected a subgraph ID"));
+                }
+                let id = id_of_name(name).unwrap_or_else(|| ID::from(name));
+                if self.graph.get_subgraph_properties(id).is_none() {
+                    let label = title.unwrap_or_else(|| name.to_string());
+                    self.graph.insert_subgraph(Subgraph::cluster(id), Properties::from_iter([("label", label)]));
+                    self.graph.move_to_subgraph(id, self.subgraphs.last().copied());
+                }
+                self.subgraphs.push(id);
+            }
+            "end" => {
+                if self.subgraphs.pop().is_none() {
+                    return Err(cursor.error("`end` without a subgraph"));
+                }
+            }
+            "direction" => {
+                cursor.eat("direction");
+                self.direction(cursor, self.subgraphs.last().copied())?;
+            }
+            "style" | "classDef" | "class" | "click" | "linkStyle" => {}
+            _ => self.chain(cursor)?,
+        }
+        Ok(())
+    }
+
+    /// Parse a chain of groups of nodes joined by links, like `a & b --> c -->|label| d`.
+    fn chain(&mut self, cursor: &mut Cursor) -> Result<(), ParseError> {
+        let mut left = self.group(cursor)?;
+        while !cursor.is_done() {
+            cursor.skip_whitespace();
+            let properties = self.link(cursor)?;
+            let right = self.group(cursor)?;
+            for start in &left {
+                for end in &right {
+                    let mut builder = self.graph.new_edge(*start, *end).with_properties(properties.clone());
+                    if let Some(&subgraph) = self.subgraphs.last() {
+                        builder = builder.in_subgraph(subgraph);
+                    }
+                    builder.finalize();
+                }
+            }
+            left = right;
+        }
+        Ok(())
+    }
+
+    /// Parse nodes joined by `&`.
+    fn group(&mut self, cursor: &mut Cursor) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = vec![self.node(cursor)?];
+        loop {
+            cursor.skip_whitespace();
+            if !cursor.eat("&") {
+                return Ok(nodes);
+            }
+            nodes.push(self.node(cursor)?);
+        }
+    }
+
+    /// Parse a node ID with an optional shape and label, creating the node if it is new.
+    fn node(&mut self, cursor: &mut Cursor) -> Result<Node, ParseError> {
+        let name = cursor.name()?;
+        let mut properties = Properties::new();
+        if let Some(&(open, close, shape)) = SHAPES.iter().find(|(open, _, _)| cursor.rest().starts_with(open)) {
+            cursor.eat(open);
+            properties.set("label", unquote(cursor.until(close)?));
+            if let Some(shape) = shape {
+                properties.set("shape", shape);
+            }
+        }
+
+        if let Some(&node) = self.nodes.get(name) {
+            if !properties.is_empty() {
+                // A later definition replaces the shape given before
+                let existing = self.graph.get_node_properties_mut(node).unwrap();
+                if properties.get("shape").is_none() {
+                    existing.remove("shape");
+                }
+                existing.append(&mut properties);
+            }
+            return Ok(node);
+        }
+        if properties.is_empty() {
+            properties.set("label", name);
+            properties.set("shape", "box");
+        }
+        let id = id_of_name(name).unwrap_or_else(|| ID::from(name));
+        let mut builder = self.graph.new_node(id).with_properties(properties);
+        if let Some(&subgraph) = self.subgraphs.last() {
+            builder = builder.in_subgraph(subgraph);
+        }
+        let node = builder.finalize();
+        self.nodes.insert(name.to_string(), node);
+        Ok(node)
+    }
+
+    /// Parse a link like `-->`, `-.-`, `<==>`, `-->|label|` or `-- label -->` into the
+    /// properties of the edges it makes.
+    fn link(&mut self, cursor: &mut Cursor) -> Result<Properties, ParseError> {
+        let back = cursor.eat("<");
+        let mut line = cursor.take_while(|c| matches!(c, '-' | '.' | '='));
+        if line.len() < 2 {
+            return Err(cursor.error("expected a link like `-->`"));
+        }
+        let mut forward = cursor.eat(">");
+        let mut label = None;
+        if !forward && matches!(line, "--" | "-." | "==") && cursor.rest().starts_with(char::is_whitespace) {
+            // The label is written inside the link, and the rest of the link follows it
+            let close = ["-->", "---", ".-", "==>", "==="].iter().filter_map(|close| cursor.rest().find(close)).min();
+            let Some(at) = close else {
+                return Err(cursor.error("expected the end of the link"));
+            };
+            label = Some(unquote(&cursor.rest()[..at]));
+            cursor.pos += at;
+            line = cursor.take_while(|c| matches!(c, '-' | '.' | '='));
+            forward = cursor.eat(">");
+        }
+        cursor.skip_whitespace();
+        if cursor.eat("|") {
+            label = Some(unquote(cursor.until("|")?));
+        }
+
+        let mut properties = Properties::new();
+        match (back, forward) {
+            (true, true) => properties.set("dir", "both"),
+            (true, false) => properties.set("dir", "back"),
+            (false, false) => properties.set("dir", "none"),
+            (false, true) => {}
+        }
+        if line.contains('.') {
+            properties.set("style", "dashed");
+        } else if line.contains('=') {
+            properties.set("style", "bold");
+        }
+        if let Some(label) = label {
+            properties.set("label", Value::String(label));
+        }
+        Ok(properties)
+    }
+}
+
+/// Split a line into statements separated by semicolons outside of strings, with the column
+/// each starts at.
+fn split_statements(line: &str) -> Vec<(usize, &str)> {
+    let mut statements = Vec::new();
+    let (mut start, mut quoted) = (0, false);
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                statements.push((start, &line[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push((start, &line[start..]));
+    statements.into_iter()
+        .filter(|(_, statement)| !statement.trim().is_empty())
+        .map(|(start, statement)| (line[..start].chars().count() + 1, statement))
+        .collect()
+}
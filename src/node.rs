@@ -1,8 +1,10 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use super::{Properties, ID};
+use serde::{Deserialize, Serialize};
+use super::ID;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Node {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     record: Option<ID>,
     id: ID,
 }
@@ -21,6 +23,11 @@ impl Node {
         self.id
     }
 
+    /// The record this node is a port of, if any.
+    pub fn record(&self) -> Option<ID> {
+        self.record
+    }
+
     pub fn name(&self) -> String {
         if let Some(record) = self.record {
             format!("Record_{}:Node_{}", record, self.id)
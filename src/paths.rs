@@ -0,0 +1,197 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use super::{layout::number, Edge, Graph, GraphError, Node, Properties, Result};
+
+/// A way of finding the weight, or length, of an edge for path searches.
+///
+/// A property key such as `"weight"` reads that property of the edge (including defaults),
+/// treating edges without a numeric value as having a weight of 1. A closure receives
+/// the edge and its resolved properties.
+pub trait EdgeWeight {
+    fn weight(&self, edge: Edge, properties: &Properties) -> f64;
+}
+
+impl EdgeWeight for &str {
+    fn weight(&self, _edge: Edge, properties: &Properties) -> f64 {
+        number(properties, self).unwrap_or(1.0)
+    }
+}
+
+impl EdgeWeight for String {
+    fn weight(&self, edge: Edge, properties: &Properties) -> f64 {
+        self.as_str().weight(edge, properties)
+    }
+}
+
+impl<F: Fn(Edge, &Properties) -> f64> EdgeWeight for F {
+    fn weight(&self, edge: Edge, properties: &Properties) -> f64 {
+        self(edge, properties)
+    }
+}
+
+/// A path through a graph, found by one of the shortest path searches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    /// The nodes along the path, from its start to its end
+    pub nodes: Vec<Node>,
+    /// The edges along the path, one fewer than the nodes
+    pub edges: Vec<Edge>,
+    /// The sum of the weights of the edges
+    pub cost: f64,
+}
+
+/// A node waiting to be visited by a search, ordered so that the heap pops the lowest estimate first.
+struct Pending {
+    estimate: f64,
+    node: Node,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// The end of an edge which isn't `node`. For self-loops, this is the edge's end.
+fn other_end(edge: Edge, node: Node) -> Node {
+    if edge.start_node() == node { edge.end_node() } else { edge.start_node() }
+}
+
+impl Graph {
+    /// The edges of the graph in the directions they can be followed, with their weights.
+    /// Undirected edges are listed once in each direction.
+    fn weighted_arcs(&self, weight: &impl EdgeWeight) -> Vec<(Node, Node, Edge, f64)> {
+        let mut arcs = Vec::new();
+        for &edge in self.edges() {
+            let cost = weight.weight(edge, &self.resolved_edge_properties(edge.id()));
+            let (start, end) = (edge.start_node(), edge.end_node());
+            arcs.push((start, end, edge, cost));
+            if !self.is_directed() && start != end {
+                arcs.push((end, start, edge, cost));
+            }
+        }
+        arcs
+    }
+
+    /// Follow the edges each node was reached by back from `to` to the node the search started from.
+    pub(crate) fn trace_path(to: Node, reached_by: &HashMap<Node, Edge>, cost: f64) -> Path {
+        let mut nodes = vec![to];
+        let mut edges = Vec::new();
+        let mut node = to;
+        while let Some(&edge) = reached_by.get(&node) {
+            node = other_end(edge, node);
+            nodes.push(node);
+            edges.push(edge);
+        }
+        nodes.reverse();
+        edges.reverse();
+        Path { nodes, edges, cost }
+    }
+
+    /// Find the cheapest path between two nodes with Dijkstra's algorithm, or `None` if `to`
+    /// can't be reached from `from`. Edges are followed in their direction in directed graphs.
+    ///
+    /// Weights must not be negative; use `bellman_ford_path` for graphs with negative weights.
+    pub fn shortest_path(&self, from: impl Into<Node>, to: impl Into<Node>, weight: impl EdgeWeight) -> Option<Path> {
+        self.astar_path(from, to, weight, |_| 0.0)
+    }
+
+    /// Find the cheapest path between two nodes with A*, guided by a heuristic which estimates
+    /// the cost of the rest of the path from a node to `to`, such as the straight-line distance
+    /// between their positions.
+    ///
+    /// The heuristic must never overestimate, or the path found may not be the cheapest.
+    /// Weights must not be negative.
+    pub fn astar_path(&self, from: impl Into<Node>, to: impl Into<Node>, weight: impl EdgeWeight, heuristic: impl Fn(Node) -> f64) -> Option<Path> {
+        let (from, to) = (from.into(), to.into());
+        let mut costs = HashMap::from([(from, 0.0)]);
+        let mut reached_by: HashMap<Node, Edge> = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut pending = BinaryHeap::from([Pending { estimate: heuristic(from), node: from }]);
+
+        while let Some(Pending { node, .. }) = pending.pop() {
+            if node == to {
+                return Some(Self::trace_path(to, &reached_by, costs[&to]));
+            }
+            if !visited.insert(node) {
+                continue;
+            }
+            for edge in self.out_edges(node) {
+                let next = other_end(edge, node);
+                let cost = costs[&node] + weight.weight(edge, &self.resolved_edge_properties(edge.id()));
+                if costs.get(&next).is_none_or(|&known| cost < known) {
+                    costs.insert(next, cost);
+                    reached_by.insert(next, edge);
+                    pending.push(Pending { estimate: cost + heuristic(next), node: next });
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the cheapest path between two nodes with the Bellman-Ford algorithm, which allows
+    /// negative weights, or `None` if `to` can't be reached from `from`.
+    ///
+    /// If a cycle with a negative total weight can be reached from `from`, there is no cheapest
+    /// path, and a `GraphError::NegativeCycle` with the edges of the cycle is returned.
+    /// In undirected graphs, any edge with a negative weight is such a cycle.
+    pub fn bellman_ford_path(&self, from: impl Into<Node>, to: impl Into<Node>, weight: impl EdgeWeight) -> Result<Option<Path>> {
+        let (from, to) = (from.into(), to.into());
+        let arcs = self.weighted_arcs(&weight);
+        let count = arcs.iter().flat_map(|&(start, end, ..)| [start, end]).chain([from]).collect::<HashSet<Node>>().len();
+        let mut costs = HashMap::from([(from, 0.0)]);
+        let mut reached_by: HashMap<Node, Edge> = HashMap::new();
+
+        let relax = |costs: &mut HashMap<Node, f64>, reached_by: &mut HashMap<Node, Edge>| {
+            let mut changed = None;
+            for &(start, end, edge, cost) in &arcs {
+                let Some(&start_cost) = costs.get(&start) else { continue };
+                if costs.get(&end).is_none_or(|&known| start_cost + cost < known) {
+                    costs.insert(end, start_cost + cost);
+                    reached_by.insert(end, edge);
+                    changed = Some(end);
+                }
+            }
+            changed
+        };
+        for _ in 1..count {
+            if relax(&mut costs, &mut reached_by).is_none() {
+                break;
+            }
+        }
+        if let Some(mut start) = relax(&mut costs, &mut reached_by) {
+            // Walking back far enough from a node which still improves always ends up on the cycle
+            for _ in 0..count {
+                start = other_end(reached_by[&start], start);
+            }
+            let mut cycle = Vec::new();
+            let mut node = start;
+            loop {
+                let edge = reached_by[&node];
+                cycle.push(edge);
+                node = other_end(edge, node);
+                if node == start {
+                    break;
+                }
+            }
+            cycle.reverse();
+            return Err(GraphError::NegativeCycle(cycle));
+        }
+
+        Ok(costs.get(&to).map(|&cost| Self::trace_path(to, &reached_by, cost)))
+    }
+}
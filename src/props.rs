@@ -1,24 +1,80 @@
-use std::collections::HashMap;
-use std::str::FromStr;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::ops::{Index, IndexMut};
+use serde::de::{Error as _, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Value {
+    /// A Graphviz escString, kept as it is written in DOT: escape sequences like `\n`, `\l`,
+    /// `\N` and `\\` are never decoded, so they keep their meaning to Graphviz. Only quotes are
+    /// escaped when it's written between double quotes, and only they are unescaped when it's read.
     String(String),
+    /// An HTML-like label, emitted between angle brackets instead of quotes
+    Html(String),
     Bool(bool),
     I32(i32),
     F64(f64),
     Nothing,
 }
 
+impl Value {
+    pub fn html(html: impl ToString) -> Self {
+        Value::Html(html.to_string())
+    }
+}
+
+/// Write a backslash read from `chars`. Escape sequences and pairs of backslashes are kept as
+/// they are, but a lone backslash before a quote, a line break or the end of the string is
+/// doubled, since it would otherwise escape it.
+pub(crate) fn push_backslash(escaped: &mut String, chars: &mut Peekable<Chars>) {
+    match chars.peek() {
+        Some('\\') => {
+            chars.next();
+            escaped.push_str("\\\\");
+        }
+        Some('"' | '\n') | None => escaped.push_str("\\\\"),
+        _ => escaped.push('\\'),
+    }
+}
+
+/// Escape an escString for use inside a double-quoted DOT string.
+pub(crate) fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => push_backslash(&mut escaped, &mut chars),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format a DOT identifier, quoting it unless it is a plain alphanumeric name.
+pub(crate) fn escape_id(s: &str) -> String {
+    let mut chars = s.chars();
+    let plain = matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric());
+    if plain {
+        s.to_string()
+    } else {
+        format!("\"{}\"", escape_string(s))
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
-            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::String(s) => write!(f, "\"{}\"", escape_string(s)),
+            Value::Html(html) => write!(f, "<{}>", html),
             Value::Bool(b) => write!(f, "{}", b),
             Value::I32(i) => write!(f, "{}", i),
-            Value::F64(n) => write!(f, "{}", n),
+            Value::F64(n) if n.is_finite() => write!(f, "{}", n),
+            Value::F64(n) => write!(f, "\"{}\"", n),
             Value::Nothing => write!(f, "null"),
         }
     }
@@ -45,6 +101,93 @@ impl FromStr for Value {
 }
 
 
+// Values are stored as plain JSON-like values where they can be. HTML-like labels, and numbers
+// JSON can't hold, are stored as single-entry maps like `{"html": "<b>label</b>"}`.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Html(html) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("html", html)?;
+                map.end()
+            }
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::I32(i) => serializer.serialize_i32(*i),
+            Value::F64(n) if n.is_finite() => serializer.serialize_f64(*n),
+            Value::F64(n) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("f64", &n.to_string())?;
+                map.end()
+            }
+            Value::Nothing => serializer.serialize_unit(),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "a string, boolean, number, null, or a map with an `html` or `f64` entry")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<Value, E> {
+        Ok(i32::try_from(i).map(Value::I32).unwrap_or(Value::F64(i as f64)))
+    }
+
+    fn visit_u64<E>(self, i: u64) -> Result<Value, E> {
+        Ok(i32::try_from(i).map(Value::I32).unwrap_or(Value::F64(i as f64)))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::F64(n))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Nothing)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Nothing)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let value = match map.next_key::<String>()?.as_deref() {
+            Some("html") => Value::Html(map.next_value()?),
+            Some("f64") => {
+                let n: String = map.next_value()?;
+                Value::F64(n.parse().map_err(|_| A::Error::custom(format!("invalid number `{}`", n)))?)
+            }
+            _ => return Err(A::Error::custom("expected a map with an `html` or `f64` entry")),
+        };
+        if map.next_key::<String>()?.is_some() {
+            return Err(A::Error::custom("expected a map with a single entry"));
+        }
+        Ok(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
 // Implement From for each type
 impl From<String> for Value {
     fn from(value: String) -> Self {
@@ -70,69 +213,128 @@ impl From<f64> for Value {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A set of attributes, kept in the order they were first set so that output is reproducible.
+#[derive(Debug, Clone, Default)]
 pub struct Properties {
-    properties: HashMap<String, Value>,
+    properties: Vec<(String, Value)>,
 }
 
 impl Properties {
     pub fn new() -> Self {
         Properties {
-            properties: HashMap::new(),
+            properties: Vec::new(),
         }
     }
 
+    fn position(&self, key: &str) -> Option<usize> {
+        self.properties.iter().position(|(k, _)| k == key)
+    }
+
     pub fn get(&self, key: &str) -> Option<&Value> {
-        self.properties.get(key)
+        self.properties.iter().find(|(k, _)| k == key).map(|(_, value)| value)
     }
 
+    /// Set a property. Overwriting a property keeps its original position.
     pub fn set(&mut self, key: impl ToString, value: impl Into<Value>) {
-        self.properties.insert(
-            key.to_string(),
-            value.into(),
-        );
+        let key = key.to_string();
+        let value = value.into();
+        match self.position(&key) {
+            Some(i) => self.properties[i].1 = value,
+            None => self.properties.push((key, value)),
+        }
     }
 
     pub fn join(&self, other: &Self) -> Self {
-        let mut properties = self.properties.clone();
+        let mut properties = self.clone();
         for (key, value) in &other.properties {
-            properties.insert(key.clone(), value.clone());
+            properties.set(key, value.clone());
         }
-        Properties { properties }
+        properties
     }
 
     pub fn append(&mut self, other: &mut Self) {
-        for (key, value) in other.properties.drain() {
-            self.properties.insert(key, value);
+        for (key, value) in other.properties.drain(..) {
+            self.set(key, value);
         }
     }
 
     pub fn remove(&mut self, key: &str) {
-        self.properties.remove(key);
+        self.properties.retain(|(k, _)| k != key);
     }
 
     pub fn extend(&mut self, properties: impl IntoIterator<Item=(String, Value)>) {
         for (key, value) in properties {
-            self.properties.insert(key, value);
+            self.set(key, value);
         }
     }
+
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Iterate over the properties in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item=(&String, &Value)> {
+        self.properties.iter().map(|(key, value)| (key, value))
+    }
+}
+
+// Properties are equal when they hold the same values, regardless of order.
+impl PartialEq for Properties {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.properties.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
 }
 
 impl Display for Properties {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "[")?;
-        for (key, value) in &self.properties {
-            write!(f, "{}={}, ", key, value)?;
+        for (i, (key, value)) in self.properties.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", escape_id(key), value)?;
         }
         write!(f, "]")
     }
 }
 
-impl Default for Properties {
-    fn default() -> Self {
-        Properties {
-            properties: HashMap::new(),
+// Properties are stored as a map in the order they were set.
+impl Serialize for Properties {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in &self.properties {
+            map.serialize_entry(key, value)?;
         }
+        map.end()
+    }
+}
+
+struct PropertiesVisitor;
+
+impl<'de> Visitor<'de> for PropertiesVisitor {
+    type Value = Properties;
+
+    fn expecting(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "a map of properties")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Properties, A::Error> {
+        let mut properties = Properties::new();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            properties.set(key, value);
+        }
+        Ok(properties)
+    }
+}
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(PropertiesVisitor)
     }
 }
 
@@ -140,20 +342,37 @@ impl<T: AsRef<str>> Index<T> for Properties {
     type Output = Value;
 
     fn index(&self, key: T) -> &Self::Output {
-        self.properties.get(key.as_ref()).expect("Key not found")
+        self.get(key.as_ref()).expect("Key not found")
     }
 }
 
 impl<T: AsRef<str>> IndexMut<T> for Properties {
     fn index_mut(&mut self, key: T) -> &mut Self::Output {
         // If the key does not exist, insert a new key with a default value
-        self.properties.entry(key.as_ref().to_string()).or_insert(Value::Nothing)
+        let i = match self.position(key.as_ref()) {
+            Some(i) => i,
+            None => {
+                self.properties.push((key.as_ref().to_string(), Value::Nothing));
+                self.properties.len() - 1
+            }
+        };
+        &mut self.properties[i].1
+    }
+}
+
+impl<K: ToString, V: Into<Value>> FromIterator<(K, V)> for Properties {
+    fn from_iter<I: IntoIterator<Item=(K, V)>>(iter: I) -> Self {
+        let mut properties = Properties::new();
+        for (key, value) in iter {
+            properties.set(key, value);
+        }
+        properties
     }
 }
 
 impl IntoIterator for Properties {
     type Item = (String, Value);
-    type IntoIter = std::collections::hash_map::IntoIter<String, Value>;
+    type IntoIter = std::vec::IntoIter<(String, Value)>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.properties.into_iter()
@@ -162,9 +381,9 @@ impl IntoIterator for Properties {
 
 impl<'a> IntoIterator for &'a Properties {
     type Item = (&'a String, &'a Value);
-    type IntoIter = std::collections::hash_map::Iter<'a, String, Value>;
+    type IntoIter = std::iter::Map<std::slice::Iter<'a, (String, Value)>, fn(&'a (String, Value)) -> (&'a String, &'a Value)>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.properties.iter()
+        self.properties.iter().map(|(key, value)| (key, value))
     }
-}
\ No newline at end of file
+}
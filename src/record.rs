@@ -1,27 +1,28 @@
-use std::{ops::{Index, IndexMut}, fmt::{Display, Formatter, Result as FmtResult}};
+use std::{ops::{Index, IndexMut}, fmt::Display};
+use serde::{Deserialize, Serialize};
 
-use super::{Node, Edge, Properties, ID, Value};
+use super::{props::push_backslash, Node, ID};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Record {
     id: ID,
     nodes: Grid,
 }
 
-impl Into<ID> for Record {
-    fn into(self) -> ID {
-        self.id
+impl From<Record> for ID {
+    fn from(record: Record) -> Self {
+        record.id
     }
 }
 
-impl Into<ID> for &Record {
-    fn into(self) -> ID {
-        self.id
+impl From<&Record> for ID {
+    fn from(record: &Record) -> Self {
+        record.id
     }
 }
 
 /// A grid which can have nodes placed on it
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Grid {
     width: usize,
     height: usize,
@@ -41,6 +42,11 @@ impl Grid {
         self.nodes.get(x)
     }
 
+    /// Iterate over the columns from left to right.
+    pub fn columns(&self) -> impl Iterator<Item=&Column> {
+        self.nodes.iter()
+    }
+
     pub fn insert_node(&mut self, x: usize, y: usize, node: impl Into<Node>, label: impl ToString) {
         if let Some(column) = self.nodes.get_mut(x) {
             column.insert_node(y, node, label);
@@ -49,7 +55,8 @@ impl Grid {
 }
 
 /// A column in a grid
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Column {
     nodes: Vec<Option<(Node, String)>>
 }
@@ -64,6 +71,28 @@ impl Column {
     pub fn insert_node(&mut self, y: usize, node: impl Into<Node>, label: impl ToString) {
         self.nodes.insert(y, Some((node.into(), label.to_string())));
     }
+
+    /// Iterate over the occupied cells from top to bottom.
+    pub fn cells(&self) -> impl Iterator<Item=(Node, &str)> {
+        self.nodes.iter().flatten().map(|(node, label)| (*node, label.as_str()))
+    }
+}
+
+/// Escape the characters which have a meaning in record labels, then escape the result for a DOT string.
+fn escape_field(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    let mut chars = label.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' | '|' | '<' | '>' | '"' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => push_backslash(&mut escaped, &mut chars),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 impl Display for Column {
@@ -76,7 +105,7 @@ impl Display for Column {
                 if y > 0 {
                     write!(f, "|")?;
                 }
-                write!(f, "<{}>{}", node, label)?;
+                write!(f, "<{}>{}", node, escape_field(label))?;
             }
         }
         if self.nodes.len() > 1 {
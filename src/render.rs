@@ -0,0 +1,242 @@
+use std::{collections::HashMap, io::{ErrorKind, Write}, path::Path, process::{Command, Stdio}, thread};
+use serde_json::Value as Json;
+use super::{EdgeRoute, Graph, GraphError, Layout, Node, Point, Rect, Result};
+use super::layout::POINTS_PER_INCH;
+use super::dot::id_of_name;
+
+/// A Graphviz layout program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LayoutEngine {
+    /// Hierarchical layouts of directed graphs
+    #[default]
+    Dot,
+    /// Spring model layouts
+    Neato,
+    /// Force-directed layouts
+    Fdp,
+    /// Force-directed layouts of large graphs
+    Sfdp,
+    /// Circular layouts
+    Circo,
+    /// Radial layouts
+    Twopi,
+    /// Layouts of clustered graphs as nested boxes
+    Osage,
+    /// Treemap layouts of clustered graphs
+    Patchwork,
+}
+
+impl LayoutEngine {
+    /// The name of the Graphviz executable for this engine.
+    pub fn program(&self) -> &'static str {
+        match self {
+            LayoutEngine::Dot => "dot",
+            LayoutEngine::Neato => "neato",
+            LayoutEngine::Fdp => "fdp",
+            LayoutEngine::Sfdp => "sfdp",
+            LayoutEngine::Circo => "circo",
+            LayoutEngine::Twopi => "twopi",
+            LayoutEngine::Osage => "osage",
+            LayoutEngine::Patchwork => "patchwork",
+        }
+    }
+}
+
+/// A Graphviz output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutputFormat {
+    #[default]
+    Svg,
+    Png,
+    Jpeg,
+    Pdf,
+    Ps,
+    /// The graph with its layout as JSON
+    Json,
+    /// The graph with its layout in Graphviz's simple text format
+    Plain,
+    /// The graph with its layout as DOT
+    Dot,
+    /// The graph with its layout and drawing operations as DOT
+    Xdot,
+}
+
+impl OutputFormat {
+    /// The name Graphviz uses for this format in its `-T` flag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Svg => "svg",
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpeg",
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Ps => "ps",
+            OutputFormat::Json => "json",
+            OutputFormat::Plain => "plain",
+            OutputFormat::Dot => "dot",
+            OutputFormat::Xdot => "xdot",
+        }
+    }
+
+    /// The file extension conventionally used for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Plain => "txt",
+            OutputFormat::Dot => "gv",
+            _ => self.name(),
+        }
+    }
+}
+
+impl Graph {
+    /// Render the graph in memory with the given Graphviz engine and output format.
+    pub fn render(&self, engine: LayoutEngine, format: OutputFormat) -> Result<Vec<u8>> {
+        let program = engine.program();
+        let mut child = Command::new(program)
+            .arg(format!("-T{}", format.name()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotFound => GraphError::GraphvizNotFound(program.to_string()),
+                _ => GraphError::Io(err),
+            })?;
+
+        // Write the DOT on another thread so a large output can't fill the pipe and deadlock us
+        let mut stdin = child.stdin.take().unwrap();
+        let dot = self.to_dot();
+        let writer = thread::spawn(move || stdin.write_all(dot.as_bytes()));
+        let output = child.wait_with_output()?;
+        let written = writer.join().expect("writing to Graphviz panicked");
+
+        if !output.status.success() {
+            return Err(GraphError::GraphvizFailed {
+                program: program.to_string(),
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            });
+        }
+        written?;
+        Ok(output.stdout)
+    }
+
+    /// Render the graph and write the output to a file.
+    pub fn save(&self, path: &Path, engine: LayoutEngine, format: OutputFormat) -> Result<()> {
+        let output = self.render(engine, format)?;
+        std::fs::write(path, output)?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        self.save(path, LayoutEngine::Dot, OutputFormat::Png)
+    }
+
+    pub fn save_svg(&self, path: &Path) -> Result<()> {
+        self.save(path, LayoutEngine::Dot, OutputFormat::Svg)
+    }
+
+    pub fn save_pdf(&self, path: &Path) -> Result<()> {
+        self.save(path, LayoutEngine::Dot, OutputFormat::Pdf)
+    }
+
+    pub fn build_circuit_diagram(&self, path: &Path) -> Result<()> {
+        self.save(path, LayoutEngine::Neato, OutputFormat::Svg)
+    }
+}
+
+/// Read an attribute of a Graphviz JSON object. Graphviz writes every attribute as a string.
+fn attribute<'a>(object: &'a Json, key: &str) -> Option<&'a str> {
+    object.get(key)?.as_str()
+}
+
+/// Read a list of comma-separated numbers, like a point or a bounding box.
+fn numbers(text: &str) -> Option<Vec<f64>> {
+    text.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+fn invalid(message: &str) -> GraphError {
+    GraphError::InvalidOutput(message.to_string())
+}
+
+impl Layout {
+    /// Read a layout from the output of Graphviz's `-Tjson` format for `graph`.
+    ///
+    /// Everything is keyed by the `ID` it was written from, and coordinates are converted to the
+    /// layout's, with `y` growing downwards. Edges keep Graphviz's spline control points.
+    pub fn from_graphviz_json(graph: &Graph, json: &[u8]) -> Result<Layout> {
+        let json: Json = serde_json::from_slice(json).map_err(|err| invalid(&err.to_string()))?;
+        let bounds = attribute(&json, "bb").and_then(numbers).filter(|b| b.len() == 4).ok_or_else(|| invalid("the graph has no bounding box"))?;
+        let top = bounds[3];
+        let point = |text: &str| numbers(text).filter(|p| p.len() == 2).map(|p| Point::new(p[0], top - p[1]));
+
+        let mut layout = Layout::new(bounds[2] - bounds[0], bounds[3] - bounds[1]);
+        let objects = json.get("objects").and_then(Json::as_array).map(Vec::as_slice).unwrap_or_default();
+        let subgraph_count = json.get("_subgraph_cnt").and_then(Json::as_u64).unwrap_or(0) as usize;
+        let mut names = HashMap::new();
+        for (index, object) in objects.iter().enumerate() {
+            let name = attribute(object, "name").unwrap_or_default();
+            let Some(id) = id_of_name(name) else { continue };
+            if index < subgraph_count {
+                let Some(b) = attribute(object, "bb").and_then(numbers).filter(|b| b.len() == 4) else { continue };
+                layout.set_subgraph(id, Rect::new(Point::new((b[0] + b[2]) / 2.0, top - (b[1] + b[3]) / 2.0), b[2] - b[0], b[3] - b[1]));
+                continue;
+            }
+            let gvid = object.get("_gvid").and_then(Json::as_u64).unwrap_or(index as u64);
+            names.insert(gvid, name);
+            let center = attribute(object, "pos").and_then(point).ok_or_else(|| invalid(&format!("`{}` has no position", name)))?;
+            let width = attribute(object, "width").and_then(|w| w.parse::<f64>().ok()).unwrap_or(0.0) * POINTS_PER_INCH;
+            let height = attribute(object, "height").and_then(|h| h.parse::<f64>().ok()).unwrap_or(0.0) * POINTS_PER_INCH;
+            layout.set_node(id, Rect::new(center, width, height));
+
+            // Record fields are listed in the same order as the ports of the record
+            if let (Some(record), Some(rects)) = (graph.sorted_records().into_iter().find(|r| r.id() == id), attribute(object, "rects")) {
+                let rects: Vec<Vec<f64>> = rects.split_whitespace().filter_map(numbers).collect();
+                let ports: Vec<_> = record.nodes().columns().flat_map(|column| column.cells().map(|(node, _)| node)).collect();
+                if rects.len() == ports.len() {
+                    for (port, r) in ports.into_iter().zip(rects).filter(|(_, r)| r.len() == 4) {
+                        layout.set_node(port, Rect::new(Point::new((r[0] + r[2]) / 2.0, top - (r[1] + r[3]) / 2.0), r[2] - r[0], r[3] - r[1]));
+                    }
+                }
+            }
+        }
+
+        // Graphviz numbers edges in the order they were written, so parallel edges are matched up in order
+        let unit_name = |node: Node| match node.record() {
+            Some(record) => format!("Record_{}", record),
+            None => node.name(),
+        };
+        let mut unmatched = graph.edges_in_dot_order();
+        for object in json.get("edges").and_then(Json::as_array).map(Vec::as_slice).unwrap_or_default() {
+            let end_name = |key: &str| object.get(key).and_then(Json::as_u64).and_then(|gvid| names.get(&gvid)).copied();
+            let (Some(tail), Some(head)) = (end_name("tail"), end_name("head")) else { continue };
+            let Some(position) = unmatched.iter().position(|edge| unit_name(edge.start_node()) == tail && unit_name(edge.end_node()) == head) else { continue };
+            let edge = unmatched.remove(position);
+
+            let mut route = EdgeRoute { spline: true, ..Default::default() };
+            // Only the first spline is used when Graphviz splits an edge into several
+            let spline = attribute(object, "pos").unwrap_or_default().split(';').next().unwrap_or_default();
+            for token in spline.split_whitespace() {
+                if let Some(tip) = token.strip_prefix("e,") {
+                    route.head = point(tip);
+                } else if let Some(tip) = token.strip_prefix("s,") {
+                    route.tail = point(tip);
+                } else if let Some(p) = point(token) {
+                    route.points.push(p);
+                }
+            }
+            route.label = attribute(object, "lp").and_then(point);
+            layout.set_edge(edge.id(), route);
+        }
+        layout.fit();
+        Ok(layout)
+    }
+}
+
+impl Graph {
+    /// Lay out the graph with a Graphviz engine and read the positions back, without drawing it.
+    pub fn graphviz_layout(&self, engine: LayoutEngine) -> Result<Layout> {
+        let json = self.render(engine, OutputFormat::Json)?;
+        Layout::from_graphviz_json(self, &json)
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use super::{Edge, Graph, GraphKind, Grid, Node, Properties, Record, Subgraph, ID};
+
+/// The version of the serialized form of graphs. Graphs written with a newer version than
+/// this library knows are rejected instead of being read wrongly.
+const FORMAT_VERSION: u32 = 1;
+
+/// The serialized form of a graph.
+#[derive(Serialize, Deserialize)]
+struct GraphData {
+    version: u32,
+    kind: GraphKind,
+    strict: bool,
+    properties: Properties,
+    node_defaults: Properties,
+    edge_defaults: Properties,
+    /// Every node, edge, record and subgraph, in the order they were inserted
+    items: Vec<Item>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Item {
+    Node {
+        id: ID,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subgraph: Option<ID>,
+        properties: Properties,
+    },
+    Edge {
+        id: ID,
+        start: Node,
+        end: Node,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subgraph: Option<ID>,
+        properties: Properties,
+    },
+    Record {
+        id: ID,
+        grid: Grid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subgraph: Option<ID>,
+        properties: Properties,
+    },
+    Subgraph {
+        id: ID,
+        cluster: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subgraph: Option<ID>,
+        properties: Properties,
+        node_defaults: Properties,
+        edge_defaults: Properties,
+    },
+}
+
+impl Item {
+    fn id(&self) -> ID {
+        match self {
+            Item::Node { id, .. } | Item::Edge { id, .. } | Item::Record { id, .. } | Item::Subgraph { id, .. } => *id,
+        }
+    }
+
+    fn subgraph(&self) -> Option<ID> {
+        match self {
+            Item::Node { subgraph, .. } | Item::Edge { subgraph, .. } | Item::Record { subgraph, .. } | Item::Subgraph { subgraph, .. } => *subgraph,
+        }
+    }
+}
+
+impl Graph {
+    fn to_data(&self) -> GraphData {
+        let mut items = Vec::new();
+        for record in self.sorted_records() {
+            let id = record.id();
+            let properties = self.get_record_properties(id).unwrap().clone();
+            items.push(Item::Record { id, grid: record.nodes().clone(), subgraph: self.parent_of(id), properties });
+        }
+        for node in self.sorted_nodes() {
+            let id = node.id();
+            let properties = self.get_node_properties(id).unwrap().clone();
+            items.push(Item::Node { id, subgraph: self.parent_of(id), properties });
+        }
+        for subgraph in self.sorted_subgraphs() {
+            let id = subgraph.id();
+            items.push(Item::Subgraph {
+                id,
+                cluster: subgraph.is_cluster(),
+                subgraph: self.parent_of(id),
+                properties: self.get_subgraph_properties(id).unwrap().clone(),
+                node_defaults: self.get_subgraph_node_defaults(id).unwrap().clone(),
+                edge_defaults: self.get_subgraph_edge_defaults(id).unwrap().clone(),
+            });
+        }
+        for edge in self.sorted_edges() {
+            let id = edge.id();
+            let properties = self.get_edge_properties(id).unwrap().clone();
+            items.push(Item::Edge { id, start: edge.start_node(), end: edge.end_node(), subgraph: self.parent_of(id), properties });
+        }
+        // Items of different kinds can share an ID, and with it a place in the order
+        items.sort_by_key(|item| self.order_of(item.id()));
+
+        GraphData {
+            version: FORMAT_VERSION,
+            kind: self.kind(),
+            strict: self.is_strict(),
+            properties: self.properties().clone(),
+            node_defaults: self.node_defaults().clone(),
+            edge_defaults: self.edge_defaults().clone(),
+            items,
+        }
+    }
+
+    fn from_data(data: GraphData) -> Graph {
+        let mut graph = Graph::new()
+            .with_kind(data.kind)
+            .with_strict(data.strict)
+            .with_node_defaults(data.node_defaults)
+            .with_edge_defaults(data.edge_defaults);
+        *graph.properties_mut() = data.properties;
+        let parents: Vec<(ID, Option<ID>)> = data.items.iter().map(|item| (item.id(), item.subgraph())).collect();
+        for item in data.items {
+            match item {
+                Item::Node { id, properties, .. } => graph.insert_node(Node::new(id), properties),
+                Item::Edge { id, start, end, properties, .. } => {
+                    graph.insert_edge(Edge::new(id, start, end), properties);
+                }
+                Item::Record { id, grid, properties, .. } => {
+                    let mut record = Record::new(id, 0, 0);
+                    *record.nodes_mut() = grid;
+                    graph.insert_record(record, properties);
+                }
+                Item::Subgraph { id, cluster, properties, node_defaults, edge_defaults, .. } => {
+                    let subgraph = if cluster { Subgraph::cluster(id) } else { Subgraph::new(id) };
+                    graph.insert_subgraph(subgraph, properties);
+                    *graph.get_subgraph_node_defaults_mut(id).unwrap() = node_defaults;
+                    *graph.get_subgraph_edge_defaults_mut(id).unwrap() = edge_defaults;
+                }
+            }
+        }
+        for (id, parent) in parents {
+            graph.move_to_subgraph(id, parent);
+        }
+        graph
+    }
+}
+
+// Graphs are stored as a versioned list of their items in insertion order, with IDs as
+// hexadecimal strings, so that a graph read back is equal to the one written.
+impl Serialize for Graph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_data().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GraphData::deserialize(deserializer)?;
+        if data.version > FORMAT_VERSION {
+            return Err(D::Error::custom(format!(
+                "graph format version {} is newer than the supported version {}",
+                data.version, FORMAT_VERSION
+            )));
+        }
+        Ok(Graph::from_data(data))
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use serde::{Deserialize, Serialize};
+use super::ID;
+
+/// A group of nodes, edges, records and other subgraphs.
+///
+/// Clusters are subgraphs which Graphviz draws inside their own bounding box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Subgraph {
+    id: ID,
+    cluster: bool,
+}
+
+impl Subgraph {
+    pub fn new(id: impl Into<ID>) -> Self {
+        Subgraph { id: id.into(), cluster: false }
+    }
+
+    pub fn cluster(id: impl Into<ID>) -> Self {
+        Subgraph { id: id.into(), cluster: true }
+    }
+
+    pub fn id(&self) -> ID {
+        self.id
+    }
+
+    pub fn is_cluster(&self) -> bool {
+        self.cluster
+    }
+
+    pub fn name(&self) -> String {
+        if self.cluster {
+            format!("cluster_{}", self.id)
+        } else {
+            format!("Subgraph_{}", self.id)
+        }
+    }
+}
+
+impl Display for Subgraph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl From<Subgraph> for ID {
+    fn from(subgraph: Subgraph) -> Self {
+        subgraph.id
+    }
+}
@@ -0,0 +1,447 @@
+use std::{collections::HashSet, path::Path};
+use super::{Graph, Layout, Point, Properties, Rect, RankDir, Result, Shape, ID};
+use super::layout::{font_size, node_label, number, record_cells, text};
+
+/// Escape text for use in XML content and attribute values.
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The entries of a `style` attribute, like `filled` or `dashed`.
+fn styles(properties: &Properties) -> Vec<String> {
+    text(properties, "style")
+        .map(|style| style.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Read a color attribute, using the first color of a color list.
+fn color(properties: &Properties, key: &str) -> Option<String> {
+    let color = text(properties, key)?;
+    let first = color.split(':').next()?.split(';').next()?.trim();
+    if first.is_empty() {
+        None
+    } else {
+        Some(escape_xml(first))
+    }
+}
+
+/// The `stroke`, `fill` and dash attributes for an outline drawn with these properties.
+fn paint(properties: &Properties, styles: &[String], fill_by_default: bool) -> String {
+    let stroke = color(properties, "color").unwrap_or_else(|| "black".to_string());
+    let filled = fill_by_default || styles.iter().any(|s| s == "filled");
+    let fill = if filled {
+        color(properties, "fillcolor")
+            .or_else(|| color(properties, "color"))
+            .unwrap_or_else(|| "lightgrey".to_string())
+    } else {
+        "none".to_string()
+    };
+    let mut width = number(properties, "penwidth").unwrap_or(1.0);
+    if styles.iter().any(|s| s == "bold") {
+        width = width.max(2.0);
+    }
+    let mut paint = format!(r#"fill="{}" stroke="{}""#, fill, stroke);
+    if width != 1.0 {
+        paint.push_str(&format!(r#" stroke-width="{}""#, width));
+    }
+    paint.push_str(dashes(styles));
+    paint
+}
+
+fn dashes(styles: &[String]) -> &'static str {
+    if styles.iter().any(|s| s == "dashed") {
+        r#" stroke-dasharray="5,2""#
+    } else if styles.iter().any(|s| s == "dotted") {
+        r#" stroke-dasharray="1,5""#
+    } else {
+        ""
+    }
+}
+
+/// Write some text, one `<text>` element per line, with its lines centered vertically on `at`.
+fn write_text(svg: &mut String, at: Point, anchor: &str, label: &str, properties: &Properties) {
+    if label.is_empty() {
+        return;
+    }
+    let size = font_size(properties);
+    let family = text(properties, "fontname").unwrap_or_else(|| "Times,serif".to_string());
+    let fill = color(properties, "fontcolor").unwrap_or_else(|| "black".to_string());
+    let lines: Vec<&str> = label.split('\n').collect();
+    let line_height = size * 1.2;
+    let first = at.y - line_height * (lines.len() - 1) as f64 / 2.0 + size * 0.3;
+    for (i, line) in lines.iter().enumerate() {
+        svg.push_str(&format!(
+            "<text text-anchor=\"{}\" x=\"{:.2}\" y=\"{:.2}\" font-family=\"{}\" font-size=\"{:.2}\" fill=\"{}\">{}</text>\n",
+            anchor,
+            at.x,
+            first + line_height * i as f64,
+            escape_xml(&family),
+            size,
+            fill,
+            escape_xml(line),
+        ));
+    }
+}
+
+/// Open a group for an item, with its `id` and CSS classes.
+///
+/// The `id` defaults to the item's DOT name and can be overridden with the `id` attribute.
+/// The `class` attribute adds classes after the kind of item.
+fn open_group(svg: &mut String, kind: &str, name: &str, title: &str, properties: &Properties) {
+    let id = text(properties, "id").unwrap_or_else(|| name.to_string());
+    let class = match text(properties, "class") {
+        Some(class) => format!("{} {}", kind, class),
+        None => kind.to_string(),
+    };
+    svg.push_str(&format!("<g id=\"{}\" class=\"{}\">\n", escape_xml(&id), escape_xml(&class)));
+    svg.push_str(&format!("<title>{}</title>\n", escape_xml(title)));
+}
+
+/// Draw the outline of a node.
+fn write_shape(svg: &mut String, rect: &Rect, shape: Shape, properties: &Properties, styles: &[String]) {
+    let (x, y) = (rect.center.x, rect.center.y);
+    let (rx, ry) = (rect.width / 2.0, rect.height / 2.0);
+    match shape {
+        Shape::Plain => {}
+        Shape::Box | Shape::Record => {
+            let round = if styles.iter().any(|s| s == "rounded") { r#" rx="6" ry="6""# } else { "" };
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"{} {}/>\n",
+                rect.left(), rect.top(), rect.width, rect.height, round, paint(properties, styles, false)
+            ));
+        }
+        Shape::Ellipse | Shape::Circle | Shape::Point | Shape::DoubleCircle => {
+            let paint = if shape == Shape::Point && properties.get("fillcolor").is_none() {
+                // Points are filled with their outline color
+                let mut filled = properties.clone();
+                filled.set("fillcolor", text(properties, "color").unwrap_or_else(|| "black".to_string()));
+                paint(&filled, styles, true)
+            } else {
+                paint(properties, styles, shape == Shape::Point)
+            };
+            svg.push_str(&format!("<ellipse cx=\"{:.2}\" cy=\"{:.2}\" rx=\"{:.2}\" ry=\"{:.2}\" {}/>\n", x, y, rx, ry, paint));
+            if shape == Shape::DoubleCircle {
+                svg.push_str(&format!(
+                    "<ellipse cx=\"{:.2}\" cy=\"{:.2}\" rx=\"{:.2}\" ry=\"{:.2}\" fill=\"none\" stroke=\"{}\"{}/>\n",
+                    x, y, (rx - 4.0).max(0.0), (ry - 4.0).max(0.0),
+                    color(properties, "color").unwrap_or_else(|| "black".to_string()), dashes(styles)
+                ));
+            }
+        }
+        Shape::Diamond => {
+            svg.push_str(&format!(
+                "<polygon points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\" {}/>\n",
+                x, y - ry, x + rx, y, x, y + ry, x - rx, y, paint(properties, styles, false)
+            ));
+        }
+    }
+}
+
+/// The SVG path through the points of an edge, rounding its bends, or along its curve for splines.
+fn edge_path(points: &[Point], spline: bool) -> String {
+    let mut path = format!("M{:.2},{:.2}", points[0].x, points[0].y);
+    if spline {
+        for piece in points[1..].chunks_exact(3) {
+            path.push_str(&format!(
+                " C{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}",
+                piece[0].x, piece[0].y, piece[1].x, piece[1].y, piece[2].x, piece[2].y
+            ));
+        }
+        return path;
+    }
+    for i in 1..points.len().saturating_sub(1) {
+        let middle = Point::new((points[i].x + points[i + 1].x) / 2.0, (points[i].y + points[i + 1].y) / 2.0);
+        path.push_str(&format!(" Q{:.2},{:.2} {:.2},{:.2}", points[i].x, points[i].y, middle.x, middle.y));
+    }
+    if let Some(last) = points.last().filter(|_| points.len() > 1) {
+        path.push_str(&format!(" L{:.2},{:.2}", last.x, last.y));
+    }
+    path
+}
+
+/// The unit vector pointing from one point towards another.
+fn direction(from: Point, to: Point) -> Point {
+    let distance = (to.x - from.x).hypot(to.y - from.y);
+    if distance <= f64::EPSILON {
+        return Point::new(0.0, 1.0);
+    }
+    Point::new((to.x - from.x) / distance, (to.y - from.y) / distance)
+}
+
+/// Shorten the end of a polyline at `tip` to make room for an arrowhead, returning the unit direction of the arrow.
+fn make_room(points: &mut [Point], tip: usize, from: usize, length: f64) -> Point {
+    let (end, before) = (points[tip], points[from]);
+    let direction = direction(before, end);
+    let shorten = length.min((end.x - before.x).hypot(end.y - before.y));
+    points[tip] = Point::new(end.x - direction.x * shorten, end.y - direction.y * shorten);
+    direction
+}
+
+/// Draw an arrowhead of the given Graphviz arrow type with its point at `tip`.
+fn write_arrow(svg: &mut String, kind: &str, tip: Point, direction: Point, length: f64, stroke: &str) {
+    let width = length * 0.35;
+    let base = Point::new(tip.x - direction.x * length, tip.y - direction.y * length);
+    let (left, right) = (
+        Point::new(base.x - direction.y * width, base.y + direction.x * width),
+        Point::new(base.x + direction.y * width, base.y - direction.x * width),
+    );
+    match kind {
+        "dot" => {
+            let center = Point::new(tip.x - direction.x * length / 2.0, tip.y - direction.y * length / 2.0);
+            svg.push_str(&format!(
+                "<ellipse cx=\"{:.2}\" cy=\"{:.2}\" rx=\"{:.2}\" ry=\"{:.2}\" fill=\"{}\" stroke=\"{}\"/>\n",
+                center.x, center.y, length / 2.0, length / 2.0, stroke, stroke
+            ));
+        }
+        "vee" => {
+            let notch = Point::new(tip.x - direction.x * length * 0.6, tip.y - direction.y * length * 0.6);
+            svg.push_str(&format!(
+                "<polygon points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\" fill=\"{}\" stroke=\"{}\"/>\n",
+                tip.x, tip.y, left.x, left.y, notch.x, notch.y, right.x, right.y, stroke, stroke
+            ));
+        }
+        _ => {
+            let fill = if kind == "empty" || kind == "onormal" { "none" } else { stroke };
+            svg.push_str(&format!(
+                "<polygon points=\"{:.2},{:.2} {:.2},{:.2} {:.2},{:.2}\" fill=\"{}\" stroke=\"{}\"/>\n",
+                tip.x, tip.y, left.x, left.y, right.x, right.y, fill, stroke
+            ));
+        }
+    }
+}
+
+impl Graph {
+    /// Draw the graph as SVG, with everything at the positions given by `layout`.
+    ///
+    /// Every node, record, edge and cluster is drawn in a group whose `id` is its DOT name
+    /// (such as `Node_<id>`, or `Edge_<id>` for edges) and whose classes are `node`, `record`,
+    /// `edge` or `cluster`, so the output can be styled with CSS. The `id` and `class` attributes
+    /// override the `id` and add classes, and the graph's `stylesheet` attribute links a stylesheet.
+    pub fn to_svg(&self, layout: &Layout) -> String {
+        let graph_properties = self.properties();
+        let label = text(graph_properties, "label").unwrap_or_default();
+        let label_height = if label.is_empty() {
+            0.0
+        } else {
+            label.split('\n').count() as f64 * font_size(graph_properties) * 1.2 + 8.0
+        };
+        let on_top = text(graph_properties, "labelloc").as_deref() == Some("t");
+        let (width, height) = (layout.width(), layout.height() + label_height);
+
+        let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n");
+        if let Some(stylesheet) = text(graph_properties, "stylesheet") {
+            svg.push_str(&format!("<?xml-stylesheet href=\"{}\" type=\"text/css\"?>\n", escape_xml(&stylesheet)));
+        }
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}pt\" height=\"{:.0}pt\" viewBox=\"0.00 0.00 {:.2} {:.2}\">\n",
+            width.ceil(), height.ceil(), width, height
+        ));
+        open_group(&mut svg, "graph", "graph", "graph", graph_properties);
+        if let Some(background) = color(graph_properties, "bgcolor") {
+            svg.push_str(&format!("<rect x=\"0\" y=\"0\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" stroke=\"none\"/>\n", width, height, background));
+        }
+        if !label.is_empty() {
+            let y = if on_top { label_height / 2.0 } else { height - label_height / 2.0 };
+            write_text(&mut svg, Point::new(width / 2.0, y), "middle", &label, graph_properties);
+        }
+        if on_top {
+            svg.push_str(&format!("<g transform=\"translate(0,{:.2})\">\n", label_height));
+        }
+
+        self.write_clusters(&mut svg, layout);
+        self.write_records(&mut svg, layout);
+        self.write_nodes(&mut svg, layout);
+        self.write_edges(&mut svg, layout);
+
+        if on_top {
+            svg.push_str("</g>\n");
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    /// Lay out the graph natively and draw it as SVG, without Graphviz.
+    pub fn render_svg(&self) -> String {
+        self.to_svg(&self.native_layout())
+    }
+
+    /// Draw the graph as SVG without Graphviz and write it to a file.
+    pub fn save_native_svg(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.render_svg())?;
+        Ok(())
+    }
+
+    /// The number of subgraphs enclosing a subgraph.
+    fn subgraph_depth(&self, id: ID) -> usize {
+        let mut seen = vec![id];
+        let mut current = self.parent_of(id);
+        while let Some(parent) = current {
+            if seen.contains(&parent) {
+                break;
+            }
+            seen.push(parent);
+            current = self.parent_of(parent);
+        }
+        seen.len() - 1
+    }
+
+    fn write_clusters(&self, svg: &mut String, layout: &Layout) {
+        // Draw outer clusters first so inner ones are drawn on top of them
+        let mut clusters: Vec<_> = self.sorted_subgraphs().into_iter().filter(|s| s.is_cluster()).collect();
+        clusters.sort_by_key(|subgraph| self.subgraph_depth(subgraph.id()));
+        for subgraph in clusters {
+            let Some(rect) = layout.subgraph(subgraph.id()) else { continue };
+            let properties = self.get_subgraph_properties(subgraph.id()).cloned().unwrap_or_default();
+            let styles = styles(&properties);
+            if styles.iter().any(|s| s == "invis") {
+                continue;
+            }
+            let mut outline = properties.clone();
+            if let Some(pen) = properties.get("pencolor") {
+                outline.set("color", pen.clone());
+            }
+            // Clusters with a `bgcolor` are filled with it even without `style=filled`
+            let background = properties.get("bgcolor").filter(|_| properties.get("fillcolor").is_none());
+            if let Some(background) = background {
+                outline.set("fillcolor", background.clone());
+            }
+            let fill_by_default = background.is_some();
+            open_group(svg, "cluster", &subgraph.name(), &subgraph.name(), &properties);
+            let round = if styles.iter().any(|s| s == "rounded") { r#" rx="6" ry="6""# } else { "" };
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"{} {}/>\n",
+                rect.left(), rect.top(), rect.width, rect.height, round, paint(&outline, &styles, fill_by_default)
+            ));
+            if let Some(label) = text(&properties, "label") {
+                let line_height = font_size(&properties) * 1.2 * label.split('\n').count() as f64;
+                write_text(svg, Point::new(rect.center.x, rect.top() + 4.0 + line_height / 2.0), "middle", &label, &properties);
+            }
+            svg.push_str("</g>\n");
+        }
+    }
+
+    fn write_records(&self, svg: &mut String, layout: &Layout) {
+        let flipped = RankDir::from_properties(self.properties()).is_horizontal();
+        for record in self.sorted_records() {
+            let Some(rect) = layout.node(record.id()) else { continue };
+            let properties = self.resolved_node_properties(record.id());
+            let styles = styles(&properties);
+            if styles.iter().any(|s| s == "invis") {
+                continue;
+            }
+            open_group(svg, "node record", &record.name(), &record.name(), &properties);
+            write_shape(svg, rect, Shape::Record, &properties, &styles);
+            let stroke = color(&properties, "color").unwrap_or_else(|| "black".to_string());
+            for (node, label, cell) in record_cells(record, rect, font_size(&properties), flipped) {
+                if let Some(node) = node {
+                    svg.push_str(&format!("<g id=\"{}\" class=\"field\">\n", escape_xml(&node.name())));
+                }
+                svg.push_str(&format!(
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"{}\"/>\n",
+                    cell.left(), cell.top(), cell.width, cell.height, stroke
+                ));
+                write_text(svg, cell.center, "middle", &label, &properties);
+                if node.is_some() {
+                    svg.push_str("</g>\n");
+                }
+            }
+            svg.push_str("</g>\n");
+        }
+    }
+
+    fn write_nodes(&self, svg: &mut String, layout: &Layout) {
+        let mut drawn = HashSet::new();
+        let mut nodes: Vec<_> = self.sorted_nodes().into_iter().copied().collect();
+        // Edges may end at nodes which were never inserted; draw those like Graphviz does
+        for edge in self.sorted_edges() {
+            for node in [edge.start_node(), edge.end_node()] {
+                let known = self.get_node_properties(node.id()).is_some() || self.get_record_properties(node.id()).is_some();
+                if node.record().is_none() && !known {
+                    nodes.push(node);
+                }
+            }
+        }
+        for node in nodes {
+            if !drawn.insert(node.id()) {
+                continue;
+            }
+            let Some(rect) = layout.node(node.id()) else { continue };
+            let properties = self.resolved_node_properties(node.id());
+            let styles = styles(&properties);
+            if styles.iter().any(|s| s == "invis") {
+                continue;
+            }
+            let shape = Shape::from_properties(&properties);
+            open_group(svg, "node", &node.name(), &node.name(), &properties);
+            write_shape(svg, rect, shape, &properties, &styles);
+            if shape != Shape::Point {
+                write_text(svg, rect.center, "middle", &node_label(&node.name(), &properties), &properties);
+            }
+            svg.push_str("</g>\n");
+        }
+    }
+
+    fn write_edges(&self, svg: &mut String, layout: &Layout) {
+        for edge in self.sorted_edges() {
+            let Some(route) = layout.edge(edge.id()) else { continue };
+            if route.points.len() < 2 {
+                continue;
+            }
+            let properties = self.resolved_edge_properties(edge.id());
+            let styles = styles(&properties);
+            if styles.iter().any(|s| s == "invis") {
+                continue;
+            }
+            let default_dir = if self.is_directed() { "forward" } else { "none" };
+            let dir = text(&properties, "dir").unwrap_or_else(|| default_dir.to_string());
+            let head = text(&properties, "arrowhead").unwrap_or_else(|| "normal".to_string());
+            let tail = text(&properties, "arrowtail").unwrap_or_else(|| "normal".to_string());
+            let head = Some(head).filter(|head| matches!(dir.as_str(), "forward" | "both") && head != "none");
+            let tail = Some(tail).filter(|tail| matches!(dir.as_str(), "back" | "both") && tail != "none");
+            let length = 10.0 * number(&properties, "arrowsize").unwrap_or(1.0);
+
+            let mut points = route.points.clone();
+            let last = points.len() - 1;
+            // Routes from Graphviz stop short of their arrowheads; make room for them in other routes
+            let mut arrow = |tip: Option<Point>, end: usize, before: usize| match tip {
+                Some(tip) => (tip, direction(points[end], tip)),
+                None => (points[end], make_room(&mut points, end, before, length)),
+            };
+            let head_arrow = head.as_ref().map(|_| arrow(route.head, last, last - 1));
+            let tail_arrow = tail.as_ref().map(|_| arrow(route.tail, 0, 1));
+
+            let title = format!("{}{}{}", edge.start_node(), self.kind().edge_op(), edge.end_node());
+            open_group(svg, "edge", &format!("Edge_{}", edge.id()), &title, &properties);
+            let stroke = color(&properties, "color").unwrap_or_else(|| "black".to_string());
+            let mut width = number(&properties, "penwidth").unwrap_or(1.0);
+            if styles.iter().any(|s| s == "bold") {
+                width = width.max(2.0);
+            }
+            svg.push_str(&format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{}/>\n",
+                edge_path(&points, route.spline), stroke, width, dashes(&styles)
+            ));
+            if let (Some(kind), Some((tip, direction))) = (&head, head_arrow) {
+                write_arrow(svg, kind, tip, direction, length, &stroke);
+            }
+            if let (Some(kind), Some((tip, direction))) = (&tail, tail_arrow) {
+                write_arrow(svg, kind, tip, direction, length, &stroke);
+            }
+            if let (Some(at), Some(label)) = (route.label, text(&properties, "label")) {
+                write_text(svg, at, "middle", &label, &properties);
+            }
+            svg.push_str("</g>\n");
+        }
+    }
+}
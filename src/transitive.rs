@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+use super::{Edge, Graph, Node, ID};
+
+impl Graph {
+    /// Whether `end` can be reached from `start` by at least one edge, using only edges which
+    /// stay within `component` and aren't in `excluded`.
+    fn reachable_within(&self, start: Node, end: Node, component: &HashSet<Node>, excluded: &HashSet<ID>) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![start];
+        while let Some(node) = pending.pop() {
+            for edge in self.out_edges(node) {
+                let next = if edge.start_node() == node { edge.end_node() } else { edge.start_node() };
+                if excluded.contains(&edge.id()) || !component.contains(&next) {
+                    continue;
+                }
+                if next == end {
+                    return true;
+                }
+                if seen.insert(next) {
+                    pending.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Make a copy of the graph without the edges implied by others, leaving out an edge when
+    /// its end can still be reached from its start without it. Kept edges keep their IDs and
+    /// properties, and the nodes, records and subgraphs are unchanged.
+    ///
+    /// For graphs without cycles, this is the transitive reduction. In graphs with cycles, the
+    /// edges between strongly connected components are reduced like those of the condensation,
+    /// keeping the first edge from one component to another, and the edges within a component
+    /// are removed from the last inserted back while the component stays connected.
+    pub fn transitive_reduction(&self) -> Graph {
+        let components = self.strongly_connected_components();
+        let mut component_of = HashMap::new();
+        for (i, members) in components.iter().enumerate() {
+            for &member in members {
+                component_of.insert(member, i);
+            }
+        }
+
+        let mut redundant = HashSet::new();
+        let mut within: Vec<Edge> = Vec::new();
+        // The first edge from each component to each other, which only lead to later components
+        let mut between: Vec<Vec<(usize, Edge)>> = vec![Vec::new(); components.len()];
+        for &edge in self.edges() {
+            let (start, end) = (component_of[&edge.start_node()], component_of[&edge.end_node()]);
+            if start == end {
+                within.push(edge);
+            } else if between[start].iter().any(|&(other, _)| other == end) {
+                redundant.insert(edge.id());
+            } else {
+                between[start].push((end, edge));
+            }
+        }
+
+        // The components each component can reach, as bit sets, built from the last component back
+        let words = components.len().div_ceil(64);
+        let mut reach = vec![vec![0u64; words]; components.len()];
+        for component in (0..components.len()).rev() {
+            let mut reached = vec![0u64; words];
+            for &(next, _) in &between[component] {
+                reached[next / 64] |= 1 << (next % 64);
+                for (word, other) in reached.iter_mut().zip(&reach[next]) {
+                    *word |= other;
+                }
+            }
+            reach[component] = reached;
+        }
+        for edges in &between {
+            for &(end, edge) in edges {
+                if edges.iter().any(|&(other, _)| other != end && reach[other][end / 64] >> (end % 64) & 1 == 1) {
+                    redundant.insert(edge.id());
+                }
+            }
+        }
+
+        for &edge in within.iter().rev() {
+            let members = components[component_of[&edge.start_node()]].iter().copied().collect();
+            redundant.insert(edge.id());
+            if !self.reachable_within(edge.start_node(), edge.end_node(), &members, &redundant) {
+                redundant.remove(&edge.id());
+            }
+        }
+
+        let mut graph = self.clone();
+        for id in redundant {
+            graph.remove_edge(id);
+        }
+        graph
+    }
+
+    /// Make a copy of the graph with an edge from each node to every node it can reach, where
+    /// there isn't one already. Existing edges are kept as they are, and the new edges have no
+    /// properties. In directed graphs, nodes on a cycle get an edge to themselves.
+    pub fn transitive_closure(&self) -> Graph {
+        let mut graph = self.clone();
+        let nodes = self.endpoints();
+        let position: HashMap<Node, usize> = nodes.iter().enumerate().map(|(i, &node)| (node, i)).collect();
+        for &node in &nodes {
+            let mut reached = HashSet::new();
+            let mut pending = vec![node];
+            while let Some(current) = pending.pop() {
+                for next in self.successors(current) {
+                    if reached.insert(position[&next]) {
+                        pending.push(next);
+                    }
+                }
+            }
+            let mut reached: Vec<usize> = reached.into_iter().collect();
+            reached.sort();
+            for end in reached.into_iter().map(|i| nodes[i]) {
+                if (self.is_directed() || end != node) && graph.find_edge(node, end).is_none() {
+                    graph.new_edge(node, end).finalize();
+                }
+            }
+        }
+        graph
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use super::{Graph, Node, Properties, Record, Value, ID};
+
+/// A problem with a graph found by `Graph::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Diagnostic {
+    /// An edge starts or ends at a node, or a field of a record, which isn't in the graph.
+    DanglingEdge { edge: ID, node: Node },
+    /// An edge starts or ends at a field which its record doesn't have.
+    UnknownRecordPort { edge: ID, record: ID, port: ID },
+    /// A node and a record have the same ID, so one hides the other in lookups by ID.
+    DuplicateId { id: ID },
+    /// A property is set to `Value::Nothing`, which DOT writes as the string `null`.
+    /// The owner is the node, edge, record or subgraph with the property, or `None` for the graph.
+    NothingValue { owner: Option<ID>, key: String },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Diagnostic::DanglingEdge { edge, node } => {
+                write!(f, "edge Edge_{} is attached to {}, which is not in the graph", edge, node)
+            }
+            Diagnostic::UnknownRecordPort { edge, record, port } => {
+                write!(f, "edge Edge_{} is attached to port Node_{}, which record Record_{} does not have", edge, port, record)
+            }
+            Diagnostic::DuplicateId { id } => write!(f, "Node_{} and Record_{} have the same ID", id, id),
+            Diagnostic::NothingValue { owner: Some(owner), key } => write!(f, "property `{}` of {} has no value", key, owner),
+            Diagnostic::NothingValue { owner: None, key } => write!(f, "graph property `{}` has no value", key),
+        }
+    }
+}
+
+/// Report every property of `owner` which is set to `Value::Nothing`.
+fn check_properties(diagnostics: &mut Vec<Diagnostic>, owner: Option<ID>, properties: &Properties) {
+    for (key, value) in properties {
+        if *value == Value::Nothing {
+            diagnostics.push(Diagnostic::NothingValue { owner, key: key.clone() });
+        }
+    }
+}
+
+impl Graph {
+    /// Check the graph for problems which would make Graphviz draw something other than what
+    /// was meant, returning them in the order of the items they were found in.
+    ///
+    /// This finds edges attached to nodes or records which were never inserted (which
+    /// Graphviz would draw as new, unlabeled nodes), edges attached to fields their record
+    /// doesn't have, nodes and records which share an ID, and properties with no value.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        check_properties(&mut diagnostics, None, self.properties());
+        check_properties(&mut diagnostics, None, self.node_defaults());
+        check_properties(&mut diagnostics, None, self.edge_defaults());
+
+        for record in self.records() {
+            let id = record.id();
+            if self.get_node_properties(id).is_some() {
+                diagnostics.push(Diagnostic::DuplicateId { id });
+            }
+            check_properties(&mut diagnostics, Some(id), self.get_record_properties(id).unwrap());
+        }
+        for node in self.nodes() {
+            check_properties(&mut diagnostics, Some(node.id()), self.get_node_properties(node.id()).unwrap());
+        }
+        for subgraph in self.subgraphs() {
+            let id = subgraph.id();
+            check_properties(&mut diagnostics, Some(id), self.get_subgraph_properties(id).unwrap());
+            check_properties(&mut diagnostics, Some(id), self.get_subgraph_node_defaults(id).unwrap());
+            check_properties(&mut diagnostics, Some(id), self.get_subgraph_edge_defaults(id).unwrap());
+        }
+        let records: HashMap<ID, &Record> = self.records().map(|record| (record.id(), record)).collect();
+        for edge in self.edges() {
+            for node in [edge.start_node(), edge.end_node()] {
+                match node.record() {
+                    None if self.get_node_properties(node.id()).is_none() => {
+                        diagnostics.push(Diagnostic::DanglingEdge { edge: edge.id(), node });
+                    }
+                    Some(record) => match records.get(&record) {
+                        None => diagnostics.push(Diagnostic::DanglingEdge { edge: edge.id(), node }),
+                        Some(r) if !r.nodes().columns().any(|column| column.cells().any(|(port, _)| port.id() == node.id())) => {
+                            diagnostics.push(Diagnostic::UnknownRecordPort { edge: edge.id(), record, port: node.id() });
+                        }
+                        Some(_) => {}
+                    },
+                    None => {}
+                }
+            }
+            check_properties(&mut diagnostics, Some(edge.id()), self.get_edge_properties(edge.id()).unwrap());
+        }
+        diagnostics
+    }
+}
@@ -0,0 +1,89 @@
+use std::thread;
+use graph::{Graph, GraphKind, Node};
+
+fn chain(length: usize) -> (Graph, Vec<Node>) {
+    let mut g = Graph::new();
+    let nodes: Vec<Node> = (0..length).map(|i| g.new_node(format!("n{}", i)).with_property("label", i.to_string()).finalize()).collect();
+    for pair in nodes.windows(2) {
+        g.new_edge(pair[0], pair[1]).finalize();
+    }
+    (g, nodes)
+}
+
+#[test]
+fn trees_are_printed_with_branches() {
+    let mut g = Graph::new();
+    let root = g.new_node("root").with_property("label", "root").finalize();
+    let a = g.new_node("a").with_property("label", "a").finalize();
+    let b = g.new_node("b").with_property("label", "b").finalize();
+    let c = g.new_node("c").with_property("label", "c").finalize();
+    g.new_edge(root, a).with_property("label", "first").finalize();
+    g.new_edge(root, b).finalize();
+    g.new_edge(a, c).finalize();
+    g.new_edge(b, c).finalize();
+
+    assert_eq!(
+        g.to_ascii_tree().unwrap(),
+        "root\n├── [first] a\n│   └── c\n└── b\n    └── c (see above)\n"
+    );
+}
+
+#[test]
+fn cycles_are_not_trees() {
+    let (mut g, nodes) = chain(3);
+    g.new_edge(nodes[2], nodes[0]).finalize();
+    assert_eq!(g.to_ascii_tree(), None);
+
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).finalize();
+    g.new_edge(c, a).finalize();
+    assert_eq!(g.to_ascii_tree(), None);
+}
+
+#[test]
+fn long_cycles_do_not_overflow_the_stack() {
+    let (mut g, nodes) = chain(30_000);
+    g.new_edge(nodes[nodes.len() - 1], nodes[0]).finalize();
+    assert_eq!(g.to_ascii_tree(), None);
+}
+
+#[test]
+fn deep_trees_do_not_overflow_the_stack() {
+    // The printed tree grows with the square of its depth, so a small stack stands in for a very long chain
+    let tree = thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| chain(3_000).0.to_ascii_tree())
+        .unwrap()
+        .join()
+        .unwrap()
+        .unwrap();
+    assert_eq!(tree.lines().count(), 3_000);
+    assert!(tree.ends_with("└── 2999\n"));
+}
+
+#[test]
+fn identical_records_keep_their_fields_apart() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("label", "a").finalize();
+    for name in ["first", "second"] {
+        let record = g.new_record(name, 2, 1).insert(0, 0, "left").insert(1, 0, "right").finalize();
+        let (left, _) = record.nodes().columns().flat_map(|column| column.cells()).next().unwrap();
+        g.new_edge(a, left).finalize();
+    }
+
+    // Each arrow ends above the left field of its own record
+    let drawing = g.to_ascii();
+    let lines: Vec<Vec<char>> = drawing.lines().map(|line| line.chars().collect()).collect();
+    let arrows = lines.iter().find(|line| line.contains(&'▼')).unwrap();
+    let fields = lines.iter().find(|line| line.iter().collect::<String>().contains("left")).unwrap();
+    let arrow_columns: Vec<usize> = (0..arrows.len()).filter(|&x| arrows[x] == '▼').collect();
+    let field_columns: Vec<usize> = (0..fields.len()).filter(|&x| fields[x..].starts_with(&['l', 'e', 'f', 't'])).collect();
+    assert_eq!(arrow_columns.len(), 2, "{}", drawing);
+    for (arrow, field) in arrow_columns.into_iter().zip(field_columns) {
+        assert!((field.saturating_sub(1)..field + 5).contains(&arrow), "{}", drawing);
+    }
+}
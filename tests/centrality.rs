@@ -0,0 +1,115 @@
+use graph::{Graph, GraphKind, Node, Value};
+
+fn assert_scores(scores: &[(Node, f64)], expected: &[(Node, f64)]) {
+    assert_eq!(scores.len(), expected.len(), "{:?}", scores);
+    for (&(node, score), &(expected_node, expected_score)) in scores.iter().zip(expected) {
+        assert_eq!(node, expected_node);
+        assert!((score - expected_score).abs() < 1e-3, "{} scored {} instead of {}", node, score, expected_score);
+    }
+}
+
+/// The undirected path `a - b - c - d`.
+fn path() -> (Graph, [Node; 4]) {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let nodes = ["a", "b", "c", "d"].map(|name| g.new_node(name).finalize());
+    for pair in nodes.windows(2) {
+        g.new_edge(pair[0], pair[1]).finalize();
+    }
+    (g, nodes)
+}
+
+/// `a -> b -> c -> a`, with `a -> d -> c` and `e -> a`.
+fn digraph() -> (Graph, [Node; 5]) {
+    let mut g = Graph::new();
+    let [a, b, c, d, e] = ["a", "b", "c", "d", "e"].map(|name| g.new_node(name).finalize());
+    for (start, end) in [(a, b), (b, c), (c, a), (a, d), (d, c), (e, a)] {
+        g.new_edge(start, end).finalize();
+    }
+    (g, [a, b, c, d, e])
+}
+
+#[test]
+fn measures_on_a_path() {
+    let (g, [a, b, c, d]) = path();
+    assert_scores(&g.degree_centrality(), &[(a, 1.0 / 3.0), (b, 2.0 / 3.0), (c, 2.0 / 3.0), (d, 1.0 / 3.0)]);
+    assert_scores(&g.closeness_centrality(), &[(a, 0.5), (b, 0.75), (c, 0.75), (d, 0.5)]);
+    assert_scores(&g.betweenness_centrality(), &[(a, 0.0), (b, 2.0 / 3.0), (c, 2.0 / 3.0), (d, 0.0)]);
+    // The principal eigenvector of a path of four nodes is (1, φ, φ, 1), scaled to a length of 1
+    let phi = (1.0 + 5f64.sqrt()) / 2.0;
+    let length = (2.0 + 2.0 * phi * phi).sqrt();
+    assert_scores(&g.eigenvector_centrality(), &[(a, 1.0 / length), (b, phi / length), (c, phi / length), (d, 1.0 / length)]);
+}
+
+#[test]
+fn measures_on_a_digraph() {
+    let (g, [a, b, c, d, e]) = digraph();
+    assert_scores(&g.degree_centrality(), &[(a, 1.0), (b, 0.5), (c, 0.75), (d, 0.5), (e, 0.25)]);
+    let pagerank = g.pagerank(0.85);
+    assert_scores(&pagerank, &[(a, 0.3223), (b, 0.1670), (c, 0.3138), (d, 0.1670), (e, 0.03)]);
+    assert!((pagerank.iter().map(|&(_, score)| score).sum::<f64>() - 1.0).abs() < 1e-6);
+    // Only paths between `a`, `b`, `c` and `d` pass through other nodes
+    let betweenness = g.betweenness_centrality();
+    assert_eq!(betweenness[4], (e, 0.0));
+    assert!(betweenness[0].1 > betweenness[1].1);
+}
+
+#[test]
+fn a_star_centers_on_its_hub() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let hub = g.new_node("hub").finalize();
+    let leaves: Vec<Node> = (0..3).map(|i| g.new_node(format!("leaf{}", i)).finalize()).collect();
+    for &leaf in &leaves {
+        g.new_edge(hub, leaf).finalize();
+    }
+    let expected = |hub_score: f64, leaf_score: f64| {
+        let mut expected = vec![(hub, hub_score)];
+        expected.extend(leaves.iter().map(|&leaf| (leaf, leaf_score)));
+        expected
+    };
+    assert_scores(&g.degree_centrality(), &expected(1.0, 1.0 / 3.0));
+    assert_scores(&g.closeness_centrality(), &expected(1.0, 0.6));
+    assert_scores(&g.betweenness_centrality(), &expected(1.0, 0.0));
+}
+
+#[test]
+fn single_nodes_and_empty_graphs() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    assert_scores(&g.degree_centrality(), &[(a, 0.0)]);
+    assert_scores(&g.closeness_centrality(), &[(a, 0.0)]);
+    assert_scores(&g.betweenness_centrality(), &[(a, 0.0)]);
+    assert_scores(&g.eigenvector_centrality(), &[(a, 1.0)]);
+    assert_scores(&g.pagerank(0.85), &[(a, 1.0)]);
+
+    let g = Graph::new();
+    assert!(g.degree_centrality().is_empty());
+    assert!(g.closeness_centrality().is_empty());
+    assert!(g.betweenness_centrality().is_empty());
+    assert!(g.eigenvector_centrality().is_empty());
+    assert!(g.pagerank(0.85).is_empty());
+}
+
+fn property(graph: &Graph, node: Node, key: &str) -> Option<Value> {
+    graph.get_node_properties(node)?.get(key).cloned()
+}
+
+#[test]
+fn scores_map_onto_properties() {
+    let (mut g, [a, b, c, d, e]) = digraph();
+    let scores = g.degree_centrality();
+    g.set_scores(&scores, "degree");
+    g.scale_by_scores(&scores, "fontsize", 10.0, 20.0);
+    g.color_by_scores(&scores, "fillcolor", "#000000", "#ff8000");
+
+    assert_eq!(property(&g, c, "degree"), Some(Value::F64(0.75)));
+    assert_eq!(property(&g, a, "fontsize"), Some(Value::F64(20.0)));
+    assert_eq!(property(&g, e, "fontsize"), Some(Value::F64(10.0)));
+    assert_eq!(property(&g, b, "fontsize"), Some(Value::F64(13.33)));
+    assert_eq!(property(&g, a, "fillcolor"), Some(Value::from("#ff8000")));
+    assert_eq!(property(&g, e, "fillcolor"), Some(Value::from("#000000")));
+    assert_eq!(property(&g, d, "fillcolor"), Some(Value::from("#552b00")));
+
+    // Colors which can't be read change nothing
+    g.color_by_scores(&scores, "color", "red", "#ffffff");
+    assert_eq!(property(&g, a, "color"), None);
+}
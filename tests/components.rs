@@ -0,0 +1,60 @@
+use graph::{Graph, GraphKind, Node, Value};
+
+fn chain(length: usize) -> (Graph, Vec<Node>) {
+    let mut g = Graph::new();
+    let nodes: Vec<Node> = (0..length).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+    for pair in nodes.windows(2) {
+        g.new_edge(pair[0], pair[1]).finalize();
+    }
+    (g, nodes)
+}
+
+/// `a <-> b -> c <-> d`, with `e` on its own.
+fn two_loops() -> (Graph, [Node; 5]) {
+    let mut g = Graph::new();
+    let [a, b, c, d, e] = ["a", "b", "c", "d", "e"].map(|name| g.new_node(name).with_property("label", name.to_uppercase()).finalize());
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, a).finalize();
+    g.new_edge(b, c).with_property("color", "red").finalize();
+    g.new_edge(a, c).finalize();
+    g.new_edge(c, d).finalize();
+    g.new_edge(d, c).finalize();
+    (g, [a, b, c, d, e])
+}
+
+#[test]
+fn strongly_connected_components_are_in_topological_order() {
+    let (g, [a, b, c, d, e]) = two_loops();
+    assert_eq!(g.strongly_connected_components(), vec![vec![e], vec![a, b], vec![c, d]]);
+    assert_eq!(g.weakly_connected_components(), vec![vec![a, b, c, d], vec![e]]);
+
+    let mut undirected = Graph::new().with_kind(GraphKind::Undirected);
+    let [x, y, z] = ["x", "y", "z"].map(|name| undirected.new_node(name).finalize());
+    undirected.new_edge(x, y).finalize();
+    assert_eq!(undirected.strongly_connected_components(), vec![vec![z], vec![x, y]]);
+}
+
+#[test]
+fn condensation_collapses_components() {
+    let (g, [a, _, c, _, e]) = two_loops();
+    let condensed = g.condensation();
+    assert_eq!(condensed.nodes().copied().collect::<Vec<_>>(), vec![e, a, c]);
+    assert_eq!(condensed.get_node_properties(e).unwrap().get("label"), Some(&Value::from("E")));
+    assert_eq!(condensed.get_node_properties(a).unwrap().get("label"), Some(&Value::from("A, B")));
+    assert_eq!(condensed.get_node_properties(c).unwrap().get("label"), Some(&Value::from("C, D")));
+    // The first edge between two components is kept, with its properties
+    assert_eq!(condensed.edges().count(), 1);
+    let edge = condensed.find_edge(a, c).unwrap();
+    assert_eq!(condensed.get_edge_properties(edge.id()).unwrap().get("color"), Some(&Value::from("red")));
+    assert!(condensed.is_dag());
+}
+
+#[test]
+fn long_chains_do_not_overflow_the_stack() {
+    let (g, nodes) = chain(60_000);
+    let components = g.strongly_connected_components();
+    assert_eq!(components.len(), 60_000);
+    assert_eq!(components[0], vec![nodes[0]]);
+    assert_eq!(components[59_999], vec![nodes[59_999]]);
+    assert_eq!(g.condensation().edges().count(), 59_999);
+}
@@ -0,0 +1,108 @@
+use std::thread;
+use graph::{Edge, Graph, GraphError, Node};
+
+fn nodes<const N: usize>(g: &mut Graph, names: [&str; N]) -> [Node; N] {
+    names.map(|name| g.new_node(name).finalize())
+}
+
+#[test]
+fn topological_sort_keeps_insertion_order_where_it_can() {
+    let mut g = Graph::new();
+    let [shirt, tie, jacket, socks, shoes] = nodes(&mut g, ["shirt", "tie", "jacket", "socks", "shoes"]);
+    g.new_edge(shirt, tie).finalize();
+    g.new_edge(tie, jacket).finalize();
+    g.new_edge(socks, shoes).finalize();
+    g.new_edge(shoes, jacket).finalize();
+    assert!(g.is_dag());
+    assert_eq!(g.topological_sort().unwrap(), vec![shirt, socks, tie, shoes, jacket]);
+}
+
+#[test]
+fn topological_sort_reports_a_cycle() {
+    let mut g = Graph::new();
+    let [a, b, c, d] = nodes(&mut g, ["a", "b", "c", "d"]);
+    g.new_edge(a, b).finalize();
+    let bc = g.new_edge(b, c).finalize();
+    let cd = g.new_edge(c, d).finalize();
+    let db = g.new_edge(d, b).finalize();
+    assert!(!g.is_dag());
+    let Err(GraphError::Cycle(cycle)) = g.topological_sort() else { panic!("expected a cycle") };
+    let mut ids: Vec<_> = cycle.iter().map(Edge::id).collect();
+    ids.sort();
+    let mut expected = vec![bc.id(), cd.id(), db.id()];
+    expected.sort();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn every_elementary_cycle_is_found() {
+    let mut g = Graph::new();
+    let [a, b, c, d] = nodes(&mut g, ["a", "b", "c", "d"]);
+    let ab = g.new_edge(a, b).finalize();
+    let bc = g.new_edge(b, c).finalize();
+    let ca = g.new_edge(c, a).finalize();
+    let ba = g.new_edge(b, a).finalize();
+    let cc = g.new_edge(c, c).finalize();
+    g.new_edge(c, d).finalize();
+    assert_eq!(g.find_cycles(), vec![vec![ab, bc, ca], vec![ab, ba], vec![cc]]);
+
+    let mut g = Graph::new();
+    let [a, b] = nodes(&mut g, ["a", "b"]);
+    g.new_edge(a, b).finalize();
+    assert!(g.find_cycles().is_empty());
+}
+
+#[test]
+fn long_cycles_do_not_overflow_the_stack() {
+    let mut g = Graph::new();
+    let ring: Vec<Node> = (0..30_000).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+    for i in 0..ring.len() {
+        g.new_edge(ring[i], ring[(i + 1) % ring.len()]).finalize();
+    }
+    let cycles = g.find_cycles();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].len(), 30_000);
+    assert_eq!(cycles[0][0].start_node(), ring[0]);
+}
+
+#[test]
+fn cycle_search_runs_on_a_small_stack() {
+    // Blocked nodes wait on each other in a long chain, which a small stack stands in for
+    let cycles = thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| {
+            let mut g = Graph::new();
+            let ring: Vec<Node> = (0..3_000).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+            for pair in ring.windows(2) {
+                g.new_edge(pair[0], pair[1]).finalize();
+                g.new_edge(pair[1], ring[0]).finalize();
+            }
+            g.find_cycles().len()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(cycles, 2_999);
+}
+
+#[test]
+fn longest_and_critical_paths() {
+    let mut g = Graph::new();
+    let [start, design, build, test, ship] = nodes(&mut g, ["start", "design", "build", "test", "ship"]);
+    g.new_edge(start, design).with_property("weight", 3).finalize();
+    g.new_edge(start, build).with_property("weight", 1).finalize();
+    g.new_edge(design, build).with_property("weight", 2).finalize();
+    g.new_edge(build, test).with_property("weight", 4).finalize();
+    g.new_edge(design, test).with_property("weight", 1).finalize();
+    g.new_edge(test, ship).with_property("weight", 1).finalize();
+
+    let path = g.longest_path(start, ship, "weight").unwrap().unwrap();
+    assert_eq!(path.nodes, vec![start, design, build, test, ship]);
+    assert_eq!(path.cost, 10.0);
+    assert_eq!(g.longest_path(ship, start, "weight").unwrap(), None);
+    assert_eq!(g.critical_path("weight").unwrap(), Some(path));
+    assert_eq!(Graph::new().critical_path("weight").unwrap(), None);
+
+    g.new_edge(ship, start).finalize();
+    assert!(matches!(g.critical_path("weight"), Err(GraphError::Cycle(_))));
+}
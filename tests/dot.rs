@@ -0,0 +1,240 @@
+use graph::{Graph, GraphKind, Value, ID};
+
+fn node_property(graph: &Graph, name: &str, key: &str) -> Option<Value> {
+    graph.get_node_properties(ID::from(name))?.get(key).cloned()
+}
+
+fn edge_property(graph: &Graph, start: &str, end: &str, key: &str) -> Option<Value> {
+    let edge = graph.find_edge(ID::from(start), ID::from(end))?;
+    graph.get_edge_properties(edge)?.get(key).cloned()
+}
+
+#[test]
+fn graph_kinds_are_read_from_the_header() {
+    let g = Graph::from_dot("strict graph G { a -- b; b -- a }").unwrap();
+    assert_eq!(g.kind(), GraphKind::Undirected);
+    assert!(g.is_strict());
+    assert_eq!(g.edges_between(ID::from("a"), ID::from("b")).len(), 1);
+
+    let g = Graph::from_dot("digraph { a -> b }").unwrap();
+    assert_eq!(g.kind(), GraphKind::Directed);
+    assert!(!g.is_strict());
+}
+
+#[test]
+fn edge_chains_and_subgraph_operands_connect_every_pair() {
+    let g = Graph::from_dot("digraph { a -> b -> c [color=red]; d -> { e f } }").unwrap();
+    assert_eq!(edge_property(&g, "a", "b", "color"), Some(Value::from("red")));
+    assert_eq!(edge_property(&g, "b", "c", "color"), Some(Value::from("red")));
+    assert!(g.find_edge(ID::from("a"), ID::from("c")).is_none());
+    assert!(g.find_edge(ID::from("d"), ID::from("e")).is_some());
+    assert!(g.find_edge(ID::from("d"), ID::from("f")).is_some());
+}
+
+#[test]
+fn names_become_labels_unless_one_is_given() {
+    let g = Graph::from_dot("digraph { a; b [label=\"B\"]; node [shape=box]; c }").unwrap();
+    assert_eq!(node_property(&g, "a", "label"), Some(Value::from("a")));
+    assert_eq!(node_property(&g, "b", "label"), Some(Value::from("B")));
+    // Defaults only apply to the nodes after them
+    assert_eq!(node_property(&g, "a", "shape"), None);
+    assert_eq!(node_property(&g, "c", "shape"), Some(Value::from("box")));
+}
+
+#[test]
+fn default_statements_before_items_are_stored() {
+    let g = Graph::from_dot("digraph { node [shape=box]; edge [color=red]; a -> b }").unwrap();
+    assert_eq!(g.node_defaults().get("shape"), Some(&Value::from("box")));
+    assert_eq!(g.edge_defaults().get("color"), Some(&Value::from("red")));
+    assert_eq!(node_property(&g, "a", "shape"), None);
+
+    let dot = g.to_dot();
+    assert!(dot.contains("node [shape=\"box\"];"), "{}", dot);
+    assert!(dot.contains("edge [color=\"red\"];"), "{}", dot);
+}
+
+#[test]
+fn default_statements_after_items_only_apply_to_later_items() {
+    let g = Graph::from_dot("digraph { a; node [color=red]; b; a -> b; edge [style=dashed]; b -> a }").unwrap();
+    assert!(g.node_defaults().is_empty());
+    assert!(g.edge_defaults().is_empty());
+    assert_eq!(g.resolved_node_properties(ID::from("a")).get("color"), None);
+    assert_eq!(g.resolved_node_properties(ID::from("b")).get("color"), Some(&Value::from("red")));
+
+    let first = g.find_edge(ID::from("a"), ID::from("b")).unwrap();
+    let second = g.find_edge(ID::from("b"), ID::from("a")).unwrap();
+    assert_eq!(g.resolved_edge_properties(first).get("style"), None);
+    assert_eq!(g.resolved_edge_properties(second).get("style"), Some(&Value::from("dashed")));
+}
+
+#[test]
+fn subgraph_default_statements_are_stored_in_the_subgraph() {
+    let g = Graph::from_dot("digraph { a; subgraph cluster_x { node [shape=box]; b; a } c }").unwrap();
+    let cluster = ID::from("cluster_x");
+    assert_eq!(g.get_subgraph_node_defaults(cluster).unwrap().get("shape"), Some(&Value::from("box")));
+    assert_eq!(g.resolved_node_properties(ID::from("b")).get("shape"), Some(&Value::from("box")));
+    // `a` was made before the default and keeps Graphviz's default shape
+    assert_eq!(g.subgraph_of(ID::from("a")).map(|s| s.id()), Some(cluster));
+    assert_eq!(g.resolved_node_properties(ID::from("a")).get("shape"), Some(&Value::from("")));
+    assert_eq!(g.resolved_node_properties(ID::from("c")).get("shape"), None);
+}
+
+#[test]
+fn nested_default_statements_override_pending_ones() {
+    let g = Graph::from_dot("digraph { a; node [color=red]; subgraph s { node [color=blue]; b } c }").unwrap();
+    assert_eq!(g.resolved_node_properties(ID::from("b")).get("color"), Some(&Value::from("blue")));
+    assert_eq!(g.resolved_node_properties(ID::from("c")).get("color"), Some(&Value::from("red")));
+}
+
+#[test]
+fn labels_default_to_names_unless_a_default_sets_them() {
+    let g = Graph::from_dot("digraph { a; node [label=\"same\"]; b }").unwrap();
+    assert_eq!(node_property(&g, "a", "label"), Some(Value::from("a")));
+    assert_eq!(node_property(&g, "b", "label"), Some(Value::from("same")));
+
+    // Stored defaults set the label too
+    let g = Graph::from_dot("digraph { node [label=\"same\"]; a }").unwrap();
+    assert_eq!(node_property(&g, "a", "label"), None);
+    assert_eq!(g.resolved_node_properties(ID::from("a")).get("label"), Some(&Value::from("same")));
+}
+
+#[test]
+fn attribute_statements_set_graph_properties() {
+    let g = Graph::from_dot("digraph { rankdir=LR; graph [splines=ortho, nodesep=0.5] a }").unwrap();
+    assert_eq!(g.properties().get("rankdir"), Some(&Value::from("LR")));
+    assert_eq!(g.properties().get("splines"), Some(&Value::from("ortho")));
+    assert_eq!(g.properties().get("nodesep"), Some(&Value::F64(0.5)));
+}
+
+#[test]
+fn subgraphs_hold_the_nodes_mentioned_in_them() {
+    let g = Graph::from_dot("digraph { a; subgraph cluster_x { label=X; b; subgraph inner { rank=same; a; c } } { d } }").unwrap();
+    let cluster = g.subgraph_of(ID::from("b")).unwrap();
+    assert!(cluster.is_cluster());
+    assert_eq!(cluster.id(), ID::from("cluster_x"));
+    assert_eq!(g.get_subgraph_properties(cluster).unwrap().get("label"), Some(&Value::from("X")));
+
+    // `a` moves into the innermost subgraph it is mentioned in
+    let inner = g.subgraph_of(ID::from("a")).unwrap();
+    assert!(!inner.is_cluster());
+    assert_eq!(g.subgraph_of(ID::from("c")), Some(inner));
+    assert_eq!(g.subgraph_of(inner), Some(cluster));
+    assert_eq!(g.get_subgraph_properties(inner).unwrap().get("rank"), Some(&Value::from("same")));
+    assert!(g.subgraph_of(ID::from("d")).is_some());
+}
+
+#[test]
+fn ports_become_tailport_and_headport() {
+    let g = Graph::from_dot("digraph { a:out:s -> b:w }").unwrap();
+    assert_eq!(edge_property(&g, "a", "b", "tailport"), Some(Value::from("out:s")));
+    assert_eq!(edge_property(&g, "a", "b", "headport"), Some(Value::from("w")));
+}
+
+#[test]
+fn strings_comments_and_values() {
+    let source = r#"
+        /* a block
+           comment */
+        digraph {
+            # a preprocessor line
+            a [label="first " + "second", tooltip="say \"hi\"", width=2, fixedsize=true]; // a line comment
+            b [label=<<b>bold</b>>]
+            "long \
+name"
+        }
+    "#;
+    let g = Graph::from_dot(source).unwrap();
+    assert_eq!(node_property(&g, "a", "label"), Some(Value::from("first second")));
+    assert_eq!(node_property(&g, "a", "tooltip"), Some(Value::from("say \"hi\"")));
+    assert_eq!(node_property(&g, "a", "width"), Some(Value::I32(2)));
+    assert_eq!(node_property(&g, "a", "fixedsize"), Some(Value::Bool(true)));
+    assert_eq!(node_property(&g, "b", "label"), Some(Value::html("<b>bold</b>")));
+    assert!(g.get_node_properties(ID::from("long name")).is_some());
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    let error = Graph::from_dot("digraph {\n  a -> ;\n}").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 8));
+    assert!(Graph::from_dot("digraph { a").is_err());
+    assert!(Graph::from_dot("digraph { a [label=\"open] }").is_err());
+    assert!(Graph::from_dot("network { a }").is_err());
+}
+
+#[test]
+fn native_names_keep_their_ids() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("label", "A").finalize();
+    let b = g.new_node("b").finalize();
+    let cluster = g.new_cluster("group").finalize();
+    g.move_to_subgraph(b, Some(cluster.id()));
+    g.new_edge(a, b).finalize();
+
+    let parsed = Graph::from_dot(&g.to_dot()).unwrap();
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+    assert_eq!(parsed.get_node_properties(b), g.get_node_properties(b));
+    assert_eq!(parsed.subgraph_of(b), Some(cluster));
+    assert!(parsed.find_edge(a, b).is_some());
+}
+
+fn edge_label(graph: &Graph) -> Option<Value> {
+    edge_property(graph, "a", "b", "label")
+}
+
+#[test]
+fn escape_sequences_round_trip() {
+    let source = r#"digraph { a -> b [label="x\ly\r\N \G\E\T\H\L\n"] }"#;
+    let g = Graph::from_dot(source).unwrap();
+    assert_eq!(edge_label(&g), Some(Value::from(r"x\ly\r\N \G\E\T\H\L\n")));
+    let dot = g.to_dot();
+    assert!(dot.contains(r#"label="x\ly\r\N \G\E\T\H\L\n""#), "{}", dot);
+    assert_eq!(Graph::from_dot(&dot).unwrap().to_dot(), dot);
+}
+
+#[test]
+fn escaped_backslashes_round_trip() {
+    // A shown backslash before `l` must not turn into a left-justified line break
+    let g = Graph::from_dot(r#"digraph { a -> b [label="x\\ly \\\\"] }"#).unwrap();
+    assert_eq!(edge_label(&g), Some(Value::from(r"x\\ly \\\\")));
+    let dot = g.to_dot();
+    assert!(dot.contains(r#"label="x\\ly \\\\""#), "{}", dot);
+    assert_eq!(edge_label(&Graph::from_dot(&dot).unwrap()), edge_label(&g));
+}
+
+#[test]
+fn hand_built_strings_are_written_as_they_are() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("label", "say \"hi\"\nleft\\l").finalize();
+    let b = g.new_node("b").with_property("label", r"C:\path\").finalize();
+    let dot = g.to_dot();
+    assert!(dot.contains("label=\"say \\\"hi\\\"\nleft\\l\""), "{}", dot);
+    // A backslash at the end is doubled so it can't escape the closing quote
+    assert!(dot.contains(r#"label="C:\path\\""#), "{}", dot);
+
+    let parsed = Graph::from_dot(&dot).unwrap();
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+    assert_eq!(parsed.get_node_properties(b).unwrap().get("label"), Some(&Value::from(r"C:\path\\")));
+}
+
+#[test]
+fn record_fields_escape_record_syntax() {
+    let mut g = Graph::new();
+    g.new_record("r", 1, 1).insert(0, 0, "{a|b} <c> \"d\" e\\l").finalize();
+    let dot = g.to_dot();
+    assert!(dot.contains(r#">\{a\|b\} \<c\> \"d\" e\l"#), "{}", dot);
+}
+
+#[test]
+fn html_labels_and_odd_keys_are_written_as_dot() {
+    let mut g = Graph::new();
+    let a = g.new_node("a")
+        .with_property("label", Value::html("<b>bold</b>"))
+        .with_property("data key", "x")
+        .finalize();
+    let dot = g.to_dot();
+    assert!(dot.contains("label=<<b>bold</b>>"), "{}", dot);
+    assert!(dot.contains("\"data key\"=\"x\""), "{}", dot);
+
+    let parsed = Graph::from_dot(&dot).unwrap();
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+}
@@ -0,0 +1,313 @@
+use graph::{Graph, GraphKind, Node, Value, ID};
+
+#[test]
+fn undirected_graphs_use_their_keyword_and_edge_operator() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let edge = g.new_edge(a, b).finalize();
+    assert!(!g.is_directed());
+
+    let dot = g.to_dot();
+    assert!(dot.starts_with("graph {\n"), "{}", dot);
+    assert!(dot.contains(&format!("  {} -- {} [];\n", a, b)), "{}", dot);
+    assert!(!dot.contains("->"), "{}", dot);
+
+    // Lookups ignore the direction of undirected edges
+    assert_eq!(g.find_edge(b, a), Some(edge));
+    assert_eq!(g.find_edge(a, b), Some(edge));
+}
+
+#[test]
+fn directed_lookups_keep_the_direction() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let edge = g.new_edge(a, b).finalize();
+    assert!(g.is_directed());
+    assert!(g.to_dot().starts_with("digraph {\n"));
+    assert_eq!(g.find_edge(a, b), Some(edge));
+    assert_eq!(g.find_edge(b, a), None);
+}
+
+#[test]
+fn strict_graphs_merge_edges_between_connected_nodes() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected).with_strict(true);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let first = g.new_edge(a, b).with_property("color", "red").finalize();
+    let second = g.new_edge(b, a).with_property("style", "dashed").finalize();
+    assert_eq!(first, second);
+
+    let properties = g.get_edge_properties(first).unwrap();
+    assert_eq!(properties.get("color"), Some(&Value::from("red")));
+    assert_eq!(properties.get("style"), Some(&Value::from("dashed")));
+
+    let dot = g.to_dot();
+    assert!(dot.starts_with("strict graph {\n"), "{}", dot);
+    assert_eq!(dot.matches(" -- ").count(), 1, "{}", dot);
+}
+
+#[test]
+fn strict_digraphs_keep_edges_in_both_directions() {
+    let mut g = Graph::new().with_strict(true);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let there = g.new_edge(a, b).finalize();
+    let back = g.new_edge(b, a).finalize();
+    assert_ne!(there, back);
+    assert_eq!(g.new_edge(a, b).finalize(), there);
+
+    let dot = g.to_dot();
+    assert!(dot.starts_with("strict digraph {\n"), "{}", dot);
+    assert_eq!(dot.matches(" -> ").count(), 2, "{}", dot);
+}
+
+#[test]
+fn parallel_edges_get_distinct_ids() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let first = g.new_edge(a, b).with_property("label", "0").finalize();
+    let second = g.new_edge(a, b).with_property("label", "1").finalize();
+    let back = g.new_edge(b, a).finalize();
+    assert_ne!(first.id(), second.id());
+    assert_eq!(g.get_edge_properties(first).unwrap().get("label"), Some(&Value::from("0")));
+    assert_eq!(g.get_edge_properties(second).unwrap().get("label"), Some(&Value::from("1")));
+
+    assert_eq!(g.edges_between(a, b), vec![first, second]);
+    assert_eq!(g.edges_between(b, a), vec![back]);
+    assert_eq!(g.find_edge(a, b), Some(first));
+    assert_eq!(g.to_dot().matches(" -> ").count(), 3);
+}
+
+#[test]
+fn undirected_edges_between_match_both_directions() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let first = g.new_edge(a, b).finalize();
+    let second = g.new_edge(b, a).finalize();
+    g.new_edge(a, c).finalize();
+    assert_eq!(g.edges_between(a, b), vec![first, second]);
+    assert_eq!(g.edges_between(b, a), vec![first, second]);
+    assert!(g.edges_between(b, c).is_empty());
+}
+
+#[test]
+fn explicit_edge_ids_replace_existing_edges() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let first = g.new_edge(a, b).with_id("transition").with_property("label", "x").finalize();
+    assert_eq!(first.id(), ID::from("transition"));
+    let replaced = g.new_edge(b, a).with_id("transition").with_property("label", "y").finalize();
+    assert_eq!(replaced.id(), first.id());
+    assert_eq!(g.edges_between(a, b), vec![]);
+    assert_eq!(g.edges_between(b, a), vec![replaced]);
+    assert_eq!(g.get_edge_properties(replaced).unwrap().get("label"), Some(&Value::from("y")));
+}
+
+#[test]
+fn default_statements_are_written_before_items() {
+    let mut g = Graph::new()
+        .with_node_defaults([("shape", "box"), ("style", "filled")].into_iter().collect())
+        .with_edge_defaults([("color", "gray")].into_iter().collect());
+    let a = g.new_node("a").with_property("shape", "circle").finalize();
+    let mut cluster = g.new_cluster("c").with_node_defaults([("fillcolor", "red")].into_iter().collect());
+    let b = cluster.new_node("b").finalize();
+    let cluster = cluster.finalize();
+    let edge = g.new_edge(a, b).finalize();
+
+    let dot = g.to_dot();
+    let expected = format!(
+        "digraph {{\n  graph [];\n  node [shape=\"box\", style=\"filled\"];\n  edge [color=\"gray\"];\n  {} [shape=\"circle\"];\n  subgraph {} {{\n    graph [];\n    node [fillcolor=\"red\"];\n    {} [];\n  }}\n",
+        a, cluster, b
+    );
+    assert!(dot.starts_with(&expected), "{}", dot);
+
+    // Items' own properties override the defaults of the graph and their subgraphs
+    let resolved = g.resolved_node_properties(a);
+    assert_eq!(resolved.get("shape"), Some(&Value::from("circle")));
+    assert_eq!(resolved.get("style"), Some(&Value::from("filled")));
+    let resolved = g.resolved_node_properties(b);
+    assert_eq!(resolved.get("shape"), Some(&Value::from("box")));
+    assert_eq!(resolved.get("fillcolor"), Some(&Value::from("red")));
+    assert_eq!(g.resolved_node_properties(a).get("fillcolor"), None);
+    assert_eq!(g.resolved_edge_properties(edge).get("color"), Some(&Value::from("gray")));
+}
+
+#[test]
+fn neighbors_follow_edge_order() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let ac = g.new_edge(a, c).finalize();
+    let ab = g.new_edge(a, b).finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(c, a).finalize();
+    g.new_edge(b, b).finalize();
+
+    assert_eq!(g.successors(a), vec![c, b]);
+    assert_eq!(g.predecessors(a), vec![c]);
+    assert_eq!(g.out_edges(a)[..2], [ac, ab]);
+    assert_eq!((g.out_degree(a), g.in_degree(a), g.degree(a)), (3, 1, 4));
+    // Self-loops count twice towards the degree
+    assert_eq!(g.degree(b), 4);
+    assert_eq!(g.successors(b), vec![b]);
+
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    g.new_edge(b, a).finalize();
+    g.new_edge(a, c).finalize();
+    assert_eq!(g.successors(a), vec![b, c]);
+    assert_eq!(g.predecessors(a), vec![b, c]);
+    assert_eq!((g.out_degree(a), g.in_degree(a), g.degree(a)), (2, 2, 2));
+}
+
+/// Two records which each have a field `port` in the same place, so the fields share an ID.
+fn twin_records(kind: GraphKind) -> (Graph, Node, Node, Node, Node) {
+    let mut g = Graph::new().with_kind(kind);
+    let r1 = g.new_record("r1", 1, 0).insert(0, 0, "port").finalize();
+    let r2 = g.new_record("r2", 1, 0).insert(0, 0, "port").finalize();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let (p1, p2) = (r1[0][0], r2[0][0]);
+    assert_eq!(p1.id(), p2.id());
+    g.new_edge(p1, a).finalize();
+    g.new_edge(p2, b).finalize();
+    (g, p1, p2, a, b)
+}
+
+#[test]
+fn fields_sharing_an_id_keep_their_own_edges() {
+    let (g, p1, p2, a, b) = twin_records(GraphKind::Directed);
+    assert_eq!(g.successors(p1), vec![a]);
+    assert_eq!(g.successors(p2), vec![b]);
+    assert_eq!(g.predecessors(a), vec![p1]);
+    assert_eq!(g.out_edges(p1).len(), 1);
+    assert_eq!(g.out_degree(p2), 1);
+    assert_eq!(g.degree(p1), 1);
+    assert!(g.find_edge(p1, b).is_none());
+    // A bare ID is a plain node, not a field
+    assert!(g.successors(p1.id()).is_empty());
+
+    let (g, p1, _, a, _) = twin_records(GraphKind::Undirected);
+    assert_eq!(g.successors(a), vec![p1]);
+    assert_eq!(g.successors(p1), vec![a]);
+}
+
+#[test]
+fn removing_nodes_and_records_removes_their_edges() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("color", "red").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let record = g.new_record("r", 1, 0).insert(0, 0, "field").finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).finalize();
+    g.new_edge(b, b).finalize();
+    let ac = g.new_edge(a, c).finalize();
+    let field = g.new_edge(record[0][0], c).finalize();
+    // An edge to a node sharing the field's ID isn't attached to the record
+    let bare = g.new_edge(record[0][0].id(), a).finalize();
+
+    let (removed, properties) = g.remove_node(b).unwrap();
+    assert_eq!(removed, b);
+    assert!(properties.is_empty());
+    assert!(g.remove_node(b).is_none());
+    assert!(g.get_node_properties(b).is_none());
+    assert_eq!(g.edges().copied().collect::<Vec<_>>(), vec![ac, field, bare]);
+    assert_eq!(g.degree(c), 2);
+
+    let (removed, _) = g.remove_record(record.id()).unwrap();
+    assert_eq!(removed, record);
+    assert_eq!(g.edges().copied().collect::<Vec<_>>(), vec![ac, bare]);
+    assert_eq!(g.predecessors(c), vec![a]);
+    assert_eq!(g.nodes().copied().collect::<Vec<_>>(), vec![a, c]);
+
+    let (edge, _) = g.remove_edge(ac).unwrap();
+    assert_eq!(edge, ac);
+    assert!(g.remove_edge(ac).is_none());
+    assert!(g.successors(a).is_empty());
+    assert_eq!(g.get_node_properties(a).unwrap().get("color"), Some(&Value::from("red")));
+}
+
+#[test]
+fn removing_a_record_keeps_edges_of_fields_sharing_an_id() {
+    let (mut g, p1, p2, _, b) = twin_records(GraphKind::Directed);
+    g.remove_record(p1.record().unwrap());
+    assert_eq!(g.edges().count(), 1);
+    assert_eq!(g.successors(p2), vec![b]);
+}
+
+#[test]
+fn removing_a_subgraph_moves_its_members_up() {
+    let mut g = Graph::new();
+    let mut outer = g.new_cluster("outer");
+    let mut inner = outer.new_cluster("inner").with_node_defaults([("color", "red")].into_iter().collect());
+    let a = inner.new_node("a").finalize();
+    let nested = inner.new_subgraph("nested").finalize();
+    let inner = inner.finalize();
+    let outer = outer.finalize();
+
+    let (removed, _) = g.remove_subgraph(inner).unwrap();
+    assert_eq!(removed, inner);
+    assert_eq!(g.subgraph_of(a), Some(outer));
+    assert_eq!(g.subgraph_of(nested.id()), Some(outer));
+    assert!(g.get_subgraph_node_defaults(inner).is_none());
+    assert_eq!(g.resolved_node_properties(a).get("color"), None);
+    assert!(!g.to_dot().contains(&inner.name()));
+}
+
+#[test]
+fn edges_can_be_moved_to_other_nodes() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let first = g.new_edge(a, b).with_property("label", "x").finalize();
+    let second = g.new_edge(b, c).finalize();
+
+    let moved = g.set_edge_endpoints(first, c, a).unwrap();
+    assert_eq!(moved.id(), first.id());
+    assert_eq!((moved.start_node(), moved.end_node()), (c, a));
+    assert!(g.successors(a).is_empty());
+    assert_eq!(g.successors(c), vec![a]);
+    assert_eq!(g.predecessors(b), Vec::<Node>::new());
+    assert_eq!(g.get_edge_properties(first).unwrap().get("label"), Some(&Value::from("x")));
+    // The edge keeps its place in the order
+    assert_eq!(g.edges().copied().collect::<Vec<_>>(), vec![moved, second]);
+    assert!(g.set_edge_endpoints("missing", a, b).is_none());
+
+    // Strict graphs merge an edge moved onto an existing one
+    let mut g = Graph::new().with_strict(true);
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let ab = g.new_edge(a, b).with_property("color", "red").finalize();
+    let ac = g.new_edge(a, c).with_property("label", "ac").finalize();
+    assert_eq!(g.set_edge_endpoints(ac, a, b), Some(ab));
+    assert_eq!(g.edges().count(), 1);
+    assert_eq!(g.get_edge_properties(ab).unwrap().get("label"), Some(&Value::from("ac")));
+    assert_eq!(g.get_edge_properties(ab).unwrap().get("color"), Some(&Value::from("red")));
+}
+
+#[test]
+fn algorithms_tell_fields_sharing_an_id_apart() {
+    let (g, p1, p2, a, b) = twin_records(GraphKind::Directed);
+    assert!(g.shortest_path(p1, b, "weight").is_none());
+    assert_eq!(g.shortest_path(p2, b, "weight").unwrap().nodes, vec![p2, b]);
+
+    let sorted = g.topological_sort().unwrap();
+    assert!(sorted.contains(&p1) && sorted.contains(&p2));
+    assert_eq!(g.weakly_connected_components(), vec![vec![a, p1], vec![b, p2]]);
+    assert_eq!(g.strongly_connected_components().len(), 4);
+    assert_eq!(g.condensation().nodes().count(), 4);
+    assert_eq!(g.transitive_closure().edges().count(), 2);
+    assert_eq!(g.degree_centrality().len(), 4);
+}
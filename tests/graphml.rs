@@ -0,0 +1,175 @@
+use graph::{Graph, GraphKind, Properties, Record, Value, ID};
+
+fn node(graph: &Graph, name: &str, key: &str) -> Option<Value> {
+    graph.get_node_properties(ID::from(name))?.get(key).cloned()
+}
+
+fn document(keys: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\"?>\n<!-- made by hand -->\n<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:y=\"http://www.yworks.com/xml/graphml\">\n{}\n<graph id=\"G\" edgedefault=\"directed\">\n{}\n</graph>\n</graphml>\n",
+        keys, body
+    )
+}
+
+#[test]
+fn entities_and_cdata_are_read_as_text() {
+    let source = document(
+        "<key id=\"l\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>",
+        "<node id=\"a\"><data key=\"l\">Tom &amp; Jerry &lt;3 &#65;&#x42;</data></node>\n<node id=\"b\"><data key=\"l\"><![CDATA[<b>bold</b> & more]]></data></node>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    assert_eq!(node(&g, "a", "label"), Some(Value::from("Tom & Jerry <3 AB")));
+    assert_eq!(node(&g, "b", "label"), Some(Value::from("<b>bold</b> & more")));
+
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("label", "<\"quoted\" & 'single'>\nnext line").finalize();
+    let parsed = Graph::from_graphml(&g.to_graphml()).unwrap();
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+}
+
+#[test]
+fn yed_graphics_are_skipped() {
+    let source = document(
+        "<key id=\"d0\" for=\"node\" yfiles.type=\"nodegraphics\"/>\n<key id=\"d1\" for=\"node\" attr.name=\"description\" attr.type=\"string\"/>\n<key id=\"d2\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>",
+        "<node id=\"n0\">\n  <data key=\"d0\"><y:ShapeNode><y:NodeLabel>Shown</y:NodeLabel></y:ShapeNode></data>\n  <data key=\"d1\"><y:Markup>ignored</y:Markup></data>\n  <data key=\"d2\">Kept</data>\n</node>\n<node id=\"n1\"/>\n<edge source=\"n0\" target=\"n1\"/>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    assert_eq!(node(&g, "n0", "label"), Some(Value::from("Kept")));
+    assert_eq!(node(&g, "n0", "description"), None);
+    assert_eq!(node(&g, "n1", "label"), Some(Value::from("n1")));
+    assert!(g.find_edge(ID::from("n0"), ID::from("n1")).is_some());
+}
+
+#[test]
+fn keys_for_all_apply_everywhere() {
+    let source = document(
+        "<key id=\"c\" for=\"all\" attr.name=\"color\" attr.type=\"string\"/>\n<key id=\"w\" attr.name=\"weight\" attr.type=\"int\"/>",
+        "<data key=\"c\">gray</data>\n<node id=\"a\"><data key=\"c\">red</data><data key=\"w\">2</data></node>\n<node id=\"b\"/>\n<edge source=\"a\" target=\"b\"><data key=\"c\">blue</data><data key=\"w\">7</data></edge>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    assert_eq!(g.properties().get("color"), Some(&Value::from("gray")));
+    assert_eq!(node(&g, "a", "color"), Some(Value::from("red")));
+    assert_eq!(node(&g, "a", "weight"), Some(Value::I32(2)));
+    let edge = g.find_edge(ID::from("a"), ID::from("b")).unwrap();
+    let properties = g.get_edge_properties(edge.id()).unwrap();
+    assert_eq!(properties.get("color"), Some(&Value::from("blue")));
+    assert_eq!(properties.get("weight"), Some(&Value::I32(7)));
+}
+
+#[test]
+fn defaults_and_data_keep_their_types() {
+    let source = document(
+        "<key id=\"s\" for=\"node\" attr.name=\"size\" attr.type=\"double\"><default>1.5</default></key>\n<key id=\"f\" for=\"node\" attr.name=\"fixed\" attr.type=\"boolean\"><default>true</default></key>\n<key id=\"p\" for=\"edge\" attr.name=\"penwidth\" attr.type=\"int\"><default>3</default></key>\n<key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>",
+        "<node id=\"a\"><data key=\"s\">INF</data><data key=\"f\">0</data><data key=\"x\">-INF</data></node>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    assert_eq!(g.node_defaults().get("size"), Some(&Value::F64(1.5)));
+    assert_eq!(g.node_defaults().get("fixed"), Some(&Value::Bool(true)));
+    assert_eq!(g.edge_defaults().get("penwidth"), Some(&Value::I32(3)));
+    assert_eq!(node(&g, "a", "size"), Some(Value::F64(f64::INFINITY)));
+    assert_eq!(node(&g, "a", "fixed"), Some(Value::Bool(false)));
+    assert_eq!(node(&g, "a", "x"), Some(Value::F64(f64::NEG_INFINITY)));
+
+    let bad = document("<key id=\"f\" for=\"node\" attr.name=\"fixed\" attr.type=\"boolean\"/>", "<node id=\"a\"><data key=\"f\">maybe</data></node>");
+    assert!(Graph::from_graphml(&bad).is_err());
+
+    let mut g = Graph::new()
+        .with_kind(GraphKind::Undirected)
+        .with_node_defaults([("shape", Value::from("box")), ("width", Value::F64(0.5))].into_iter().collect());
+    let a = g.new_node("a").with_property("fixed", Value::Bool(true)).with_property("rank", 2).with_property("width", 1.25).finalize();
+    let b = g.new_node("b").finalize();
+    g.new_edge(a, b).with_property("weight", 4).finalize();
+    let parsed = Graph::from_graphml(&g.to_graphml()).unwrap();
+    assert_eq!(parsed.kind(), GraphKind::Undirected);
+    assert_eq!(parsed.node_defaults(), g.node_defaults());
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+    let edge = parsed.find_edge(a, b).unwrap();
+    assert_eq!(parsed.get_edge_properties(edge.id()).unwrap().get("weight"), Some(&Value::I32(4)));
+}
+
+#[test]
+fn records_keep_their_fields_as_ports() {
+    let mut g = Graph::new();
+    let record = g.new_record("r", 2, 0).insert(0, 0, "in").insert(1, 0, "top").insert(1, 1, "bottom").finalize();
+    let target = g.new_node("t").finalize();
+    g.new_edge(record[1][1], target).finalize();
+
+    let parsed = Graph::from_graphml(&g.to_graphml()).unwrap();
+    assert!(parsed.to_dot().contains(&record.to_string()));
+    assert!(parsed.find_edge(record[1][1], target).is_some());
+
+    let source = document(
+        "<key id=\"c\" for=\"port\" attr.name=\"column\" attr.type=\"int\"/>\n<key id=\"r\" for=\"port\" attr.name=\"row\" attr.type=\"int\"/>",
+        "<node id=\"box\">\n  <port name=\"b\"><data key=\"c\">1</data><data key=\"r\">0</data></port>\n  <port name=\"a\"><data key=\"c\">0</data><data key=\"r\">0</data></port>\n</node>\n<node id=\"x\"/>\n<edge source=\"box\" sourceport=\"b\" target=\"x\"/>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    let mut record = Record::new("box", 2, 0);
+    record.insert_node(0, 0, ID::from("a"), "a");
+    record.insert_node(1, 0, ID::from("b"), "b");
+    assert!(g.to_dot().contains(&record.to_string()), "{}", g.to_dot());
+    assert!(g.find_edge(record[1][0], ID::from("x")).is_some());
+}
+
+#[test]
+fn nested_graphs_become_subgraphs() {
+    let source = document(
+        "<key id=\"l\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>",
+        "<node id=\"outer\">\n  <graph id=\"outer:\" edgedefault=\"directed\">\n    <node id=\"a\"/>\n    <node id=\"inner\"><graph id=\"inner:\"><node id=\"b\"/><edge source=\"a\" target=\"b\"/></graph></node>\n  </graph>\n</node>\n<node id=\"c\"/>",
+    );
+    let g = Graph::from_graphml(&source).unwrap();
+    let (outer, inner) = (ID::from("outer"), ID::from("inner"));
+    assert!(g.subgraph_of(inner).unwrap().is_cluster() && g.subgraph_of(ID::from("b")).unwrap().is_cluster());
+    assert_eq!(g.get_subgraph_properties(outer).unwrap().get("label"), Some(&Value::from("outer")));
+    assert_eq!(g.subgraph_of(inner).map(|s| s.id()), Some(outer));
+    assert_eq!(g.subgraph_of(ID::from("a")).map(|s| s.id()), Some(outer));
+    assert_eq!(g.subgraph_of(ID::from("b")).map(|s| s.id()), Some(inner));
+    assert!(g.subgraph_of(ID::from("c")).is_none());
+    let edge = g.find_edge(ID::from("a"), ID::from("b")).unwrap();
+    assert_eq!(g.subgraph_of(edge.id()).map(|s| s.id()), Some(inner));
+
+    let mut g = Graph::new();
+    let cluster = g.new_cluster("group").with_property("label", "Group").finalize();
+    let plain = g.new_subgraph("same rank").with_property("rank", "same").finalize();
+    g.move_to_subgraph(plain.id(), Some(cluster.id()));
+    let a = g.new_node("a").finalize();
+    g.move_to_subgraph(a, Some(plain.id()));
+    let parsed = Graph::from_graphml(&g.to_graphml()).unwrap();
+    assert_eq!(parsed.subgraph_of(a), Some(plain));
+    assert_eq!(parsed.subgraph_of(plain.id()), Some(cluster));
+    assert_eq!(parsed.get_subgraph_properties(plain.id()), g.get_subgraph_properties(plain.id()));
+    assert_eq!(parsed.to_graphml(), g.to_graphml());
+}
+
+#[test]
+fn subgraph_defaults_are_written_into_their_members() {
+    let mut g = Graph::new().with_node_defaults(Properties::from_iter([("shape", "box")]));
+    let mut outer = g.new_cluster("outer")
+        .with_node_defaults(Properties::from_iter([("color", "red"), ("style", "filled")]))
+        .with_edge_defaults(Properties::from_iter([("color", "blue")]));
+    let mut inner = outer.new_subgraph("inner").with_node_defaults(Properties::from_iter([("color", "green")]));
+    let a = inner.new_node("a").finalize();
+    let b = inner.new_node("b").with_property("style", "dashed").finalize();
+    let inner = inner.finalize();
+    let r = outer.new_record("r", 1, 1).insert(0, 0, "field").finalize();
+    let outer = outer.finalize();
+    let c = g.new_node("c").finalize();
+    let ab = g.new_edge(a, b).in_subgraph(inner).finalize();
+    let ac = g.new_edge(a, c).finalize();
+
+    let parsed = Graph::from_graphml(&g.to_graphml()).unwrap();
+    for id in [a.id(), b.id(), r.id(), c.id()] {
+        assert_eq!(parsed.resolved_node_properties(id), g.resolved_node_properties(id));
+    }
+    assert_eq!(parsed.resolved_node_properties(a).get("color"), Some(&Value::from("green")));
+    assert_eq!(parsed.resolved_node_properties(r.id()).get("color"), Some(&Value::from("red")));
+    assert_eq!(parsed.resolved_node_properties(c).get("color"), None);
+    for edge in [ab, ac] {
+        assert_eq!(parsed.resolved_edge_properties(edge.id()), g.resolved_edge_properties(edge.id()));
+    }
+    assert_eq!(parsed.resolved_edge_properties(ab.id()).get("color"), Some(&Value::from("blue")));
+    assert_eq!(parsed.subgraph_of(a), Some(inner));
+    assert_eq!(parsed.subgraph_of(&r), Some(outer));
+
+    // The reparsed graph holds the defaults on its members, so it writes the same GraphML
+    assert_eq!(parsed.to_graphml(), g.to_graphml());
+}
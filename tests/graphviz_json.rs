@@ -0,0 +1,99 @@
+use graph::{Graph, GraphError, Layout, Node, Point, Record, Rect};
+use serde_json::json;
+
+fn first_field(record: &Record) -> Node {
+    record.nodes().columns().flat_map(|column| column.cells()).next().unwrap().0
+}
+
+fn inside(outer: &Rect, inner: &Rect) -> bool {
+    outer.left() <= inner.left() && inner.right() <= outer.right() && outer.top() <= inner.top() && inner.bottom() <= outer.bottom()
+}
+
+#[test]
+fn positions_are_read_under_their_ids() {
+    let mut g = Graph::new();
+    let mut cluster = g.new_cluster("cluster");
+    let a = cluster.new_node("a").finalize();
+    let b = cluster.new_node("b").finalize();
+    let cluster = cluster.finalize();
+    let ab = g.new_edge(a, b).with_property("label", "ab").finalize();
+
+    let json = json!({
+        "bb": "0,0,200,200",
+        "_subgraph_cnt": 1,
+        "objects": [
+            { "_gvid": 0, "name": cluster.name(), "bb": "10,10,110,190" },
+            { "_gvid": 1, "name": a.name(), "pos": "60,150", "width": "0.75", "height": "0.5" },
+            { "_gvid": 2, "name": b.name(), "pos": "60,50", "width": "0.75", "height": "0.5" },
+        ],
+        "edges": [
+            { "_gvid": 0, "tail": 1, "head": 2, "pos": "e,60,68 60,132 60,110 60,90 60,78", "lp": "70,100" },
+        ],
+    });
+    let layout = Layout::from_graphviz_json(&g, json.to_string().as_bytes()).unwrap();
+
+    // Everything is moved by the same amount to fit the margin, with `y` flipped to grow downwards
+    let bounds = *layout.subgraph(cluster).unwrap();
+    let origin = Point::new(bounds.left() - 10.0, bounds.top() - 10.0);
+    assert_eq!((bounds.width, bounds.height), (100.0, 180.0));
+    let (a, b) = (*layout.node(a).unwrap(), *layout.node(b).unwrap());
+    assert_eq!((a.width, a.height), (54.0, 36.0));
+    assert_eq!(a.center, Point::new(origin.x + 60.0, origin.y + 50.0));
+    assert_eq!(b.center, Point::new(origin.x + 60.0, origin.y + 150.0));
+
+    let route = layout.edge(ab).unwrap();
+    assert!(route.spline);
+    assert_eq!(route.points.len(), 4);
+    assert_eq!(route.points[0], Point::new(origin.x + 60.0, origin.y + 68.0));
+    assert_eq!(route.head, Some(Point::new(origin.x + 60.0, origin.y + 132.0)));
+    assert_eq!(route.tail, None);
+    assert_eq!(route.label, Some(Point::new(origin.x + 70.0, origin.y + 100.0)));
+}
+
+#[test]
+fn identical_records_keep_their_fields_apart() {
+    let mut g = Graph::new();
+    let first = g.new_record("first", 1, 2).insert(0, 0, "top").insert(0, 1, "bottom").finalize();
+    let second = g.new_record("second", 1, 2).insert(0, 0, "top").insert(0, 1, "bottom").finalize();
+    let a = g.new_node("a").finalize();
+    let to_first = g.new_edge(a, first_field(&first)).finalize();
+    let to_second = g.new_edge(a, first_field(&second)).finalize();
+
+    let json = json!({
+        "bb": "0,0,300,200",
+        "objects": [
+            { "_gvid": 0, "name": first.name(), "pos": "50,50", "width": "1", "height": "1", "rects": "14,50,86,86 14,14,86,50" },
+            { "_gvid": 1, "name": second.name(), "pos": "250,50", "width": "1", "height": "1", "rects": "214,50,286,86 214,14,286,50" },
+            { "_gvid": 2, "name": a.name(), "pos": "150,150", "width": "0.75", "height": "0.5" },
+        ],
+        "edges": [
+            { "_gvid": 0, "tail": 2, "head": 0, "pos": "150,132 120,110 80,90 50,86" },
+            { "_gvid": 1, "tail": 2, "head": 1, "pos": "150,132 180,110 220,90 250,86" },
+        ],
+    });
+    let layout = Layout::from_graphviz_json(&g, json.to_string().as_bytes()).unwrap();
+
+    let first_cell = layout.node(first_field(&first)).unwrap();
+    let second_cell = layout.node(first_field(&second)).unwrap();
+    assert_ne!(first_cell, second_cell);
+    assert!(inside(layout.node(first.id()).unwrap(), first_cell));
+    assert!(inside(layout.node(second.id()).unwrap(), second_cell));
+    assert_eq!(second_cell.center.x - first_cell.center.x, 200.0);
+
+    let end = |edge| *layout.edge(edge).unwrap().points.last().unwrap();
+    assert_eq!(end(to_second).x - end(to_first).x, 200.0);
+}
+
+#[test]
+fn invalid_output_is_reported() {
+    let g = Graph::new();
+    let error = |json: &str| Layout::from_graphviz_json(&g, json.as_bytes()).unwrap_err();
+    assert!(matches!(error("not json"), GraphError::InvalidOutput(_)));
+    assert!(matches!(error("{}"), GraphError::InvalidOutput(message) if message == "the graph has no bounding box"));
+
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let json = json!({ "bb": "0,0,10,10", "objects": [{ "_gvid": 0, "name": a.name() }] });
+    let error = Layout::from_graphviz_json(&g, json.to_string().as_bytes()).unwrap_err();
+    assert_eq!(error.to_string(), format!("invalid Graphviz output: `{}` has no position", a.name()));
+}
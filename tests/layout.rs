@@ -0,0 +1,147 @@
+use graph::{ForceLayout, Graph, LayeredLayout, Node, RankDir, Record, Rect};
+
+fn overlap(a: &Rect, b: &Rect) -> bool {
+    a.left() < b.right() && b.left() < a.right() && a.top() < b.bottom() && b.top() < a.bottom()
+}
+
+fn inside(outer: &Rect, inner: &Rect) -> bool {
+    outer.left() <= inner.left() && inner.right() <= outer.right() && outer.top() <= inner.top() && inner.bottom() <= outer.bottom()
+}
+
+fn first_field(record: &Record) -> Node {
+    record.nodes().columns().flat_map(|column| column.cells()).next().unwrap().0
+}
+
+#[test]
+fn edges_flow_down_the_layers() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).finalize();
+    g.new_edge(a, c).finalize();
+
+    let layout = g.layered_layout();
+    let rect = |node: Node| *layout.node(node).unwrap();
+    assert!(rect(a).bottom() < rect(b).top());
+    assert!(rect(b).bottom() < rect(c).top());
+    assert!(!overlap(&rect(a), &rect(b)) && !overlap(&rect(b), &rect(c)) && !overlap(&rect(a), &rect(c)));
+    for rect in [rect(a), rect(b), rect(c)] {
+        assert!(rect.left() >= 0.0 && rect.right() <= layout.width() && rect.top() >= 0.0 && rect.bottom() <= layout.height());
+    }
+}
+
+#[test]
+fn rank_direction_turns_the_layers() {
+    let mut g = Graph::new().with_property("rankdir", "LR");
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let edge = g.new_edge(a, b).finalize();
+
+    let layout = g.layered_layout();
+    assert!(layout.node(a).unwrap().right() < layout.node(b).unwrap().left());
+    let route = layout.edge(edge).unwrap();
+    assert!(route.points.first().unwrap().x < route.points.last().unwrap().x);
+
+    // An explicit direction overrides the graph's attribute
+    let layout = LayeredLayout::for_graph(&g).with_rank_dir(RankDir::TopToBottom).layout(&g);
+    assert!(layout.node(a).unwrap().bottom() < layout.node(b).unwrap().top());
+}
+
+#[test]
+fn subgraph_boxes_hold_their_members() {
+    let mut g = Graph::new();
+    let mut cluster = g.new_cluster("cluster").with_property("label", "Cluster");
+    let a = cluster.new_node("a").finalize();
+    let b = cluster.new_node("b").finalize();
+    let cluster = cluster.finalize();
+    let c = g.new_node("c").finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).finalize();
+
+    let layout = g.layered_layout();
+    let bounds = layout.subgraph(cluster).unwrap();
+    assert!(inside(bounds, layout.node(a).unwrap()));
+    assert!(inside(bounds, layout.node(b).unwrap()));
+    assert!(!overlap(bounds, layout.node(c).unwrap()));
+}
+
+#[test]
+fn identical_records_keep_their_fields_apart() {
+    let mut g = Graph::new();
+    let first = g.new_record("first", 1, 2).insert(0, 0, "top").insert(0, 1, "bottom").finalize();
+    let second = g.new_record("second", 1, 2).insert(0, 0, "top").insert(0, 1, "bottom").finalize();
+    let (first_top, second_top) = (first_field(&first), first_field(&second));
+    assert_eq!(first_top.id(), second_top.id());
+
+    let a = g.new_node("a").finalize();
+    let to_first = g.new_edge(a, first_top).finalize();
+    let to_second = g.new_edge(a, second_top).finalize();
+
+    let layout = g.layered_layout();
+    let first_rect = layout.node(first.id()).unwrap();
+    let second_rect = layout.node(second.id()).unwrap();
+    let first_cell = layout.node(first_top).unwrap();
+    let second_cell = layout.node(second_top).unwrap();
+    assert!(inside(first_rect, first_cell));
+    assert!(inside(second_rect, second_cell));
+    assert_ne!(first_cell, second_cell);
+
+    let end = |edge| *layout.edge(edge).unwrap().points.last().unwrap();
+    assert_ne!(end(to_first), end(to_second));
+    assert!(first_rect.left() <= end(to_first).x && end(to_first).x <= first_rect.right());
+    assert!(second_rect.left() <= end(to_second).x && end(to_second).x <= second_rect.right());
+}
+
+#[test]
+fn pinned_nodes_stay_put() {
+    let mut g = Graph::new().with_property("layout", "fdp");
+    let a = g.new_node("a").with_property("pos", "0,0!").finalize();
+    let b = g.new_node("b").with_property("pos", "2,1!").finalize();
+    let c = g.new_node("c").with_property("pos", "1,3").with_property("pin", "true").finalize();
+    let free: Vec<Node> = (0..10).map(|i| g.new_node(format!("free{}", i)).finalize()).collect();
+    for &node in &free {
+        g.new_edge(a, node).finalize();
+        g.new_edge(node, b).finalize();
+    }
+
+    // The layout is moved to fit the margin, so pinned nodes keep their offsets from each other
+    let layout = g.native_layout();
+    let (a, b, c) = (layout.node(a).unwrap().center, layout.node(b).unwrap().center, layout.node(c).unwrap().center);
+    assert!((b.x - a.x - 144.0).abs() < 1e-9 && (b.y - a.y + 72.0).abs() < 1e-9, "{:?} {:?}", a, b);
+    assert!((c.x - a.x - 72.0).abs() < 1e-9 && (c.y - a.y + 216.0).abs() < 1e-9, "{:?} {:?}", a, c);
+}
+
+#[test]
+fn force_layout_leaves_no_overlaps() {
+    let mut g = Graph::new();
+    let nodes: Vec<Node> = (0..40).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+    for i in 0..nodes.len() {
+        g.new_edge(nodes[i], nodes[(i * 7 + 3) % nodes.len()]).finalize();
+        g.new_edge(nodes[i], nodes[(i + 1) % nodes.len()]).finalize();
+    }
+
+    let layout = ForceLayout::new().with_seed(7).layout(&g);
+    let rects: Vec<Rect> = nodes.iter().map(|&node| *layout.node(node).unwrap()).collect();
+    for (i, first) in rects.iter().enumerate() {
+        for second in &rects[i + 1..] {
+            assert!(!overlap(first, second), "{:?} {:?}", first, second);
+        }
+    }
+}
+
+#[test]
+fn force_layout_is_reproducible_from_its_seed() {
+    let mut g = Graph::new();
+    let nodes: Vec<Node> = (0..12).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+    for pair in nodes.windows(2) {
+        g.new_edge(pair[0], pair[1]).finalize();
+    }
+
+    let layout = ForceLayout::new().with_seed(3);
+    assert_eq!(layout.layout(&g), layout.layout(&g));
+    assert_ne!(layout.layout(&g), layout.with_seed(4).layout(&g));
+    let expected = layout.layout(&g);
+    assert_eq!(g.with_property("start", 3).force_layout(), expected);
+}
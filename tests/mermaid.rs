@@ -0,0 +1,86 @@
+use graph::{Graph, Value, ID};
+
+fn property(graph: &Graph, name: &str, key: &str) -> Option<Value> {
+    graph.get_node_properties(ID::from(name))?.get(key).cloned()
+}
+
+fn edge_property(graph: &Graph, start: &str, end: &str, key: &str) -> Option<Value> {
+    let edge = graph.find_edge(ID::from(start), ID::from(end))?;
+    graph.get_edge_properties(edge.id())?.get(key).cloned()
+}
+
+#[test]
+fn shapes_become_labels_and_shapes() {
+    let g = Graph::from_mermaid("flowchart LR\n  a[Box] --> b(Round) --> c((Circle))\n  c --> d(((Double))) --> e{Choice} --> f{{Hex}} --> g[(Store)] --> h\n").unwrap();
+    assert_eq!(g.properties().get("rankdir"), Some(&Value::from("LR")));
+    for (name, label, shape) in [
+        ("a", "Box", Some("box")),
+        ("b", "Round", None),
+        ("c", "Circle", Some("circle")),
+        ("d", "Double", Some("doublecircle")),
+        ("e", "Choice", Some("diamond")),
+        ("f", "Hex", Some("hexagon")),
+        ("g", "Store", Some("cylinder")),
+        ("h", "h", Some("box")),
+    ] {
+        assert_eq!(property(&g, name, "label"), Some(Value::from(label)), "{}", name);
+        assert_eq!(property(&g, name, "shape"), shape.map(Value::from), "{}", name);
+    }
+}
+
+#[test]
+fn ampersands_link_every_pair() {
+    let g = Graph::from_mermaid("graph TD\n  a & b --> c & d --> e").unwrap();
+    assert_eq!(g.to_dot().matches(" -> ").count(), 6);
+    for (start, end) in [("a", "c"), ("a", "d"), ("b", "c"), ("b", "d"), ("c", "e"), ("d", "e")] {
+        assert!(g.find_edge(ID::from(start), ID::from(end)).is_some(), "{} -> {}", start, end);
+    }
+}
+
+#[test]
+fn link_labels_and_styles() {
+    let g = Graph::from_mermaid("flowchart TD\n  a -->|\"go #amp; stop\"| b\n  b -- text here --> c\n  c -.-> d\n  d === e\n  e <--> f").unwrap();
+    assert_eq!(edge_property(&g, "a", "b", "label"), Some(Value::from("go & stop")));
+    assert_eq!(edge_property(&g, "b", "c", "label"), Some(Value::from("text here")));
+    assert_eq!(edge_property(&g, "c", "d", "style"), Some(Value::from("dashed")));
+    assert_eq!(edge_property(&g, "d", "e", "style"), Some(Value::from("bold")));
+    assert_eq!(edge_property(&g, "d", "e", "dir"), Some(Value::from("none")));
+    assert_eq!(edge_property(&g, "e", "f", "dir"), Some(Value::from("both")));
+}
+
+#[test]
+fn subgraphs_nest_as_clusters() {
+    let source = "flowchart TB\n  subgraph outer[Outer]\n    direction LR\n    a\n    subgraph inner\n      b --> c\n    end\n  end\n  d --> a";
+    let g = Graph::from_mermaid(source).unwrap();
+    let (outer, inner) = (ID::from("outer"), ID::from("inner"));
+    assert_eq!(g.get_subgraph_properties(outer).unwrap().get("label"), Some(&Value::from("Outer")));
+    assert_eq!(g.get_subgraph_properties(outer).unwrap().get("rankdir"), Some(&Value::from("LR")));
+    assert_eq!(g.get_subgraph_properties(inner).unwrap().get("label"), Some(&Value::from("inner")));
+    assert_eq!(g.subgraph_of(inner).map(|s| s.id()), Some(outer));
+    assert!(g.subgraph_of(outer).is_none());
+    assert_eq!(g.subgraph_of(ID::from("a")).map(|s| s.id()), Some(outer));
+    assert_eq!(g.subgraph_of(ID::from("b")).map(|s| s.id()), Some(inner));
+    assert!(g.subgraph_of(ID::from("d")).is_none());
+    assert!(g.subgraph_of(inner).unwrap().is_cluster() && g.subgraph_of(ID::from("b")).unwrap().is_cluster());
+
+    assert!(Graph::from_mermaid("flowchart TB\n  subgraph s\n  a").is_err());
+    assert!(Graph::from_mermaid("flowchart TB\n  end").is_err());
+}
+
+#[test]
+fn round_trips_keep_ids() {
+    let mut g = Graph::new().with_property("rankdir", "LR");
+    let a = g.new_node("a").with_property("label", "Start\n\"here\"").with_property("shape", "box").finalize();
+    let b = g.new_node("b").with_property("label", "Stop").with_property("shape", "circle").finalize();
+    let cluster = g.new_cluster("group").with_property("label", "Group").finalize();
+    g.move_to_subgraph(b, Some(cluster.id()));
+    g.new_edge(a, b).with_property("label", "go").finalize();
+
+    let parsed = Graph::from_mermaid(&g.to_mermaid()).unwrap();
+    assert_eq!(parsed.get_node_properties(a), g.get_node_properties(a));
+    assert_eq!(parsed.get_node_properties(b), g.get_node_properties(b));
+    assert_eq!(parsed.subgraph_of(b), Some(cluster));
+    let edge = parsed.find_edge(a, b).unwrap();
+    assert_eq!(parsed.get_edge_properties(edge.id()).unwrap().get("label"), Some(&Value::from("go")));
+    assert_eq!(parsed.to_mermaid(), g.to_mermaid());
+}
@@ -0,0 +1,128 @@
+use graph::{Edge, Graph, GraphError, GraphKind, Node};
+
+/// Edges `a -> b -> d` costing 1 each, and `a -> c -> d` costing 1 and 5.
+fn diamond(kind: GraphKind) -> (Graph, [Node; 4]) {
+    let mut g = Graph::new().with_kind(kind);
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|name| g.new_node(name).finalize());
+    g.new_edge(a, b).with_property("weight", 1).finalize();
+    g.new_edge(b, d).with_property("weight", 1.0).finalize();
+    g.new_edge(a, c).finalize();
+    g.new_edge(c, d).with_property("weight", "5").finalize();
+    (g, [a, b, c, d])
+}
+
+/// Whether the edges lead from one to the next and back to the first.
+fn is_cycle(edges: &[Edge]) -> bool {
+    !edges.is_empty() && (0..edges.len()).all(|i| edges[i].end_node() == edges[(i + 1) % edges.len()].start_node())
+}
+
+#[test]
+fn dijkstra_finds_the_cheapest_path() {
+    let (g, [a, b, c, d]) = diamond(GraphKind::Directed);
+    let path = g.shortest_path(a, d, "weight").unwrap();
+    assert_eq!(path.nodes, vec![a, b, d]);
+    assert_eq!(path.edges, vec![g.find_edge(a, b).unwrap(), g.find_edge(b, d).unwrap()]);
+    assert_eq!(path.cost, 2.0);
+
+    // A closure can weigh the edges instead
+    let through_c = g.shortest_path(a, d, |edge: Edge, _: &_| if edge.start_node() == a && edge.end_node() == b { 10.0 } else { 1.0 }).unwrap();
+    assert_eq!(through_c.nodes, vec![a, c, d]);
+    assert_eq!(through_c.cost, 2.0);
+
+    assert!(g.shortest_path(d, a, "weight").is_none());
+    let (g, _) = diamond(GraphKind::Undirected);
+    assert_eq!(g.shortest_path(d, a, "weight").unwrap().nodes, vec![d, b, a]);
+}
+
+#[test]
+fn astar_matches_dijkstra_with_a_good_heuristic() {
+    // A 5 by 5 grid with unit edges to the right and down
+    let mut g = Graph::new();
+    let grid: Vec<Vec<Node>> = (0..5).map(|y| (0..5).map(|x| g.new_node(format!("{},{}", x, y)).finalize()).collect()).collect();
+    for y in 0..5 {
+        for x in 0..5 {
+            if x + 1 < 5 {
+                g.new_edge(grid[y][x], grid[y][x + 1]).finalize();
+            }
+            if y + 1 < 5 {
+                g.new_edge(grid[y][x], grid[y + 1][x]).finalize();
+            }
+        }
+    }
+    let goal = grid[4][4];
+    let position = |node: Node| (0..5).flat_map(|y| (0..5).map(move |x| (x, y))).find(|&(x, y)| grid[y][x] == node).unwrap();
+    let manhattan = |node: Node| {
+        let (x, y) = position(node);
+        (4 - x + 4 - y) as f64
+    };
+
+    let astar = g.astar_path(grid[0][0], goal, "weight", manhattan).unwrap();
+    let dijkstra = g.shortest_path(grid[0][0], goal, "weight").unwrap();
+    assert_eq!(astar.cost, 8.0);
+    assert_eq!(astar.cost, dijkstra.cost);
+    assert_eq!(astar.nodes.len(), 9);
+    assert_eq!(astar.nodes.last(), Some(&goal));
+    assert!(g.astar_path(goal, grid[0][0], "weight", |_| 0.0).is_none());
+}
+
+#[test]
+fn paths_from_a_node_to_itself_are_empty() {
+    let (g, [a, ..]) = diamond(GraphKind::Directed);
+    for path in [
+        g.shortest_path(a, a, "weight"),
+        g.astar_path(a, a, "weight", |_| 0.0),
+        g.bellman_ford_path(a, a, "weight").unwrap(),
+    ] {
+        let path = path.unwrap();
+        assert_eq!(path.nodes, vec![a]);
+        assert!(path.edges.is_empty());
+        assert_eq!(path.cost, 0.0);
+    }
+}
+
+#[test]
+fn bellman_ford_allows_negative_weights() {
+    let (mut g, [a, b, c, d]) = diamond(GraphKind::Directed);
+    g.new_edge(c, b).with_property("weight", -3).finalize();
+    let path = g.bellman_ford_path(a, d, "weight").unwrap().unwrap();
+    assert_eq!(path.nodes, vec![a, c, b, d]);
+    assert_eq!(path.cost, -1.0);
+    assert_eq!(g.bellman_ford_path(d, a, "weight").unwrap(), None);
+
+    let (g, [a, _, _, d]) = diamond(GraphKind::Directed);
+    assert_eq!(g.bellman_ford_path(a, d, "weight").unwrap(), g.shortest_path(a, d, "weight"));
+}
+
+#[test]
+fn bellman_ford_reports_negative_cycles() {
+    let mut g = Graph::new();
+    let [a, b, c, d, e] = ["a", "b", "c", "d", "e"].map(|name| g.new_node(name).finalize());
+    g.new_edge(a, b).with_property("weight", 1).finalize();
+    let bc = g.new_edge(b, c).with_property("weight", 2).finalize();
+    let cd = g.new_edge(c, d).with_property("weight", -4).finalize();
+    let db = g.new_edge(d, b).with_property("weight", 1).finalize();
+    g.new_edge(d, e).finalize();
+
+    let Err(GraphError::NegativeCycle(cycle)) = g.bellman_ford_path(a, e, "weight") else { panic!("expected a negative cycle") };
+    assert!(is_cycle(&cycle), "{:?}", cycle);
+    let mut ids: Vec<_> = cycle.iter().map(Edge::id).collect();
+    ids.sort();
+    let mut expected = vec![bc.id(), cd.id(), db.id()];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    // A negative cycle which can't be reached doesn't matter
+    assert_eq!(g.bellman_ford_path(e, e, "weight").unwrap().unwrap().cost, 0.0);
+}
+
+#[test]
+fn undirected_negative_edges_are_negative_cycles() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected);
+    let [a, b, c] = ["a", "b", "c"].map(|name| g.new_node(name).finalize());
+    g.new_edge(a, b).with_property("weight", 2).finalize();
+    let negative = g.new_edge(b, c).with_property("weight", -1).finalize();
+
+    let Err(GraphError::NegativeCycle(cycle)) = g.bellman_ford_path(a, c, "weight") else { panic!("expected a negative cycle") };
+    assert!(!cycle.is_empty());
+    assert!(cycle.iter().all(|edge| *edge == negative));
+}
@@ -0,0 +1,51 @@
+use graph::{Graph, Properties, Value};
+
+#[test]
+fn properties_keep_their_insertion_order() {
+    let mut properties = Properties::new();
+    properties.set("shape", "box");
+    properties.set("color", "red");
+    properties.set("width", 2);
+    // Overwriting a property keeps its position, and removing one keeps the rest in order
+    properties.set("shape", "circle");
+    properties.remove("color");
+    properties.set("color", "blue");
+
+    let keys: Vec<&String> = properties.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["shape", "width", "color"]);
+    assert_eq!(properties.to_string(), "[shape=\"circle\", width=2, color=\"blue\"]");
+    assert_eq!(Properties::new().to_string(), "[]");
+}
+
+#[test]
+fn equality_ignores_order() {
+    let mut first = Properties::new();
+    first.set("a", 1);
+    first.set("b", 2);
+    let mut second = Properties::new();
+    second.set("b", 2);
+    second.set("a", 1);
+    assert_eq!(first, second);
+    second.set("a", Value::from("1"));
+    assert_ne!(first, second);
+}
+
+#[test]
+fn dot_output_is_reproducible() {
+    let build = || {
+        let mut g = Graph::new().with_property("rankdir", "LR").with_property("bgcolor", "white");
+        let a = g.new_node("a").with_property("z", 1).with_property("y", 2).with_property("x", 3).finalize();
+        let b = g.new_node("b").finalize();
+        g.new_edge(a, b).with_property("label", "ab").with_property("color", "gray").finalize();
+        g.new_record("r", 1, 1).insert(0, 0, "field").with_property("style", "filled").with_property("fillcolor", "red").finalize();
+        (g.to_dot(), a)
+    };
+    let (dot, a) = build();
+    assert!(dot.contains("graph [rankdir=\"LR\", bgcolor=\"white\"];"), "{}", dot);
+    assert!(dot.contains(&format!("{} [z=1, y=2, x=3];", a)), "{}", dot);
+    assert!(dot.contains("[label=\"ab\", color=\"gray\"];"), "{}", dot);
+    assert!(dot.contains("[style=\"filled\", fillcolor=\"red\"];"), "{}", dot);
+    for _ in 0..10 {
+        assert_eq!(build().0, dot);
+    }
+}
@@ -0,0 +1,103 @@
+#![cfg(unix)]
+
+use std::{env, fs, os::unix::fs::PermissionsExt, path::{Path, PathBuf}, sync::Mutex};
+use graph::{Graph, GraphError, LayoutEngine, OutputFormat};
+
+// Every test swaps the `PATH` Graphviz is looked up on, so they can't run at the same time
+static PATH_LOCK: Mutex<()> = Mutex::new(());
+
+/// Make an empty directory for one test.
+fn directory(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("graph-render-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write a shell script standing in for a Graphviz program.
+fn fake_program(dir: &Path, name: &str, script: &str) {
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Run `f` with only `dir` on the `PATH`.
+fn with_path<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let _lock = PATH_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let old = env::var_os("PATH");
+    env::set_var("PATH", dir);
+    let result = f();
+    match old {
+        Some(old) => env::set_var("PATH", old),
+        None => env::remove_var("PATH"),
+    }
+    result
+}
+
+fn graph() -> Graph {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    g.new_edge(a, b).finalize();
+    g
+}
+
+#[test]
+fn missing_graphviz_is_reported() {
+    let dir = directory("missing");
+    let result = with_path(&dir, || graph().save_svg(&dir.join("out.svg")));
+    match result {
+        Err(GraphError::GraphvizNotFound(program)) => assert_eq!(program, "dot"),
+        other => panic!("expected GraphvizNotFound, got {:?}", other),
+    }
+    let result = with_path(&dir, || graph().build_circuit_diagram(&dir.join("out.svg")));
+    assert!(matches!(result, Err(GraphError::GraphvizNotFound(program)) if program == "neato"));
+}
+
+#[test]
+fn failed_runs_capture_stderr() {
+    let dir = directory("failed");
+    fake_program(&dir, "dot", "echo 'syntax error in line 1' >&2\nexit 3");
+    let error = with_path(&dir, || graph().save_png(&dir.join("out.png"))).unwrap_err();
+    match &error {
+        GraphError::GraphvizFailed { program, status, stderr } => {
+            assert_eq!(program, "dot");
+            assert_eq!(*status, Some(3));
+            assert_eq!(stderr.trim(), "syntax error in line 1");
+        }
+        other => panic!("expected GraphvizFailed, got {:?}", other),
+    }
+    assert_eq!(error.to_string(), "`dot` exited with status 3: syntax error in line 1");
+}
+
+#[test]
+fn dot_is_piped_to_the_engine_and_its_output_returned() {
+    let dir = directory("piped");
+    // Print the arguments, then echo the DOT read from stdin
+    fake_program(&dir, "dot", "echo \"$@\"\n/bin/cat");
+    fake_program(&dir, "circo", "echo circo");
+    let g = graph();
+    let output = with_path(&dir, || g.render(LayoutEngine::Dot, OutputFormat::Pdf)).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), format!("-Tpdf\n{}", g.to_dot()));
+    let output = with_path(&dir, || g.render(LayoutEngine::Circo, OutputFormat::Json)).unwrap();
+    assert_eq!(output, b"circo\n");
+
+    let result = with_path(&dir, || g.render(LayoutEngine::Patchwork, OutputFormat::Svg));
+    assert!(matches!(result, Err(GraphError::GraphvizNotFound(program)) if program == "patchwork"));
+}
+
+#[test]
+fn saving_writes_only_the_output() {
+    let dir = directory("save");
+    fake_program(&dir, "dot", "/bin/cat");
+    let output = dir.join("out.svg");
+    with_path(&dir, || graph().save_svg(&output)).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), graph().to_dot());
+    // No `.dot` file is left next to the output
+    let mut files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    files.sort();
+    assert_eq!(files, ["dot", "out.svg"]);
+
+    let result = with_path(&dir, || graph().save_svg(&dir.join("missing").join("out.svg")));
+    assert!(matches!(result, Err(GraphError::Io(_))), "{:?}", result);
+}
@@ -0,0 +1,97 @@
+use graph::{Graph, GraphKind, Value};
+
+fn round_trip(graph: &Graph) -> Graph {
+    serde_json::from_str(&serde_json::to_string(graph).unwrap()).unwrap()
+}
+
+#[test]
+fn values_keep_their_types() {
+    let mut g = Graph::new().with_kind(GraphKind::Undirected).with_strict(true);
+    g.node_defaults_mut().set("label", Value::html("<b>bold</b>"));
+    let a = g.new_node("a")
+        .with_property("fixed", Value::Bool(true))
+        .with_property("rank", 2)
+        .with_property("width", 1.5)
+        .with_property("far", f64::INFINITY)
+        .with_property("near", f64::NEG_INFINITY)
+        .with_property("missing", Value::Nothing)
+        .finalize();
+
+    let json = serde_json::to_value(&g).unwrap();
+    let node = &json["items"][0]["properties"];
+    assert_eq!(json["node_defaults"]["label"], serde_json::json!({"html": "<b>bold</b>"}));
+    assert_eq!(node["far"], serde_json::json!({"f64": "inf"}));
+    assert_eq!(node["rank"], serde_json::json!(2));
+
+    let parsed = round_trip(&g);
+    assert_eq!(parsed, g);
+    assert_eq!(parsed.node_defaults().get("label"), Some(&Value::Html("<b>bold</b>".to_string())));
+    assert_eq!(parsed.get_node_properties(a).unwrap().get("near"), Some(&Value::F64(f64::NEG_INFINITY)));
+    assert_eq!(parsed.get_node_properties(a).unwrap().get("missing"), Some(&Value::Nothing));
+}
+
+#[test]
+fn nan_survives_a_round_trip() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("weight", f64::NAN).finalize();
+    let parsed = round_trip(&g);
+    assert!(matches!(parsed.get_node_properties(a).unwrap().get("weight"), Some(Value::F64(n)) if n.is_nan()));
+}
+
+#[test]
+fn records_and_nested_clusters_round_trip() {
+    let mut g = Graph::new().with_property("rankdir", "LR");
+    let outer = g.new_cluster("outer").with_property("label", "Outer").finalize();
+    let mut builder = g.new_subgraph("inner");
+    let inner = builder.id();
+    builder = builder.in_subgraph(outer.id());
+    builder.finalize();
+    g.get_subgraph_node_defaults_mut(inner).unwrap().set("shape", "box");
+    let record = g.new_record("r", 2, 0).insert(0, 0, "in").insert(1, 0, "out").in_subgraph(inner).finalize();
+    let a = g.new_node("a").in_subgraph(outer.id()).finalize();
+    g.new_edge(record[1][0], a).in_subgraph(outer.id()).with_property("label", "to a").finalize();
+
+    let parsed = round_trip(&g);
+    assert_eq!(parsed, g);
+    assert!(parsed.to_dot().contains(&record.to_string()));
+    assert_eq!(parsed.subgraph_of(inner).map(|s| s.id()), Some(outer.id()));
+    assert_eq!(parsed.subgraph_of(record.id()).map(|s| s.id()), Some(inner));
+    assert!(parsed.find_edge(record[1][0], a).is_some());
+    assert_eq!(parsed.to_dot(), g.to_dot());
+}
+
+#[test]
+fn removals_are_not_written() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").finalize();
+    let b = g.new_node("b").finalize();
+    let c = g.new_node("c").finalize();
+    let record = g.new_record("r", 1, 0).insert(0, 0, "field").finalize();
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).finalize();
+    let removed = g.new_edge(record[0][0], c).finalize();
+    g.remove_node(b);
+    g.remove_edge(removed.id());
+
+    let parsed = round_trip(&g);
+    assert_eq!(parsed, g);
+    assert_eq!(parsed.nodes().copied().collect::<Vec<_>>(), vec![a, c]);
+    assert_eq!(parsed.edges().count(), 0);
+    assert!(parsed.get_node_properties(b).is_none());
+    assert_eq!(parsed.degree(c), 0);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut json = serde_json::to_value(Graph::new()).unwrap();
+    json["version"] = serde_json::json!(2);
+    let error = serde_json::from_value::<Graph>(json).unwrap_err().to_string();
+    assert!(error.contains("newer than the supported version"), "{}", error);
+
+    let mut g = Graph::new();
+    g.new_node("a").finalize();
+    let mut json = serde_json::to_value(&g).unwrap();
+    assert!(serde_json::from_value::<Graph>(json.clone()).is_ok());
+    json["items"][0]["properties"]["label"] = serde_json::json!({"neither": 1});
+    assert!(serde_json::from_value::<Graph>(json).is_err());
+}
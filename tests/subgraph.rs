@@ -0,0 +1,73 @@
+use graph::{Graph, Subgraph, ID};
+
+#[test]
+fn nested_subgraphs_are_written_inside_their_parents() {
+    let mut g = Graph::new();
+    let mut outer = g.new_cluster("outer").with_property("label", "Outer");
+    let a = outer.new_node("a").finalize();
+    let mut inner = outer.new_subgraph("inner").with_property("rank", "same");
+    let b = inner.new_node("b").finalize();
+    let c = inner.new_node("c").finalize();
+    let inner = inner.finalize();
+    let outer = outer.finalize();
+    let d = g.new_node("d").finalize();
+    g.new_edge(b, c).in_subgraph(inner).finalize();
+    g.new_edge(a, d).finalize();
+
+    assert_eq!(g.subgraph_of(inner), Some(outer));
+    assert_eq!(g.subgraph_of(a), Some(outer));
+    assert_eq!(g.subgraph_of(b), Some(inner));
+    assert_eq!(g.subgraph_of(d), None);
+    assert!(outer.is_cluster() && !inner.is_cluster());
+
+    let expected = format!(
+        "digraph {{
+  graph [];
+  {d} [];
+  subgraph {outer} {{
+    graph [label=\"Outer\"];
+    {a} [];
+    subgraph {inner} {{
+      graph [rank=\"same\"];
+      {b} [];
+      {c} [];
+      {b} -> {c} [];
+    }}
+  }}
+  {a} -> {d} [];
+}}
+"
+    );
+    assert_eq!(g.to_dot(), expected);
+}
+
+#[test]
+fn clusters_and_subgraphs_are_named_by_kind() {
+    assert_eq!(Subgraph::cluster("x").name(), format!("cluster_{}", ID::from("x")));
+    assert_eq!(Subgraph::new("x").name(), format!("Subgraph_{}", ID::from("x")));
+
+    let mut g = Graph::new();
+    g.new_cluster("x").finalize();
+    g.new_subgraph("y").finalize();
+    let dot = g.to_dot();
+    assert!(dot.contains(&format!("  subgraph cluster_{} {{\n", ID::from("x"))), "{}", dot);
+    assert!(dot.contains(&format!("  subgraph Subgraph_{} {{\n", ID::from("y"))), "{}", dot);
+}
+
+#[test]
+fn items_move_between_subgraphs() {
+    let mut g = Graph::new();
+    let first = g.new_cluster("first").finalize();
+    let second = g.new_cluster("second").finalize();
+    let a = g.new_node("a").in_subgraph(first).finalize();
+    let r = g.new_record("r", 1, 1).insert(0, 0, "field").in_subgraph(first).finalize();
+    g.move_to_subgraph(a, Some(second.id()));
+    g.move_to_subgraph(&r, None);
+    assert_eq!(g.subgraph_of(a), Some(second));
+    assert_eq!(g.subgraph_of(&r), None);
+
+    // Subgraphs left empty are still written
+    let dot = g.to_dot();
+    assert!(dot.contains(&format!("  subgraph {} {{\n    graph [];\n  }}\n", first)), "{}", dot);
+    assert!(dot.contains(&format!("  subgraph {} {{\n    graph [];\n    {} [];\n  }}\n", second, a)), "{}", dot);
+}
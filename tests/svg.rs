@@ -0,0 +1,63 @@
+use graph::{EdgeRoute, Graph, Layout, Point, Rect};
+
+#[test]
+fn items_are_drawn_at_their_layout_positions() {
+    let mut g = Graph::new();
+    let a = g.new_node("a").with_property("label", "A & B").with_property("shape", "box").with_property("style", "filled").with_property("fillcolor", "red").finalize();
+    let b = g.new_node("b").with_property("shape", "circle").finalize();
+    let edge = g.new_edge(a, b).with_property("label", "ab").finalize();
+
+    let mut layout = Layout::new(200.0, 100.0);
+    layout.set_node(a, Rect::new(Point::new(50.0, 20.0), 60.0, 30.0));
+    layout.set_node(b, Rect::new(Point::new(50.0, 80.0), 30.0, 30.0));
+    layout.set_edge(edge, EdgeRoute { points: vec![Point::new(50.0, 35.0), Point::new(50.0, 65.0)], label: Some(Point::new(56.0, 50.0)), ..Default::default() });
+
+    let svg = g.to_svg(&layout);
+    assert!(svg.starts_with("<?xml"), "{}", svg);
+    assert!(svg.contains(r#"width="200pt" height="100pt" viewBox="0.00 0.00 200.00 100.00""#), "{}", svg);
+    assert!(svg.contains(&format!(r#"<g id="{}" class="node">"#, a.name())), "{}", svg);
+    assert!(svg.contains(r#"<rect x="20.00" y="5.00" width="60.00" height="30.00" fill="red" stroke="black"/>"#), "{}", svg);
+    assert!(svg.contains(">A &amp; B</text>"), "{}", svg);
+    assert!(svg.contains(r#"<ellipse cx="50.00" cy="80.00" rx="15.00" ry="15.00""#), "{}", svg);
+    assert!(svg.contains(&format!(r#"<g id="Edge_{}" class="edge">"#, edge.id())), "{}", svg);
+    assert!(svg.contains(&format!("<title>{}-&gt;{}</title>", a, b)), "{}", svg);
+    assert!(svg.contains(r#"<path d="M50.00,35.00"#), "{}", svg);
+    assert!(svg.contains(r#"<polygon points="50.00,65.00 46.50,55.00 53.50,55.00" fill="black""#), "{}", svg);
+    assert!(svg.contains(r#"<text text-anchor="middle" x="56.00" y="54.20"#), "{}", svg);
+    assert!(svg.ends_with("</g>\n</svg>\n"), "{}", svg);
+}
+
+#[test]
+fn ids_classes_and_invisible_items() {
+    let mut g = Graph::new().with_property("stylesheet", "style.css");
+    g.new_node("a").with_property("id", "first").with_property("class", "important").finalize();
+    let hidden = g.new_node("hidden").with_property("style", "invis").finalize();
+    let mut cluster = g.new_cluster("c").with_property("label", "Cluster");
+    cluster.new_node("b").finalize();
+    let cluster = cluster.finalize();
+
+    let svg = g.render_svg();
+    assert!(svg.contains(r#"<?xml-stylesheet href="style.css" type="text/css"?>"#), "{}", svg);
+    assert!(svg.contains(r#"<g id="first" class="node important">"#), "{}", svg);
+    assert!(!svg.contains(&hidden.name()), "{}", svg);
+    assert!(svg.contains(&format!(r#"<g id="{}" class="cluster">"#, cluster.name())), "{}", svg);
+    assert!(svg.contains(">Cluster</text>"), "{}", svg);
+}
+
+#[test]
+fn record_fields_get_their_own_groups() {
+    let mut g = Graph::new();
+    let first = g.new_record("first", 2, 1).insert(0, 0, "left").insert(1, 0, "right").finalize();
+    let second = g.new_record("second", 2, 1).insert(0, 0, "left").insert(1, 0, "right").finalize();
+
+    let svg = g.render_svg();
+    for record in [&first, &second] {
+        assert!(svg.contains(&format!(r#"<g id="{}" class="node record">"#, record.name())), "{}", svg);
+        for column in record.nodes().columns() {
+            for (node, _) in column.cells() {
+                assert_eq!(svg.matches(&format!(r#"<g id="{}" class="field">"#, node.name())).count(), 1, "{}", svg);
+            }
+        }
+    }
+    assert_eq!(svg.matches(">left</text>").count(), 2, "{}", svg);
+}
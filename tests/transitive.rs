@@ -0,0 +1,65 @@
+use std::thread;
+use graph::{Graph, Node, Value};
+
+fn chain(length: usize) -> (Graph, Vec<Node>) {
+    let mut g = Graph::new();
+    let nodes: Vec<Node> = (0..length).map(|i| g.new_node(format!("n{}", i)).finalize()).collect();
+    for pair in nodes.windows(2) {
+        g.new_edge(pair[0], pair[1]).finalize();
+    }
+    (g, nodes)
+}
+
+#[test]
+fn reduction_and_closure_undo_each_other() {
+    let mut g = Graph::new();
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|name| g.new_node(name).finalize());
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, c).with_property("color", "blue").finalize();
+    g.new_edge(a, c).finalize();
+    g.new_edge(c, d).finalize();
+    g.new_edge(a, d).finalize();
+
+    let reduced = g.transitive_reduction();
+    assert_eq!(reduced.edges().count(), 3);
+    assert!(reduced.find_edge(a, c).is_none() && reduced.find_edge(a, d).is_none());
+    let edge = reduced.find_edge(b, c).unwrap();
+    assert_eq!(reduced.get_edge_properties(edge.id()).unwrap().get("color"), Some(&Value::from("blue")));
+
+    let closure = reduced.transitive_closure();
+    assert_eq!(closure.edges().count(), 6);
+    assert!(closure.find_edge(b, d).is_some());
+    assert_eq!(closure.transitive_reduction().edges().count(), 3);
+}
+
+#[test]
+fn reduction_keeps_one_edge_through_each_cycle() {
+    let mut g = Graph::new();
+    let [a, b, c] = ["a", "b", "c"].map(|name| g.new_node(name).finalize());
+    g.new_edge(a, b).finalize();
+    g.new_edge(b, a).finalize();
+    g.new_edge(a, c).finalize();
+    g.new_edge(b, c).finalize();
+
+    let reduced = g.transitive_reduction();
+    assert!(reduced.find_edge(a, b).is_some() && reduced.find_edge(b, a).is_some());
+    assert_eq!(reduced.edges().count(), 3);
+    assert_eq!(reduced.transitive_closure().edges().count(), g.transitive_closure().edges().count());
+}
+
+#[test]
+fn reduction_runs_on_a_small_stack() {
+    // The reduction keeps a set of reachable components per component, so a shorter chain on a
+    // small stack stands in for a very long one
+    let edges = thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(|| {
+            let (mut g, nodes) = chain(5_000);
+            g.new_edge(nodes[0], nodes[4_999]).finalize();
+            g.transitive_reduction().edges().count()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(edges, 4_999);
+}
@@ -0,0 +1,40 @@
+use graph::{Diagnostic, Graph, Node, Value, ID};
+
+#[test]
+fn problems_are_reported_in_order() {
+    let mut g = Graph::new().with_property("label", Value::Nothing);
+    let record = g.new_record("r", 1, 0).insert(0, 0, "field").finalize();
+    g.new_node("r").finalize();
+    let a = g.new_node("a").finalize();
+    let missing = Node::new(ID::from("missing"));
+    let edge = g.new_edge(a, missing).finalize();
+    let unknown = Node::new(ID::from("nowhere")).with_record(record.id());
+    let port_edge = g.new_edge(a, unknown).finalize();
+
+    let diagnostics = g.validate();
+    assert_eq!(diagnostics, vec![
+        Diagnostic::NothingValue { owner: None, key: "label".to_string() },
+        Diagnostic::DuplicateId { id: record.id() },
+        Diagnostic::DanglingEdge { edge: edge.id(), node: missing },
+        Diagnostic::UnknownRecordPort { edge: port_edge.id(), record: record.id(), port: unknown.id() },
+    ]);
+    assert_eq!(diagnostics[0].to_string(), "graph property `label` has no value");
+    assert_eq!(diagnostics[1].to_string(), format!("Node_{} and Record_{} have the same ID", record.id(), record.id()));
+    assert_eq!(diagnostics[2].to_string(), format!("edge Edge_{} is attached to {}, which is not in the graph", edge.id(), missing));
+}
+
+#[test]
+fn well_formed_graphs_have_no_problems() {
+    let mut g = Graph::new();
+    let record = g.new_record("r", 2, 0).insert(0, 0, "in").insert(1, 0, "out").finalize();
+    let a = g.new_node("a").with_property("label", "A").finalize();
+    let cluster = g.new_cluster("c").with_property("label", "C").finalize();
+    g.move_to_subgraph(a, Some(cluster.id()));
+    g.new_edge(record[1][0], a).finalize();
+    g.new_edge(a, record[0][0]).finalize();
+    assert_eq!(g.validate(), vec![]);
+
+    // Properties without a value are reported with their owner
+    g.get_node_properties_mut(a).unwrap().set("width", Value::Nothing);
+    assert_eq!(g.validate(), vec![Diagnostic::NothingValue { owner: Some(a.id()), key: "width".to_string() }]);
+}